@@ -1,7 +1,23 @@
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
+use crate::{
+    data::{
+        alignment::MappingStats,
+        conversion::ConversionCheck,
+        import::ImportPlan,
+        launch::LaunchPlan,
+        lifecycle::{LifecycleLog, LifecycleStep},
+        manifest::Verification,
+        preset::Preset,
+        project::{BarcodeResults, BasecallResults, BasecallRun, BasecallStatus},
+        reference::Reference,
+        storage::{StorageUsage, VolumeUsage},
+    },
+    notifications::Notification,
+};
+
+#[derive(Debug, Clone, PartialEq, Display, Serialize, Deserialize)]
 pub enum Action {
     Tick,
     Render,
@@ -17,4 +33,106 @@ pub enum Action {
     ArchiveProject,
     ToggleArchivedProjectsDisplay,
     ToggleSettingsDisplay,
+    Notify(Notification),
+    /// Start basecalling a run of the named project on the configured executor
+    StartBasecall(String, BasecallRun),
+    JobProgress(usize, String),
+    JobStatus(usize, BasecallStatus),
+    /// Process id of the command a job is running, for the resource monitor
+    JobProcess(usize, u32),
+    /// Resume the selected interrupted or failed job from its partial output
+    ResumeJob,
+    /// Reads found in the partial output a resumed job started from
+    JobResumed(usize, u64),
+    SelectNext,
+    SelectPrevious,
+    /// Split the selected project's latest basecall by barcode
+    Demultiplex,
+    /// Per-barcode results read back from a finished demux job
+    DemuxResults(usize, Vec<BarcodeResults>),
+    /// Read the MinKNOW sample sheet in the selected project's data directory
+    ImportSampleSheet,
+    /// Write the selected project's samples out as a MinKNOW sample sheet
+    ExportSampleSheet,
+    /// Compute QC for the selected project's finished basecalls that don't have any yet
+    ComputeQc,
+    /// QC for the named project's basecall writing to the given output path
+    QcResults(String, String, BasecallResults),
+    /// Switch the selected project between its details and its run charts
+    ToggleRunCharts,
+    /// Show disk usage for every project and configured volume
+    ToggleStorage,
+    /// Show how fast each machine has basecalled, by model
+    ToggleThroughput,
+    /// Show or hide the CPU, memory, disk and job usage side pane
+    ToggleResourceMonitor,
+    /// Show or hide the frame rate, action queue and render time overlay
+    ToggleDebugOverlay,
+    /// Disk usage measured for each named project, and free space on each volume
+    StorageUsage(Vec<(String, StorageUsage)>, Vec<VolumeUsage>),
+    /// Projects a bulk import would create, shown for confirmation
+    ImportPreview(ImportPlan),
+    ConfirmImport,
+    CancelImport,
+    /// Convert the selected project's FAST5 to POD5, removing the originals once verified if set
+    ConvertToPod5(bool),
+    /// Read counts checked after a conversion job finished
+    ConversionVerified(usize, ConversionCheck),
+    /// Preview what the selected project's lifecycle rules would do to its raw data
+    PreviewLifecycle,
+    /// Files the named project's lifecycle rules would act on, shown for confirmation
+    LifecyclePreview(String, Vec<LifecycleStep>),
+    ConfirmLifecycle,
+    CancelLifecycle,
+    /// Outcome of each lifecycle rule applied to the named project
+    LifecycleApplied(String, Vec<LifecycleLog>),
+    /// Hash the selected project's data into a checksum manifest
+    GenerateManifest,
+    /// Files listed in the named project's new manifest
+    ManifestGenerated(String, usize),
+    /// Re-hash the selected project's data against its manifest
+    VerifyManifest,
+    /// Outcome of checking the named project's data against its manifest
+    ManifestVerified(String, Verification),
+    /// Align the selected project's latest basecall to the configured reference
+    Align,
+    /// Align the named project's latest basecall to a reference, registering it if it's new
+    AlignTo(String, Reference),
+    /// Mapping statistics read back from a finished alignment job
+    MappingStats(usize, MappingStats),
+    /// Show the registered reference genomes
+    ToggleReferences,
+    /// Open the form for registering a reference
+    AddReference,
+    CancelReferenceEntry,
+    /// Hash and register a reference entered in the form
+    RegisterReference(Reference),
+    /// A reference hashed and ready to be added to the registry
+    ReferenceRegistered(Reference),
+    /// Remove the selected reference from the registry
+    RemoveReference,
+    /// Look for every reference's indexes again
+    ValidateReferences,
+    /// Make the selected reference the selected project's default
+    SetProjectReference,
+    /// Pick a preset to basecall the selected project with
+    PickPreset,
+    /// Launch the selected preset when picking one, otherwise edit it
+    ChoosePreset,
+    /// Open the preset form empty, or filled in with the selected preset
+    AddPreset,
+    EditPreset,
+    CancelPresetEntry,
+    /// Save a preset from the form, replacing the named preset it was edited from
+    SavePreset(Option<String>, Preset),
+    /// Delete the selected preset's file
+    RemovePreset,
+    /// A basecall's command and estimates, shown for confirmation before it's launched
+    LaunchPreview(LaunchPlan),
+    ConfirmLaunch,
+    CancelLaunch,
+    /// Copy the previewed basecall's command line to the clipboard
+    CopyLaunchCommand,
+    /// Save the previewed basecall as a shell script instead of running it
+    SaveLaunchScript,
 }
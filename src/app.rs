@@ -1,66 +1,1453 @@
-use color_eyre::Result;
-use crossterm::event::KeyEvent;
+use std::{
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Constraint, Layout},
+    layout::{Constraint, Layout, Position},
     prelude::Rect,
     widgets::Paragraph,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     action::Action,
     components::*,
     config::Config,
+    data::{
+        alignment::{self, AlignLog, MappingStats},
+        bam, barcoding,
+        conversion::{self, ConversionCheck},
+        current_user, duplex, import,
+        launch::LaunchPlan,
+        lifecycle::{self, LifecycleLog},
+        manifest::{Manifest, Verification},
+        modifications, now,
+        preset::Preset,
+        project::{
+            BarcodeResults, BasecallLog, BasecallResults, BasecallRun, BasecallStatus, Basecaller,
+            ConvertLog, DemuxLog, Project, ProjectAction, ProjectHistory, ProjectType,
+            RawBasecallsFormat, RunType,
+        },
+        qc,
+        reference::{Reference, ReferenceRegistry},
+        rna,
+        sample_sheet::{self, SampleSheet},
+        sanitize, storage,
+        throughput::{self, ThroughputTable},
+    },
+    executor::{Executor, Job, JobKind},
+    notifications::{DiskSpaceMonitor, Notifier},
+    resources::ResourceSampler,
     tui::{Event, Tui},
 };
 
+/// Key hints in the footer, with the action run when one is clicked
+const FOOTER_HINTS: &[(&str, Option<Action>)] = &[
+    ("[q] Quit", Some(Action::Quit)),
+    ("[Tab] Next Pane", Some(Action::NextPane)),
+    ("[Up/Down] Navigate", None),
+    ("[Enter] Select", None),
+    ("[`] Configuration", Some(Action::ToggleSettingsDisplay)),
+    ("[h] Keys", Some(Action::Help)),
+];
+
 pub struct App {
     config: Config,
     should_quit: bool,
     should_suspend: bool,
+    tick_rate: f64,
+    frame_rate: f64,
+    /// When a key was last pressed or the mouse used, for the adaptive frame rate
+    last_input: Instant,
+    /// Rendering at the idle frame rate
+    idle: bool,
+    /// Something on screen moves by itself, so the frame rate is kept up
+    animating: bool,
+    /// Where the software, projects and jobs panes were last drawn, in focus order
+    pane_areas: [Rect; 3],
+    /// Row of the footer and the columns and action of each of its key hints
+    footer_hints: (u16, Vec<(Range<u16>, Action)>),
     mode: Mode,
     last_tick_key_events: Vec<KeyEvent>,
     action_tx: mpsc::UnboundedSender<Action>,
     action_rx: mpsc::UnboundedReceiver<Action>,
     software: SoftwareList,
     projects: ProjectList,
+    jobs: JobList,
     has_focus: usize,
     focusable_max: usize,
     main_area: Box<dyn Component>,
+    project_view: ProjectView,
+    import_preview: ImportPreview,
+    lifecycle_preview: LifecyclePreview,
+    run_charts: RunCharts,
+    show_run_charts: bool,
+    storage_view: StorageView,
+    show_storage: bool,
+    /// Whether the full list of project keys replaces the main area
+    show_help: bool,
+    show_throughput: bool,
+    throughput_view: ThroughputView,
+    show_resources: bool,
+    resource_monitor: ResourceMonitor,
+    show_debug: bool,
+    fps: FpsCounter,
+    resource_sampler: ResourceSampler,
+    last_storage_check: Option<Instant>,
+    references: ReferenceRegistry,
+    reference_list: ReferenceList,
+    settings: Settings,
+    launch_preview: LaunchPreview,
+    notifier: Notifier,
+    disk_monitor: DiskSpaceMonitor,
+    executor: Box<dyn Executor>,
+    next_job_id: usize,
 }
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Mode {
     #[default]
     Home,
+    /// Reviewing a bulk import before it is written
+    Import,
+    /// Reviewing what lifecycle rules would do before any files are touched
+    Lifecycle,
+    /// Managing the registered reference genomes
+    References,
+    /// Typing a new reference into the form, keys go to the form rather than keybindings
+    ReferenceEntry,
+    /// Managing basecall presets, or picking one to launch a basecall with
+    Settings,
+    /// Typing into the preset form, keys go to the form rather than keybindings
+    PresetEntry,
+    /// Reviewing a basecall's command and estimates before it's launched
+    Launch,
 }
 
 impl App {
-    pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
+    /// Rates given on the command line override those in the config file
+    pub fn new(tick_rate: Option<f64>, frame_rate: Option<f64>) -> Result<Self> {
+        Self::with_config(Config::new()?, tick_rate, frame_rate)
+    }
+
+    fn with_config(
+        config: Config,
+        tick_rate: Option<f64>,
+        frame_rate: Option<f64>,
+    ) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
-        Ok(Self {
+        let tick_rate = tick_rate.unwrap_or(config.display.tick_rate);
+        let frame_rate = frame_rate.unwrap_or(config.display.frame_rate);
+        // NaN and infinity would make a zero or invalid interval
+        if [tick_rate, frame_rate, config.display.idle_frame_rate]
+            .iter()
+            .any(|rate| !(rate.is_finite() && *rate > 0.0))
+        {
+            return Err(eyre!("Tick and frame rates must be finite and above zero"));
+        }
+        let references =
+            ReferenceRegistry::load(&ReferenceRegistry::path_in(&config.config.data_dir))?;
+        let mut app = Self {
             should_quit: false,
             should_suspend: false,
-            config: Config::new()?,
+            tick_rate,
+            frame_rate,
+            last_input: Instant::now(),
+            idle: false,
+            animating: false,
+            pane_areas: [Rect::default(); 3],
+            footer_hints: (0, Vec::new()),
+            notifier: Notifier::new(&config.notifications),
+            disk_monitor: DiskSpaceMonitor::new(&config.notifications),
+            executor: config.execution.executor()?,
+            resource_sampler: ResourceSampler::new(&config.monitor, config.storage.volumes.clone()),
+            next_job_id: 0,
+            config,
             mode: Mode::Home,
             last_tick_key_events: Vec::new(),
             action_tx,
             action_rx,
             software: SoftwareList::default(),
             projects: ProjectList::default(),
+            jobs: JobList::default(),
             has_focus: 0,
-            focusable_max: 2, // Software and Projects
+            focusable_max: 3, // Software, Projects and Jobs
             main_area: Box::new(Fishtank::default()),
-        })
+            project_view: ProjectView::new(),
+            import_preview: ImportPreview::new(),
+            lifecycle_preview: LifecyclePreview::new(),
+            run_charts: RunCharts::new(),
+            show_run_charts: false,
+            storage_view: StorageView::new(),
+            show_storage: false,
+            show_help: false,
+            show_throughput: false,
+            throughput_view: ThroughputView::new(),
+            show_resources: false,
+            resource_monitor: ResourceMonitor::new(),
+            show_debug: false,
+            fps: FpsCounter::new(),
+            last_storage_check: None,
+            reference_list: ReferenceList::new(),
+            references,
+            settings: Settings::new(),
+            launch_preview: LaunchPreview::new(),
+        };
+        app.reference_list
+            .set_references(app.references.references.clone());
+        app.resource_monitor.capacity = app.config.monitor.history_len();
+        app.jobs.window = app
+            .config
+            .execution
+            .runtime_window_hours
+            .map(|hours| (hours * 3600.0) as u64);
+        app.load_projects()?;
+        Ok(app)
+    }
+
+    fn projects_dir(&self) -> PathBuf {
+        self.config.config.data_dir.join("projects")
+    }
+
+    /// Load project records and list any unfinished basecalls they contain as resumable jobs
+    fn load_projects(&mut self) -> Result<()> {
+        self.projects.items = Project::load_all(&self.projects_dir())?;
+        let mut interrupted = Vec::new();
+        for project in &self.projects.items {
+            for log in project.basecall_logs().filter(|log| log.is_resumable()) {
+                interrupted.push((project.name.clone(), log.basecall_run.clone(), log.status));
+            }
+        }
+        for (project, run, status) in interrupted {
+            let job = Job {
+                id: self.next_job_id(),
+                project,
+                run,
+                resume_from: None,
+                kind: JobKind::Basecall,
+            };
+            self.jobs.add_interrupted(job, status);
+        }
+        Ok(())
+    }
+
+    fn save_project(&self, name: &str) -> Result<()> {
+        let project = self
+            .projects
+            .items
+            .iter()
+            .find(|project| project.name == name)
+            .ok_or_else(|| eyre!("No project named {name}"))?;
+        project.save(&self.projects_dir())
+    }
+
+    fn next_job_id(&mut self) -> usize {
+        self.next_job_id += 1;
+        self.next_job_id
+    }
+
+    fn start_job(&mut self, job: Job) {
+        info!("Starting job {} on {}", job.id, self.executor.name());
+        let estimate = self.estimate_runtime(&job);
+        self.jobs.add(job.clone(), self.executor.name(), estimate);
+        self.executor.spawn(job, self.action_tx.clone());
+    }
+
+    /// Predicted wall-clock seconds of a basecall job, from the executor's earlier runs
+    fn estimate_runtime(&self, job: &Job) -> Option<u64> {
+        if job.kind != JobKind::Basecall {
+            return None;
+        }
+        let log = self
+            .projects
+            .get(&job.project)?
+            .basecall_logs()
+            .filter(|log| log.basecall_run.output_path == job.run.output_path)
+            .last()?;
+        ThroughputTable::from_projects(&self.projects.items).estimate(
+            self.executor.name(),
+            &job.run.basecaller.model,
+            log.input_bytes,
+        )
+    }
+
+    /// Keep the rate dorado reports when a basecall finishes, for the throughput table
+    fn record_samples_rate(&mut self, id: usize, line: &str) {
+        let Some(rate) = throughput::parse_samples_per_second(line) else {
+            return;
+        };
+        let Some(job) = self.jobs.get(id).map(|entry| entry.job.clone()) else {
+            return;
+        };
+        if let Some(log) = self
+            .projects
+            .get_mut(&job.project)
+            .and_then(|project| project.basecall_log_mut(&job.run.output_path))
+            .filter(|_| job.kind == JobKind::Basecall)
+        {
+            // Saved with the job's status, which dorado's closing lines come just before
+            log.samples_per_second = Some(rate);
+        }
+    }
+
+    /// Give RNA runs asking for a model by speed the one for their chemistry, and check the
+    /// run's options work together
+    fn prepare_run(&self, project: &str, mut run: BasecallRun) -> Result<BasecallRun> {
+        if let Some(ProjectType::RNA(info)) = self.projects.get(project).map(|p| &p.project_type) {
+            if let Some(model) = info.model(&run.basecaller.model) {
+                run.basecaller.model = model;
+            }
+            run.estimate_poly_a |= info.poly_a;
+        }
+        if run.run_type == RunType::Duplex && run.kit_name.is_some() {
+            return Err(eyre!(
+                "dorado duplex can't classify barcodes, demultiplex the output instead"
+            ));
+        }
+        Ok(run)
+    }
+
+    /// Record a new basecall in the project's history and start it
+    fn start_basecall(&mut self, project: String, run: BasecallRun) -> Result<()> {
+        let run = self.prepare_run(&project, run)?;
+        let input_bytes = storage::path_size(Path::new(&run.path));
+        let job = Job {
+            id: self.next_job_id(),
+            project,
+            run,
+            resume_from: None,
+            kind: JobKind::Basecall,
+        };
+        let host = self.executor.name().to_string();
+        let hardware = self.config.execution.hardware.clone().unwrap_or_default();
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        project.history.push(ProjectHistory {
+            action: ProjectAction::Basecall(BasecallLog {
+                host,
+                hardware,
+                input_bytes: *input_bytes.as_ref().unwrap_or(&0),
+                ..BasecallLog::new(&job.run, job.command_line())
+            }),
+            created_by: current_user(),
+            created_at: now(),
+        });
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+        self.start_job(job);
+        Ok(())
+    }
+
+    /// Mark the resumed basecall as running again, with the command that resumes it
+    fn record_resume(&mut self, job: &Job) -> Result<()> {
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        let log = project
+            .basecall_log_mut(&job.run.output_path)
+            .ok_or_else(|| eyre!("No basecall writing to {}", job.run.output_path))?;
+        log.command.push(job.command_line());
+        log.status = BasecallStatus::InProgress;
+        project.last_updated = now();
+        self.save_project(&job.project)
+    }
+
+    /// Restart the selected unfinished job with `--resume-from` its partial output
+    fn resume_job(&mut self) -> Result<()> {
+        let Some(entry) = self.jobs.selected_entry() else {
+            return Ok(());
+        };
+        if entry.active || entry.status == BasecallStatus::Success {
+            return Err(eyre!("Only stopped or failed jobs can be resumed"));
+        }
+        if entry.job.kind != JobKind::Basecall {
+            return Err(eyre!("Only basecalls can be resumed"));
+        }
+        if entry.job.run.run_type == RunType::Duplex {
+            return Err(eyre!("dorado duplex can't resume from a partial output"));
+        }
+        let previous = entry.job.clone();
+        let output = PathBuf::from(&previous.run.output_path);
+        if !output.exists() {
+            let executor = self.executor.name();
+            if executor == "local" {
+                return Err(eyre!("No partial output at {}", output.display()));
+            }
+            // Resuming reads the partial output locally, so it must be on a shared filesystem
+            return Err(eyre!(
+                "No partial output at {} on this machine, {executor} jobs can only be resumed \
+                 when their output is on a filesystem shared with it",
+                output.display()
+            ));
+        }
+
+        // The shell truncates the output before dorado starts, so move the partial out of the way
+        let partial = partial_output_path(&output);
+        let job = Job {
+            id: self.next_job_id(),
+            project: previous.project.clone(),
+            run: previous.run.clone(),
+            resume_from: Some(partial.display().to_string()),
+            kind: JobKind::Basecall,
+        };
+        std::fs::rename(&output, &partial)?;
+        // Put the partial output back if the resume can't be recorded, as no job will use it
+        if let Err(e) = self.record_resume(&job) {
+            std::fs::rename(&partial, &output)?;
+            return Err(e);
+        }
+
+        // The partial output can be large, so count what's already done in the background
+        let (id, tx) = (job.id, self.action_tx.clone());
+        tokio::task::spawn_blocking(move || match bam::count_reads(&partial) {
+            Ok(reads) => {
+                let _ = tx.send(Action::JobResumed(id, reads));
+            }
+            Err(e) => warn!("Unable to count reads in {}: {e}", partial.display()),
+        });
+
+        self.jobs.remove(previous.id);
+        self.start_job(job);
+        Ok(())
+    }
+
+    /// Demultiplex the selected project's latest successful basecall with its barcode kit
+    fn start_demux(&mut self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to demultiplex"))?;
+        let barcoding = project
+            .barcoding
+            .clone()
+            .ok_or_else(|| eyre!("{} has no barcode kit", project.name))?;
+        barcoding.validate()?;
+        let run = project
+            .basecall_logs()
+            .filter(|log| log.status == BasecallStatus::Success)
+            .last()
+            .map(|log| log.basecall_run.clone())
+            .ok_or_else(|| eyre!("{} has no finished basecall", project.name))?;
+        let name = project.name.clone();
+
+        // Reads are already classified when the basecaller was given the kit
+        let kit = match run.kit_name {
+            Some(_) => None,
+            None => Some(barcoding.kit.clone()),
+        };
+        let output_dir = Path::new(&run.output_path)
+            .with_extension("demux")
+            .display()
+            .to_string();
+        let job = Job {
+            id: self.next_job_id(),
+            project: name,
+            run,
+            resume_from: None,
+            kind: JobKind::Demux {
+                kit,
+                output_dir: output_dir.clone(),
+            },
+        };
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        project.history.push(ProjectHistory {
+            action: ProjectAction::Demux(DemuxLog {
+                status: BasecallStatus::InProgress,
+                input: job.input().to_string(),
+                output_dir,
+                kit: barcoding.kit,
+                command: vec![job.command_line()],
+                barcodes: Vec::new(),
+            }),
+            created_by: current_user(),
+            created_at: now(),
+        });
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+        self.start_job(job);
+        Ok(())
+    }
+
+    /// Convert the selected project's FAST5 to POD5 next to the original files
+    fn start_conversion(&mut self, remove_originals: bool) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to convert"))?;
+        if project.format != RawBasecallsFormat::Fast5 {
+            return Err(eyre!("{} is not FAST5", project.name));
+        }
+        let input = conversion::fast5_input(project)
+            .ok_or_else(|| eyre!("No FAST5 found at {}", project.data_location))?;
+        let output_dir = if input == Path::new(&project.data_location) {
+            input.join("pod5")
+        } else {
+            conversion::output_dir(&input)
+        };
+        let run = BasecallRun {
+            name: sanitize(&project.name),
+            path: input.display().to_string(),
+            basecaller: Basecaller {
+                name: "pod5".to_string(),
+                path: self.config.execution.pod5.clone().unwrap_or_default(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let name = project.name.clone();
+        let job = Job {
+            id: self.next_job_id(),
+            project: name,
+            run,
+            resume_from: None,
+            kind: JobKind::Convert {
+                output_dir: output_dir.display().to_string(),
+                remove_originals,
+            },
+        };
+
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        project.history.push(ProjectHistory {
+            action: ProjectAction::Convert(ConvertLog {
+                status: BasecallStatus::InProgress,
+                input: job.input().to_string(),
+                output_dir: job.output().to_string(),
+                command: vec![job.command_line()],
+                check: None,
+            }),
+            created_by: current_user(),
+            created_at: now(),
+        });
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+        self.start_job(job);
+        Ok(())
+    }
+
+    /// Align the selected project's latest successful basecall to its default reference, or the
+    /// configured one, hashing and registering a new FASTA in the background first
+    fn align(&mut self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to align"))?;
+        if let Some(name) = &project.reference {
+            let reference = self
+                .references
+                .find(name)
+                .ok_or_else(|| eyre!("No registered reference named {name}"))?
+                .clone();
+            return self.start_alignment(project.name.clone(), reference);
+        }
+        let reference = self.config.alignment.reference.clone().ok_or_else(|| {
+            eyre!("Set a default reference for the project, or alignment.reference")
+        })?;
+        let name = project.name.clone();
+        if let Some(reference) = self.references.find(&reference) {
+            return self.start_alignment(name, reference.clone());
+        }
+        let tx = self.action_tx.clone();
+        info!("Registering reference {reference}");
+        tokio::task::spawn_blocking(move || {
+            let action = match Reference::from_path(Path::new(&reference)) {
+                Ok(reference) => Action::AlignTo(name, reference),
+                Err(e) => Action::Error(format!("Unable to register {reference}: {e}")),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    fn start_alignment(&mut self, project: String, reference: Reference) -> Result<()> {
+        if self.references.find(&reference.name) != Some(&reference) {
+            self.references.register(reference.clone());
+            self.save_references()?;
+        }
+        let run = self
+            .projects
+            .get_mut(&project)
+            .ok_or_else(|| eyre!("No project named {project}"))?
+            .basecall_logs()
+            .filter(|log| log.status == BasecallStatus::Success)
+            .last()
+            .map(|log| log.basecall_run.clone())
+            .ok_or_else(|| eyre!("{project} has no finished basecall"))?;
+        // minimap2 loads a prebuilt index much faster than it indexes the FASTA
+        let minimap2 = self.config.alignment.minimap2();
+        let target = match minimap2 {
+            Some(_) => reference.index("mmi").unwrap_or(&reference.path),
+            None => &reference.path,
+        };
+        let job = Job {
+            id: self.next_job_id(),
+            project,
+            kind: JobKind::Align {
+                reference: target.to_string(),
+                output_path: alignment::output_path(&run.output_path, &reference.name),
+                minimap2,
+            },
+            run,
+            resume_from: None,
+        };
+
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        project.history.push(ProjectHistory {
+            action: ProjectAction::Align(AlignLog {
+                status: BasecallStatus::InProgress,
+                aligner: if job.runs_dorado() {
+                    "dorado"
+                } else {
+                    "minimap2"
+                }
+                .to_string(),
+                reference,
+                input: job.input().to_string(),
+                output_path: job.output().to_string(),
+                command: vec![job.command_line()],
+            }),
+            created_by: current_user(),
+            created_at: now(),
+        });
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+        self.start_job(job);
+        Ok(())
+    }
+
+    fn save_references(&mut self) -> Result<()> {
+        self.reference_list
+            .set_references(self.references.references.clone());
+        self.references
+            .save(&ReferenceRegistry::path_in(&self.config.config.data_dir))
+    }
+
+    fn presets_dir(&self) -> PathBuf {
+        Preset::dir_in(&self.config.config.config_dir)
+    }
+
+    /// Re-read the presets, picking up any preset files shared since they were last shown
+    fn load_presets(&mut self) -> Result<()> {
+        let presets = Preset::merged(&self.config.presets, &self.presets_dir())?;
+        self.settings.set_presets(presets);
+        Ok(())
+    }
+
+    fn pick_preset(&mut self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to basecall"))?;
+        self.settings.launching = Some(project.name.clone());
+        self.mode = Mode::Settings;
+        self.load_presets()
+    }
+
+    /// Plan a basecall of the project being launched with the selected preset
+    fn launch_preset(&mut self) -> Result<()> {
+        let preset = self
+            .settings
+            .selected_preset()
+            .cloned()
+            .ok_or_else(|| eyre!("Select a preset to basecall with"))?;
+        let name = self.settings.launching.take().unwrap_or_default();
+        self.mode = Mode::Home;
+        let project = self
+            .projects
+            .get(&name)
+            .ok_or_else(|| eyre!("No project named {name}"))?;
+        let run = preset.run(project);
+        self.preview_launch(name, run)
+    }
+
+    /// Measure a basecall's input in the background and show it, with the free space its
+    /// output needs, for confirmation before anything runs
+    fn preview_launch(&mut self, name: String, run: BasecallRun) -> Result<()> {
+        let run = self.prepare_run(&name, run)?;
+        let job = Job {
+            id: 0,
+            project: name.clone(),
+            run,
+            resume_from: None,
+            kind: JobKind::Basecall,
+        };
+        let command = job.command_line();
+        let host = self.executor.name().to_string();
+        let ratio = self.config.storage.basecall_output_ratio;
+        let projects = self.projects.items.clone();
+        let tx = self.action_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let action = match LaunchPlan::new(name, job.run, command, host, ratio, &projects) {
+                Ok(plan) => Action::LaunchPreview(plan),
+                Err(e) => Action::Error(format!("Unable to read the run's input: {e}")),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    /// Start the previewed basecall
+    fn confirm_launch(&mut self) -> Result<()> {
+        let Some(plan) = self.launch_preview.plan.take() else {
+            return Ok(());
+        };
+        if let Some(parent) = Path::new(&plan.run.output_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        info!(
+            "Basecalling {} with preset {}",
+            plan.project, plan.run.basecaller.config
+        );
+        self.start_basecall(plan.project, plan.run)
+    }
+
+    /// Save the previewed basecall as a script to run by hand, rather than running it
+    fn save_launch_script(&mut self) -> Result<()> {
+        let Some(plan) = self.launch_preview.plan.take() else {
+            return Ok(());
+        };
+        let dir = self.config.config.data_dir.join("scripts");
+        std::fs::create_dir_all(&dir)?;
+        let path = plan.script_path_in(&dir);
+        std::fs::write(&path, plan.script())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        info!(
+            "Saved the basecall of {} to {}",
+            plan.project,
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Write a preset from the form to its file, removing the file it was renamed from
+    fn save_preset(&mut self, original: Option<&str>, preset: &Preset) -> Result<()> {
+        if original != Some(preset.name.as_str())
+            && self.settings.presets.iter().any(|p| p.name == preset.name)
+        {
+            return Err(eyre!("There's already a preset named {}", preset.name));
+        }
+        let renamed_file = self
+            .settings
+            .presets
+            .iter()
+            .find(|p| Some(p.name.as_str()) == original && p.name != preset.name)
+            .and_then(|p| p.file.clone());
+        let path = preset.save(&self.presets_dir())?;
+        if let Some(old) = renamed_file {
+            std::fs::remove_file(old)?;
+        }
+        info!("Saved preset {} to {}", preset.name, path.display());
+        self.load_presets()
+    }
+
+    /// Delete the selected preset's file, presets in the config file have to be removed there
+    fn remove_preset(&mut self) -> Result<()> {
+        let Some(preset) = self.settings.selected_preset() else {
+            return Ok(());
+        };
+        let file = preset.file.clone().ok_or_else(|| {
+            eyre!(
+                "{} is in the config file, remove it from there",
+                preset.name
+            )
+        })?;
+        std::fs::remove_file(&file)?;
+        info!("Removed preset {}", preset.name);
+        self.load_presets()
+    }
+
+    /// Hash a reference entered in the form in the background before registering it
+    fn register_reference(&self, reference: Reference) -> Result<()> {
+        if reference.path.is_empty() {
+            return Err(eyre!("Enter the path to the reference's FASTA"));
+        }
+        let tx = self.action_tx.clone();
+        info!("Registering reference {}", reference.path);
+        tokio::task::spawn_blocking(move || {
+            let path = reference.path.clone();
+            let action = match reference.hashed() {
+                Ok(reference) => Action::ReferenceRegistered(reference),
+                Err(e) => Action::Error(format!("Unable to register {path}: {e}")),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    /// Remove the selected reference unless a project still uses it as its default
+    fn remove_reference(&mut self) -> Result<()> {
+        let Some(name) = self
+            .reference_list
+            .selected_reference()
+            .map(|reference| reference.name.clone())
+        else {
+            return Ok(());
+        };
+        let users = self
+            .projects
+            .items
+            .iter()
+            .filter(|project| project.reference.as_ref() == Some(&name))
+            .map(|project| project.name.as_str())
+            .collect::<Vec<_>>();
+        if !users.is_empty() {
+            return Err(eyre!(
+                "{name} is the default reference of {}",
+                users.join(", ")
+            ));
+        }
+        self.references.remove(&name);
+        info!("Removed reference {name}");
+        self.save_references()
+    }
+
+    /// Look for each reference's indexes again and report any that are missing
+    fn validate_references(&mut self) -> Result<()> {
+        for reference in &mut self.references.references {
+            reference.find_indexes();
+            let problems = reference.problems();
+            if problems.is_empty() {
+                info!("{}: ready", reference.name);
+            } else {
+                warn!("{}: {}", reference.name, problems.join(", "));
+            }
+        }
+        self.save_references()
+    }
+
+    fn set_project_reference(&mut self) -> Result<()> {
+        let reference = self
+            .reference_list
+            .selected_reference()
+            .ok_or_else(|| eyre!("Select a reference"))?
+            .name
+            .clone();
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to set its default reference"))?
+            .name
+            .clone();
+        if let Some(entry) = self.projects.get_mut(&project) {
+            entry.reference = Some(reference);
+            entry.last_updated = now();
+        }
+        self.save_project(&project)
+    }
+
+    /// Fill in the selected project's run details and samples from its MinKNOW sample sheet
+    fn import_sample_sheet(&mut self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to import a sample sheet into"))?;
+        let path = sample_sheet::find_in(Path::new(&project.data_location))
+            .ok_or_else(|| eyre!("No sample sheet in {}", project.data_location))?;
+        let sheet = SampleSheet::load(&path)?;
+        let name = project.name.clone();
+
+        let project = self
+            .projects
+            .get_mut(&name)
+            .ok_or_else(|| eyre!("No project named {name}"))?;
+        sheet.apply(project);
+        project.last_updated = now();
+        self.save_project(&name)?;
+        info!("Imported {} into {name}", path.display());
+        Ok(())
+    }
+
+    fn export_sample_sheet(&self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to export a sample sheet for"))?;
+        let path = self
+            .config
+            .config
+            .data_dir
+            .join("sample_sheets")
+            .join(format!("{}.csv", sanitize(&project.name)));
+        SampleSheet::from_project(project).save(&path)?;
+        info!("Exported {} to {}", project.name, path.display());
+        Ok(())
+    }
+
+    /// Compute QC for a basecall's output in the background, summarising its modified base
+    /// calls, duplex pairing and poly-A tails too when the run made them
+    fn compute_qc(&self, project: String, run: &BasecallRun) {
+        let tx = self.action_tx.clone();
+        let output_path = run.output_path.clone();
+        let modified_bases = !run.modified_bases.is_empty();
+        let duplex = run.run_type == RunType::Duplex;
+        let rna = run.estimate_poly_a
+            || self
+                .projects
+                .get(&project)
+                .is_some_and(|project| matches!(project.project_type, ProjectType::RNA(_)));
+        tokio::task::spawn_blocking(move || {
+            let output = Path::new(&output_path);
+            let results = qc::basecall_results(output).and_then(|mut results| {
+                if modified_bases {
+                    results.modifications = modifications::summarise(output)?;
+                }
+                if duplex {
+                    results.duplex = Some(duplex::summarise(output)?);
+                }
+                if rna {
+                    results.rna = Some(rna::summarise(output)?);
+                }
+                Ok(results)
+            });
+            let action = match results {
+                Ok(results) => Action::QcResults(project, output_path, results),
+                Err(e) => Action::Error(format!("Unable to compute QC for {output_path}: {e}")),
+            };
+            let _ = tx.send(action);
+        });
+    }
+
+    fn compute_selected_qc(&self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to compute QC for"))?;
+        let pending = project
+            .basecall_logs()
+            .filter(|log| log.status == BasecallStatus::Success && log.results.is_none())
+            .map(|log| &log.basecall_run);
+        for run in pending {
+            self.compute_qc(project.name.clone(), run);
+        }
+        Ok(())
+    }
+
+    fn record_qc_results(
+        &mut self,
+        project: &str,
+        output_path: &str,
+        results: BasecallResults,
+    ) -> Result<()> {
+        if let Some(log) = self
+            .projects
+            .get_mut(project)
+            .and_then(|project| project.basecall_log_mut(output_path))
+        {
+            log.results = Some(results);
+        }
+        self.save_project(project)
+    }
+
+    /// Recount every project's disk usage and the configured volumes' free space in the background
+    fn measure_storage(&mut self) {
+        self.last_storage_check = Some(Instant::now());
+        let projects = self.projects.items.clone();
+        let volumes = self.config.storage.volumes.clone();
+        let tx = self.action_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut usages = Vec::new();
+            for project in projects {
+                match storage::measure(&project) {
+                    Ok(usage) => usages.push((project.name, usage)),
+                    Err(e) => warn!("Unable to measure {}: {e}", project.name),
+                }
+            }
+            let _ = tx.send(Action::StorageUsage(
+                usages,
+                storage::volume_usage(&volumes),
+            ));
+        });
+    }
+
+    fn record_storage_usage(&mut self, usages: Vec<(String, storage::StorageUsage)>) -> Result<()> {
+        for (name, usage) in usages {
+            // Unchanged projects aren't rewritten on every check
+            if self
+                .projects
+                .get_mut(&name)
+                .is_some_and(|project| project.record_usage(usage))
+            {
+                self.save_project(&name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scan `root` for run directories in the background and preview the projects they'd become
+    pub fn preview_import(&self, root: PathBuf) {
+        let existing = self
+            .projects
+            .items
+            .iter()
+            .map(|project| project.name.clone())
+            .collect::<Vec<_>>();
+        let tx = self.action_tx.clone();
+        info!("Scanning {} for runs to import", root.display());
+        tokio::task::spawn_blocking(move || {
+            let action = match import::plan(&root, &existing) {
+                Ok(plan) => Action::ImportPreview(plan),
+                Err(e) => Action::Error(format!("Unable to scan {}: {e}", root.display())),
+            };
+            let _ = tx.send(action);
+        });
+    }
+
+    /// Save the previewed projects that don't exist yet
+    fn confirm_import(&mut self) -> Result<()> {
+        // Saved runs are marked as existing, so a failure leaves the rest in the preview
+        let dir = self.projects_dir();
+        let mut saved = Ok(());
+        for run in self
+            .import_preview
+            .items
+            .iter_mut()
+            .filter(|run| !run.exists)
+        {
+            saved = run.project.save(&dir);
+            if saved.is_err() {
+                break;
+            }
+            info!("Imported {} as {}", run.path.display(), run.project.name);
+            run.exists = true;
+            self.projects.items.push(run.project.clone());
+        }
+        self.projects.items.sort_by(|a, b| a.name.cmp(&b.name));
+        saved?;
+        self.import_preview.items.clear();
+        self.import_preview.failed.clear();
+        Ok(())
+    }
+
+    /// The project's own lifecycle rules, or the configured defaults
+    fn lifecycle_rules(&self, project: &Project) -> Vec<lifecycle::LifecycleRule> {
+        if project.lifecycle.is_empty() {
+            self.config.storage.lifecycle.clone()
+        } else {
+            project.lifecycle.clone()
+        }
+    }
+
+    /// Work out in the background what the selected project's lifecycle rules would do
+    fn preview_lifecycle(&self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to preview its lifecycle rules"))?
+            .clone();
+        let rules = self.lifecycle_rules(&project);
+        if rules.is_empty() {
+            return Err(eyre!("{} has no lifecycle rules", project.name));
+        }
+        let volumes = self.config.storage.volumes.clone();
+        let tx = self.action_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let action = match lifecycle::plan(&project, &rules, &volumes, now()) {
+                Ok(steps) => Action::LifecyclePreview(project.name, steps),
+                Err(e) => Action::Error(e.to_string()),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    /// Carry out the previewed lifecycle steps in the background
+    fn confirm_lifecycle(&mut self) -> Result<()> {
+        let name = std::mem::take(&mut self.lifecycle_preview.project);
+        let steps = std::mem::take(&mut self.lifecycle_preview.steps);
+        if steps.is_empty() {
+            return Ok(());
+        }
+        let project = self
+            .projects
+            .get_mut(&name)
+            .ok_or_else(|| eyre!("No project named {name}"))?
+            .clone();
+        let rules = self.lifecycle_rules(&project);
+        let tx = self.action_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let action = match lifecycle::apply(&project, &rules, &steps) {
+                Ok(logs) => Action::LifecycleApplied(project.name, logs),
+                Err(e) => Action::Error(e.to_string()),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    fn record_lifecycle(&mut self, name: &str, logs: Vec<LifecycleLog>) -> Result<()> {
+        let project = self
+            .projects
+            .get_mut(name)
+            .ok_or_else(|| eyre!("No project named {name}"))?;
+        for log in logs {
+            match &log.error {
+                Some(e) => error!("{name}: {} failed at {e}", log.rule),
+                None => info!(
+                    "{name}: {} ({} files, {})",
+                    log.rule,
+                    log.files.len(),
+                    storage::format_size(log.bytes)
+                ),
+            }
+            for (from, to) in &log.relocated {
+                info!("{name}: raw data in {from} is now in {to}");
+                project.relocate(from, to);
+            }
+            project.history.push(ProjectHistory {
+                action: ProjectAction::Lifecycle(log),
+                created_by: current_user(),
+                created_at: now(),
+            });
+        }
+        project.last_updated = now();
+        self.save_project(name)?;
+        self.measure_storage();
+        Ok(())
+    }
+
+    /// Hash the selected project's data into a manifest next to its record, in the background
+    fn generate_manifest(&self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to generate its manifest"))?;
+        if project.data_location.is_empty() {
+            return Err(eyre!("{} has no data location", project.name));
+        }
+        let name = project.name.clone();
+        let data = PathBuf::from(&project.data_location);
+        let path = project.manifest_path_in(&self.projects_dir());
+        let tx = self.action_tx.clone();
+        info!("Generating a manifest for {name}");
+        tokio::task::spawn_blocking(move || {
+            let generate = || -> Result<usize> {
+                let manifest = Manifest::generate(&data)?;
+                manifest.save(&path)?;
+                Ok(manifest.entries.len())
+            };
+            let action = match generate() {
+                Ok(files) => Action::ManifestGenerated(name, files),
+                Err(e) => Action::Error(format!("Unable to generate a manifest for {name}: {e}")),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    /// Re-hash the selected project's data against its manifest in the background
+    fn verify_manifest(&self) -> Result<()> {
+        let project = self
+            .projects
+            .selected_project()
+            .ok_or_else(|| eyre!("Select a project to verify"))?;
+        let path = project.manifest_path_in(&self.projects_dir());
+        if !path.exists() {
+            return Err(eyre!("{} has no manifest yet", project.name));
+        }
+        let manifest = Manifest::load(&path)?;
+        let name = project.name.clone();
+        let data = PathBuf::from(&project.data_location);
+        let report_path = project.verify_report_path_in(&self.projects_dir());
+        let tx = self.action_tx.clone();
+        info!("Verifying {name} against its manifest");
+        tokio::task::spawn_blocking(move || {
+            // The paths can run to thousands after a move, so they're kept out of the record
+            let verify = || -> Result<Verification> {
+                let report = manifest.verify(&data)?;
+                report.save(&report_path)?;
+                Ok(report.summary())
+            };
+            let action = match verify() {
+                Ok(verification) => Action::ManifestVerified(name, verification),
+                Err(e) => Action::Error(format!("Unable to verify {name}: {e}")),
+            };
+            let _ = tx.send(action);
+        });
+        Ok(())
+    }
+
+    fn record_manifest(&mut self, name: &str, files: usize) -> Result<()> {
+        let project = self
+            .projects
+            .get_mut(name)
+            .ok_or_else(|| eyre!("No project named {name}"))?;
+        info!("{name}: manifest of {files} files written");
+        project.manifest_at = Some(now());
+        self.save_project(name)
+    }
+
+    fn record_verification(&mut self, name: &str, verification: Verification) -> Result<()> {
+        let path = self
+            .projects
+            .get(name)
+            .map(|project| project.verify_report_path_in(&self.projects_dir()))
+            .ok_or_else(|| eyre!("No project named {name}"))?;
+        if verification.is_ok() {
+            info!("{name}: all {} files match the manifest", verification.ok);
+        } else {
+            error!(
+                "{name}: {} missing, {} extra and {} corrupted files, listed in {}",
+                verification.missing,
+                verification.extra,
+                verification.corrupted,
+                path.display()
+            );
+        }
+        if let Some(project) = self.projects.get_mut(name) {
+            project.verification = Some(verification);
+        }
+        self.save_project(name)
+    }
+
+    fn record_demux_results(&mut self, id: usize, barcodes: Vec<BarcodeResults>) -> Result<()> {
+        let Some(job) = self.jobs.get(id).map(|entry| entry.job.clone()) else {
+            return Ok(());
+        };
+        if let Some(log) = self
+            .projects
+            .get_mut(&job.project)
+            .and_then(|project| project.demux_log_mut(job.output()))
+        {
+            log.barcodes = barcodes;
+        }
+        self.save_project(&job.project)
+    }
+
+    fn record_resumed_reads(&mut self, id: usize, reads: u64) -> Result<()> {
+        let Some(job) = self.jobs.get(id).map(|entry| entry.job.clone()) else {
+            return Ok(());
+        };
+        if let Some(log) = self
+            .projects
+            .get_mut(&job.project)
+            .and_then(|project| project.basecall_log_mut(&job.run.output_path))
+        {
+            log.resumed_reads = reads;
+        }
+        self.save_project(&job.project)?;
+        self.action_tx.send(Action::JobProgress(
+            id,
+            format!("Resumed with {reads} reads already basecalled"),
+        ))?;
+        Ok(())
+    }
+
+    /// Store a finished job's status in its project history
+    fn record_job_status(&mut self, id: usize, status: BasecallStatus) -> Result<()> {
+        let Some(job) = self.jobs.get(id).map(|entry| entry.job.clone()) else {
+            return Ok(());
+        };
+        let Some(project) = self.projects.get_mut(&job.project) else {
+            return Ok(());
+        };
+        match &job.kind {
+            JobKind::Basecall => {
+                if let Some(log) = project.basecall_log_mut(&job.run.output_path) {
+                    log.status = status;
+                    if status == BasecallStatus::Success {
+                        log.finished_at = Some(now());
+                    }
+                }
+            }
+            JobKind::Demux { output_dir, .. } => {
+                if let Some(log) = project.demux_log_mut(output_dir) {
+                    log.status = status;
+                }
+            }
+            // A finished conversion stays in progress until its read counts are checked, and an
+            // alignment until its mapping statistics are read
+            JobKind::Convert { output_dir, .. } => {
+                if let Some(log) = project
+                    .convert_log_mut(output_dir)
+                    .filter(|_| status != BasecallStatus::Success)
+                {
+                    log.status = status;
+                }
+            }
+            JobKind::Align { output_path, .. } => {
+                if let Some(log) = project
+                    .align_log_mut(output_path)
+                    .filter(|_| status != BasecallStatus::Success)
+                {
+                    log.status = status;
+                }
+            }
+        }
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+
+        if status != BasecallStatus::Success {
+            return Ok(());
+        }
+        match job.kind.clone() {
+            // The merged output now holds every read from the partial one
+            JobKind::Basecall => {
+                if let Some(partial) = &job.resume_from {
+                    match std::fs::remove_file(partial) {
+                        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                            warn!("Unable to remove partial output {partial}: {e}")
+                        }
+                        _ => {}
+                    }
+                }
+                self.compute_qc(job.project, &job.run);
+            }
+            JobKind::Demux { output_dir, .. } => {
+                let tx = self.action_tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    match barcoding::summarise(Path::new(&output_dir)) {
+                        Ok(barcodes) => {
+                            let _ = tx.send(Action::DemuxResults(id, barcodes));
+                        }
+                        Err(e) => warn!("Unable to summarise {output_dir}: {e}"),
+                    }
+                });
+            }
+            JobKind::Convert {
+                output_dir,
+                remove_originals,
+            } => {
+                self.action_tx
+                    .send(Action::JobProgress(id, "Checking read counts".to_string()))?;
+                let (pod5, tx) = (job.run.basecaller.binary(), self.action_tx.clone());
+                tokio::task::spawn_blocking(move || {
+                    let input = Path::new(&job.run.path);
+                    let action = match conversion::verify(
+                        &pod5,
+                        input,
+                        Path::new(&output_dir),
+                        remove_originals,
+                    ) {
+                        Ok(check) => Action::ConversionVerified(id, check),
+                        Err(e) => {
+                            let reason = format!("Unable to verify {output_dir}: {e}");
+                            let _ = tx.send(Action::Notify(job.notification(Some(reason.clone()))));
+                            Action::Error(reason)
+                        }
+                    };
+                    let _ = tx.send(action);
+                });
+            }
+            JobKind::Align { output_path, .. } => {
+                let reference = self
+                    .projects
+                    .get_mut(&job.project)
+                    .and_then(|project| project.align_log_mut(&output_path))
+                    .map(|log| log.reference.name.clone())
+                    .unwrap_or_default();
+                let tx = self.action_tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    let action = match alignment::mapping_stats(Path::new(&output_path), &reference)
+                    {
+                        Ok(stats) => Action::MappingStats(id, stats),
+                        Err(e) => Action::Error(format!("Unable to read {output_path}: {e}")),
+                    };
+                    let _ = tx.send(action);
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Store an alignment's mapping statistics with the basecall it aligned
+    fn record_mapping(&mut self, id: usize, stats: MappingStats) -> Result<()> {
+        let Some(job) = self.jobs.get(id).map(|entry| entry.job.clone()) else {
+            return Ok(());
+        };
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        let message = format!(
+            "{:.1}% mapped to {}, {:.1}% identity, {:.1}x coverage",
+            stats.mapped_percent(),
+            stats.reference,
+            stats.identity * 100.0,
+            stats.coverage
+        );
+        if let Some(log) = project.align_log_mut(job.output()) {
+            log.status = BasecallStatus::Success;
+        }
+        if let Some(log) = project.basecall_log_mut(job.input()) {
+            log.mapping = Some(stats);
+        }
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+        self.action_tx.send(Action::JobProgress(id, message))?;
+        Ok(())
+    }
+
+    /// Record a conversion's read counts, switching the project to POD5 once they match
+    fn record_conversion(&mut self, id: usize, check: ConversionCheck) -> Result<()> {
+        let Some(job) = self.jobs.get(id).map(|entry| entry.job.clone()) else {
+            return Ok(());
+        };
+        let project = self
+            .projects
+            .get_mut(&job.project)
+            .ok_or_else(|| eyre!("No project named {}", job.project))?;
+        let message = if check.verified() {
+            project.format = RawBasecallsFormat::Pod5;
+            project.raw_path = Some(job.output().to_string());
+            format!(
+                "Verified {} reads{}",
+                check.pod5_reads,
+                if check.removed_originals {
+                    ", FAST5 removed"
+                } else {
+                    ""
+                }
+            )
+        } else {
+            format!(
+                "Read counts differ: {} in FAST5, {} in POD5",
+                check.fast5_reads, check.pod5_reads
+            )
+        };
+        if let Some(log) = project.convert_log_mut(job.output()) {
+            log.status = if check.verified() {
+                BasecallStatus::Success
+            } else {
+                BasecallStatus::Failed
+            };
+            log.check = Some(check.clone());
+        }
+        project.last_updated = now();
+        self.save_project(&job.project)?;
+        let reason = (!check.verified()).then(|| message.clone());
+        self.action_tx.send(Action::JobProgress(id, message))?;
+        self.action_tx
+            .send(Action::Notify(job.notification(reason)))?;
+        Ok(())
     }
 
     pub async fn run(&mut self) -> Result<()> {
-        let mut tui = Tui::new()?;
-        // .mouse(true) // uncomment this line to enable mouse support
-        // .tick_rate(self.tick_rate)
-        // .frame_rate(self.frame_rate);
+        let mut tui = Tui::new()?
+            .mouse(self.config.display.mouse)
+            .tick_rate(self.tick_rate)
+            .frame_rate(self.frame_rate);
         tui.enter()?;
 
         /*
@@ -87,7 +1474,6 @@ impl App {
                 tui.suspend()?;
                 action_tx.send(Action::Resume)?;
                 action_tx.send(Action::ClearScreen)?;
-                // tui.mouse(true);
                 tui.enter()?;
             } else if self.should_quit {
                 tui.stop()?;
@@ -108,7 +1494,14 @@ impl App {
             Event::Tick => action_tx.send(Action::Tick)?,
             Event::Render => action_tx.send(Action::Render)?,
             Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
-            Event::Key(key) => self.handle_key_event(key)?,
+            Event::Key(key) => {
+                self.wake(tui);
+                self.handle_key_event(key)?
+            }
+            Event::Mouse(mouse) => {
+                self.wake(tui);
+                self.handle_mouse_event(mouse)?
+            }
             _ => {}
         }
 
@@ -121,8 +1514,126 @@ impl App {
         Ok(())
     }
 
+    /// Go back to the full frame rate on input
+    fn wake(&mut self, tui: &mut Tui) {
+        self.last_input = Instant::now();
+        if self.idle {
+            self.idle = false;
+            tui.set_frame_rate(self.frame_rate);
+        }
+    }
+
+    /// With the adaptive frame rate, drop to the idle rate once there's been no input for a
+    /// while and nothing on screen animates
+    fn update_frame_rate(&mut self, tui: &mut Tui) {
+        if let Some(rate) = self.next_frame_rate() {
+            tui.set_frame_rate(rate);
+        }
+    }
+
+    /// The frame rate to switch to, if going idle or leaving it changes the rate
+    fn next_frame_rate(&mut self) -> Option<f64> {
+        let display = &self.config.display;
+        let idle = display.adaptive
+            && !self.animating
+            && self.last_input.elapsed() >= Duration::from_secs(display.idle_after_seconds);
+        if idle == self.idle {
+            return None;
+        }
+        self.idle = idle;
+        Some(if idle {
+            display.idle_frame_rate
+        } else {
+            self.frame_rate
+        })
+    }
+
+    /// Click a footer hint to run its action, click a pane to focus it and select the row under
+    /// the pointer, or scroll the list under the pointer with the wheel
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<()> {
+        let position = Position::new(mouse.column, mouse.row);
+        let pane = self
+            .pane_areas
+            .iter()
+            .position(|area| area.contains(position));
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let (footer_row, hints) = &self.footer_hints;
+                if let Some((_, action)) = hints.iter().find(|(columns, _)| {
+                    mouse.row == *footer_row && columns.contains(&mouse.column)
+                }) {
+                    self.action_tx.send(action.clone())?;
+                    return Ok(());
+                }
+                // Other modes keep the keyboard on their own view
+                let Some(pane) = pane.filter(|_| self.mode == Mode::Home) else {
+                    return Ok(());
+                };
+                self.focus_pane(pane);
+                let action = match pane {
+                    1 => self.projects.handle_mouse_event(mouse)?,
+                    2 => self.jobs.handle_mouse_event(mouse)?,
+                    _ => None,
+                };
+                if let Some(action) = action {
+                    self.action_tx.send(action)?;
+                }
+            }
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+                match self.mode {
+                    Mode::Home => {
+                        let Some(pane) = pane else {
+                            return Ok(());
+                        };
+                        self.focus_pane(pane);
+                    }
+                    // Views with a list of their own scroll it wherever the pointer is
+                    Mode::Import | Mode::References | Mode::Settings | Mode::Lifecycle => {}
+                    _ => return Ok(()),
+                }
+                self.action_tx
+                    .send(if mouse.kind == MouseEventKind::ScrollDown {
+                        Action::SelectNext
+                    } else {
+                        Action::SelectPrevious
+                    })?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Focus the software (0), projects (1) or jobs (2) pane
+    fn focus_pane(&mut self, pane: usize) {
+        self.has_focus = pane % self.focusable_max;
+
+        // Unfocus all
+        self.software.unfocus();
+        self.projects.unfocus();
+        self.jobs.unfocus();
+
+        match self.has_focus {
+            0 => self.software.focus(),
+            1 => self.projects.focus(),
+            2 => self.jobs.focus(),
+            _ => {}
+        }
+    }
+
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         let action_tx = self.action_tx.clone();
+        if self.mode == Mode::ReferenceEntry {
+            if let Some(action) = self.reference_list.handle_key_event(key)? {
+                action_tx.send(action)?;
+            }
+            return Ok(());
+        }
+        if self.mode == Mode::PresetEntry {
+            if let Some(action) = self.settings.handle_key_event(key)? {
+                action_tx.send(action)?;
+            }
+            return Ok(());
+        }
         let Some(keymap) = self.config.keybindings.get(&self.mode) else {
             return Ok(());
         };
@@ -148,35 +1659,363 @@ impl App {
     }
 
     fn handle_actions(&mut self, tui: &mut Tui) -> Result<()> {
+        self.fps.record_queue_depth(self.action_rx.len());
         while let Ok(action) = self.action_rx.try_recv() {
             if action != Action::Tick && action != Action::Render {
                 debug!("{action:?}");
+                self.fps.record_action(&action);
             }
             match action {
                 Action::Tick => {
+                    self.fps.update(Action::Tick)?;
+                    self.update_frame_rate(tui);
                     self.last_tick_key_events.drain(..);
+                    let interval = Duration::from_secs(self.config.storage.usage_check_interval);
+                    if self
+                        .last_storage_check
+                        .is_none_or(|last| last.elapsed() >= interval)
+                    {
+                        self.measure_storage();
+                    }
+                    for notification in self.disk_monitor.poll() {
+                        self.action_tx.send(Action::Notify(notification))?;
+                    }
+                    // Sampled while hidden too, so the sparklines are filled when it's opened
+                    match self.resource_sampler.poll(&self.jobs.running_processes()) {
+                        Some(Ok(sample)) => self.resource_monitor.push(sample),
+                        Some(Err(e)) => self.resource_monitor.error = Some(e.to_string()),
+                        None => {}
+                    }
+                }
+                Action::Notify(notification) => {
+                    info!("{}", notification.message());
+                    if !self.notifier.is_empty() {
+                        let notifier = self.notifier.clone();
+                        tokio::spawn(async move { notifier.send(&notification).await });
+                    }
+                }
+                // Confirmed in the launch preview, which warns when the output won't fit
+                Action::StartBasecall(project, run) => {
+                    if let Err(e) = self.preview_launch(project, run) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ResumeJob => {
+                    if let Err(e) = self.resume_job() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::JobResumed(id, reads) => {
+                    if let Err(e) = self.record_resumed_reads(id, reads) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::JobStatus(id, status) => {
+                    if let Err(e) = self.record_job_status(id, status) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                    if let Some(action) = self.jobs.update(action)? {
+                        self.action_tx.send(action)?;
+                    }
+                }
+                Action::Demultiplex => {
+                    if let Err(e) = self.start_demux() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ImportSampleSheet => {
+                    if let Err(e) = self.import_sample_sheet() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ExportSampleSheet => {
+                    if let Err(e) = self.export_sample_sheet() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ComputeQc => {
+                    if let Err(e) = self.compute_selected_qc() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::QcResults(project, output_path, results) => {
+                    if let Err(e) = self.record_qc_results(&project, &output_path, results) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::Help => self.show_help = !self.show_help,
+                Action::ToggleRunCharts => self.show_run_charts = !self.show_run_charts,
+                Action::ToggleStorage => {
+                    self.show_storage = !self.show_storage;
+                    if self.show_storage {
+                        self.measure_storage();
+                    }
+                }
+                Action::ToggleResourceMonitor => self.show_resources = !self.show_resources,
+                Action::ToggleThroughput => {
+                    self.show_throughput = !self.show_throughput;
+                    if self.show_throughput {
+                        self.throughput_view.table =
+                            ThroughputTable::from_projects(&self.projects.items);
+                    }
+                }
+                Action::StorageUsage(ref usages, _) => {
+                    if let Err(e) = self.record_storage_usage(usages.clone()) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                    self.storage_view.update(action)?;
+                }
+                Action::ImportPreview(..) => {
+                    self.import_preview.update(action)?;
+                    self.mode = Mode::Import;
+                }
+                Action::ConfirmImport => match self.confirm_import() {
+                    Ok(()) => self.mode = Mode::Home,
+                    // Stay on the preview so the unsaved runs can be retried
+                    Err(e) => self.action_tx.send(Action::Error(e.to_string()))?,
+                },
+                Action::CancelImport => {
+                    self.mode = Mode::Home;
+                    self.import_preview.items.clear();
+                    self.import_preview.failed.clear();
+                }
+                Action::ConvertToPod5(remove_originals) => {
+                    if let Err(e) = self.start_conversion(remove_originals) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ConversionVerified(id, check) => {
+                    if let Err(e) = self.record_conversion(id, check) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::PreviewLifecycle => {
+                    if let Err(e) = self.preview_lifecycle() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::LifecyclePreview(..) => {
+                    self.lifecycle_preview.update(action)?;
+                    self.mode = Mode::Lifecycle;
+                }
+                Action::ConfirmLifecycle => {
+                    self.mode = Mode::Home;
+                    if let Err(e) = self.confirm_lifecycle() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::CancelLifecycle => {
+                    self.mode = Mode::Home;
+                    self.lifecycle_preview.steps.clear();
+                }
+                Action::LifecycleApplied(name, logs) => {
+                    if let Err(e) = self.record_lifecycle(&name, logs) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ToggleReferences => {
+                    self.mode = if self.mode == Mode::References {
+                        Mode::Home
+                    } else {
+                        Mode::References
+                    };
                 }
+                Action::AddReference => {
+                    self.reference_list.update(action)?;
+                    self.mode = Mode::ReferenceEntry;
+                }
+                Action::CancelReferenceEntry => {
+                    self.reference_list.update(action)?;
+                    self.mode = Mode::References;
+                }
+                Action::RegisterReference(ref reference) => {
+                    let reference = reference.clone();
+                    self.reference_list.update(action)?;
+                    self.mode = Mode::References;
+                    if let Err(e) = self.register_reference(reference) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ReferenceRegistered(reference) => {
+                    info!("Registered reference {}", reference.name);
+                    self.references.register(reference);
+                    if let Err(e) = self.save_references() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::RemoveReference => {
+                    if let Err(e) = self.remove_reference() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ValidateReferences => {
+                    if let Err(e) = self.validate_references() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::SetProjectReference => {
+                    if let Err(e) = self.set_project_reference() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ToggleSettingsDisplay => {
+                    self.settings.launching = None;
+                    self.mode = if self.mode == Mode::Settings {
+                        Mode::Home
+                    } else {
+                        Mode::Settings
+                    };
+                    if let Err(e) = self.load_presets() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::PickPreset => {
+                    if let Err(e) = self.pick_preset() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ChoosePreset => {
+                    if self.settings.launching.is_some() {
+                        if let Err(e) = self.launch_preset() {
+                            self.action_tx.send(Action::Error(e.to_string()))?;
+                        }
+                    } else {
+                        self.action_tx.send(Action::EditPreset)?;
+                    }
+                }
+                Action::LaunchPreview(..) => {
+                    self.launch_preview.update(action)?;
+                    self.mode = Mode::Launch;
+                }
+                Action::ConfirmLaunch => {
+                    self.mode = Mode::Home;
+                    if let Err(e) = self.confirm_launch() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::CancelLaunch => {
+                    self.mode = Mode::Home;
+                    self.launch_preview.update(action)?;
+                }
+                Action::CopyLaunchCommand => {
+                    if let Some(plan) = &self.launch_preview.plan {
+                        match copy_to_clipboard(&plan.command) {
+                            Ok(()) => info!("Copied the command to the clipboard"),
+                            Err(e) => self.action_tx.send(Action::Error(e.to_string()))?,
+                        }
+                    }
+                }
+                Action::SaveLaunchScript => {
+                    self.mode = Mode::Home;
+                    if let Err(e) = self.save_launch_script() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::AddPreset | Action::EditPreset => {
+                    self.settings.update(action)?;
+                    if self.settings.form.is_some() {
+                        self.mode = Mode::PresetEntry;
+                    }
+                }
+                Action::CancelPresetEntry => {
+                    self.settings.update(action)?;
+                    self.mode = Mode::Settings;
+                }
+                Action::SavePreset(ref original, ref preset) => {
+                    // A preset that can't be saved stays in the form
+                    match self.save_preset(original.as_deref(), preset) {
+                        Ok(()) => {
+                            self.settings.update(action)?;
+                            self.mode = Mode::Settings;
+                        }
+                        Err(e) => self.action_tx.send(Action::Error(e.to_string()))?,
+                    }
+                }
+                Action::RemovePreset => {
+                    if let Err(e) = self.remove_preset() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::Align => {
+                    if let Err(e) = self.align() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::AlignTo(project, reference) => {
+                    if let Err(e) = self.start_alignment(project, reference) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::MappingStats(id, stats) => {
+                    if let Err(e) = self.record_mapping(id, stats) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::GenerateManifest => {
+                    if let Err(e) = self.generate_manifest() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ManifestGenerated(name, files) => {
+                    if let Err(e) = self.record_manifest(&name, files) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::VerifyManifest => {
+                    if let Err(e) = self.verify_manifest() {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::ManifestVerified(name, report) => {
+                    if let Err(e) = self.record_verification(&name, report) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::DemuxResults(id, barcodes) => {
+                    if let Err(e) = self.record_demux_results(id, barcodes) {
+                        self.action_tx.send(Action::Error(e.to_string()))?;
+                    }
+                }
+                Action::JobProcess(..) => {
+                    self.jobs.update(action)?;
+                }
+                Action::JobProgress(id, ref line) => {
+                    self.record_samples_rate(id, line);
+                    self.jobs.update(action)?;
+                }
+                Action::SelectNext | Action::SelectPrevious if self.mode == Mode::Import => {
+                    self.import_preview.update(action)?;
+                }
+                Action::SelectNext | Action::SelectPrevious if self.mode == Mode::References => {
+                    self.reference_list.update(action)?;
+                }
+                Action::SelectNext | Action::SelectPrevious if self.mode == Mode::Settings => {
+                    self.settings.update(action)?;
+                }
+                Action::SelectNext | Action::SelectPrevious if self.mode == Mode::Lifecycle => {
+                    self.lifecycle_preview.update(action)?;
+                }
+                Action::SelectNext | Action::SelectPrevious => match self.has_focus {
+                    1 => {
+                        self.projects.update(action)?;
+                    }
+                    2 => {
+                        self.jobs.update(action)?;
+                    }
+                    _ => {}
+                },
+                Action::Error(message) => error!("{message}"),
                 Action::Quit => self.should_quit = true,
                 Action::Suspend => self.should_suspend = true,
                 Action::Resume => self.should_suspend = false,
                 Action::ClearScreen => tui.terminal.clear()?,
                 Action::Resize(w, h) => self.handle_resize(tui, w, h)?,
-                Action::Render => self.render(tui)?,
-                Action::NextPane => {
-                    self.has_focus = (self.has_focus + 1) % self.focusable_max;
-                    self.software.has_focus = self.has_focus == 0;
-                    self.projects.has_focus = self.has_focus == 1;
-
-                    // Unfocus all
-                    self.software.unfocus();
-                    self.projects.unfocus();
-
-                    match self.has_focus {
-                        0 => self.software.focus(),
-                        1 => self.projects.focus(),
-                        _ => {}
-                    }
+                Action::Render => {
+                    self.fps.update(Action::Render)?;
+                    self.render(tui)?;
                 }
+                Action::ToggleDebugOverlay => self.show_debug = !self.show_debug,
+                Action::NextPane => self.focus_pane(self.has_focus + 1),
                 _ => {}
             }
 
@@ -197,6 +2036,9 @@ impl App {
     }
 
     fn render(&mut self, tui: &mut Tui) -> Result<()> {
+        let frame_start = Instant::now();
+        // How long each component takes to draw, for the debug overlay
+        let mut render_times = Vec::new();
         tui.draw(|frame| {
             let [header_area, main_area, footer_area] = Layout::vertical([
                 Constraint::Length(2),
@@ -205,6 +2047,13 @@ impl App {
             ])
             .areas(frame.area());
 
+            // The resource monitor collapses to nothing when hidden
+            let monitor_width = if self.show_resources { 40 } else { 0 };
+            let [main_area, monitor_area] = Layout::horizontal([
+                Constraint::Fill(1),
+                Constraint::Length(monitor_width),
+            ])
+            .areas(main_area);
             let [list_area, item_area] =
                 Layout::horizontal([Constraint::Fill(1), Constraint::Fill(2)]).areas(main_area);
 
@@ -214,30 +2063,124 @@ impl App {
                 header_area,
             );
 
-            // Add key combos to the footer
-            // Up/Down - Navigate, Enter - Select, q - Quit, Tab - Next Pane
-
-            let bottom_footer = Paragraph::new("Author: Joseph Guhlin -- [q] Quit | [Tab] Next Pane | [Up/Down] Navigate | [Enter] Select | [`] Configuration");
-            frame.render_widget(bottom_footer, footer_area);
+            // Add key combos to the footer, remembering where the clickable ones are
+            let (footer, hints) = footer(footer_area);
+            self.footer_hints = (footer_area.y, hints);
+            frame.render_widget(Paragraph::new(footer), footer_area);
 
 
             // Left hand column (Projects + Software)
             let [projects_area, software_area] =
                 Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)])
                     .areas(list_area);
+            self.pane_areas[0] = software_area;
+            self.pane_areas[1] = projects_area;
 
+            let start = Instant::now();
             self.projects
                 .draw(frame, projects_area)
                 .expect("Failed to draw projects list");
+            render_times.push(("projects", start.elapsed()));
 
+            let start = Instant::now();
             self.software
                 .draw(frame, software_area)
                 .expect("Failed to draw software list");
+            render_times.push(("software", start.elapsed()));
+
+            // Main area, with running jobs underneath
+            let [item_area, jobs_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(8)]).areas(item_area);
+            self.pane_areas[2] = jobs_area;
 
-            // frame.render_widget(Paragraph::new("item"), item_area);
-            self.main_area
-                .draw(frame, item_area)
-                .expect("Failed to draw main area");
+            // The selected project replaces the fish tank
+            let start = Instant::now();
+            self.project_view.project = self.projects.selected_project().cloned();
+            let main_component = if self.mode == Mode::Import {
+                self.import_preview
+                    .draw(frame, item_area)
+                    .expect("Failed to draw import preview");
+                "import preview"
+            } else if self.mode == Mode::Lifecycle {
+                self.lifecycle_preview
+                    .draw(frame, item_area)
+                    .expect("Failed to draw lifecycle preview");
+                "lifecycle preview"
+            } else if matches!(self.mode, Mode::References | Mode::ReferenceEntry) {
+                self.reference_list.project_default = self
+                    .project_view
+                    .project
+                    .as_ref()
+                    .and_then(|project| project.reference.clone());
+                self.reference_list
+                    .draw(frame, item_area)
+                    .expect("Failed to draw references");
+                "references"
+            } else if self.mode == Mode::Launch {
+                self.launch_preview
+                    .draw(frame, item_area)
+                    .expect("Failed to draw launch preview");
+                "launch preview"
+            } else if matches!(self.mode, Mode::Settings | Mode::PresetEntry) {
+                self.settings
+                    .draw(frame, item_area)
+                    .expect("Failed to draw settings");
+                "settings"
+            } else if self.show_help {
+                self.projects.draw_keys(frame, item_area);
+                "keys"
+            } else if self.show_throughput {
+                self.throughput_view
+                    .draw(frame, item_area)
+                    .expect("Failed to draw throughput view");
+                "throughput"
+            } else if self.show_storage {
+                self.storage_view.projects = self.projects.items.clone();
+                self.storage_view
+                    .draw(frame, item_area)
+                    .expect("Failed to draw storage view");
+                "storage"
+            } else if let (true, Some(project)) =
+                (self.show_run_charts, &self.project_view.project)
+            {
+                self.run_charts.results = project.latest_results().cloned();
+                self.run_charts
+                    .draw(frame, item_area)
+                    .expect("Failed to draw run charts");
+                "run charts"
+            } else if self.project_view.project.is_some() {
+                self.project_view
+                    .draw(frame, item_area)
+                    .expect("Failed to draw project view");
+                "project view"
+            } else {
+                self.main_area
+                    .draw(frame, item_area)
+                    .expect("Failed to draw main area");
+                "fish tank"
+            };
+            render_times.push((main_component, start.elapsed()));
+            self.animating = main_component == "fish tank";
+
+            let start = Instant::now();
+            self.jobs
+                .draw(frame, jobs_area)
+                .expect("Failed to draw jobs list");
+            render_times.push(("jobs", start.elapsed()));
+
+            if self.show_resources {
+                let start = Instant::now();
+                self.resource_monitor
+                    .draw(frame, monitor_area)
+                    .expect("Failed to draw resource monitor");
+                render_times.push(("resources", start.elapsed()));
+            }
+
+            if self.show_debug {
+                self.fps
+                    .draw(frame, main_area)
+                    .expect("Failed to draw debug overlay");
+            }
 
             /*
             for component in self.components.iter_mut() {
@@ -248,6 +2191,230 @@ impl App {
                 }
             } */
         })?;
+        self.fps.record_frame(render_times, frame_start.elapsed());
         Ok(())
     }
 }
+
+/// The footer's text and the columns and action of each clickable key hint, when drawn in `area`
+fn footer(area: Rect) -> (String, Vec<(Range<u16>, Action)>) {
+    let mut footer = "Author: Joseph Guhlin -- ".to_string();
+    let mut hints = Vec::new();
+    for (i, (hint, action)) in FOOTER_HINTS.iter().enumerate() {
+        if i > 0 {
+            footer.push_str(" | ");
+        }
+        let start = area.x + footer.chars().count() as u16;
+        footer.push_str(hint);
+        if let Some(action) = action {
+            hints.push((start..start + hint.chars().count() as u16, action.clone()));
+        }
+    }
+    (footer, hints)
+}
+
+/// First unused `<stem>.partial-N.bam` next to `output`
+fn partial_output_path(output: &Path) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (1..)
+        .map(|n| output.with_file_name(format!("{stem}.partial-{n}.bam")))
+        .find(|path| !path.exists())
+        .expect("an unused partial output name")
+}
+
+/// Standard base64 with padding, as OSC 52 expects
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Put text on the terminal's clipboard with an OSC 52 escape sequence, which also works over ssh
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.config.data_dir =
+            std::env::temp_dir().join(format!("fishtank-app-{}", std::process::id()));
+        config
+    }
+
+    #[test]
+    fn test_rates() {
+        let mut config = config();
+        config.display.tick_rate = 2.0;
+        config.display.frame_rate = 30.0;
+        let app = App::with_config(config.clone(), None, None).unwrap();
+        assert_eq!((app.tick_rate, app.frame_rate), (2.0, 30.0));
+        // The command line wins over the config file
+        let app = App::with_config(config.clone(), Some(8.0), Some(120.0)).unwrap();
+        assert_eq!((app.tick_rate, app.frame_rate), (8.0, 120.0));
+
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(App::with_config(config.clone(), Some(rate), None).is_err());
+            assert!(App::with_config(config.clone(), None, Some(rate)).is_err());
+        }
+        config.display.idle_frame_rate = f64::NAN;
+        assert!(App::with_config(config, None, None).is_err());
+    }
+
+    #[test]
+    fn test_idle_frame_rate() {
+        let mut config = config();
+        config.display.adaptive = true;
+        config.display.idle_frame_rate = 2.0;
+        config.display.idle_after_seconds = 5;
+        let mut app = App::with_config(config, None, Some(60.0)).unwrap();
+        assert_eq!(app.next_frame_rate(), None);
+
+        let long_ago = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+        app.last_input = long_ago;
+        assert_eq!(app.next_frame_rate(), Some(2.0));
+        assert_eq!(app.next_frame_rate(), None);
+
+        // The fish tank keeps the full rate however long it's been since a key was pressed
+        app.animating = true;
+        assert_eq!(app.next_frame_rate(), Some(60.0));
+        app.animating = false;
+        assert_eq!(app.next_frame_rate(), Some(2.0));
+
+        app.last_input = Instant::now();
+        assert_eq!(app.next_frame_rate(), Some(60.0));
+
+        app.config.display.adaptive = false;
+        app.last_input = long_ago;
+        assert_eq!(app.next_frame_rate(), None);
+    }
+
+    fn click(column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn test_click_footer_hint() {
+        let mut app = App::with_config(config(), None, None).unwrap();
+        let area = Rect::new(2, 40, 120, 1);
+        let (text, hints) = footer(area);
+        app.footer_hints = (area.y, hints);
+        let column = |hint: &str| area.x + text.find(hint).unwrap() as u16;
+
+        app.handle_mouse_event(click(column("[Tab] Next Pane") + 3, 40))
+            .unwrap();
+        assert_eq!(app.action_rx.try_recv().ok(), Some(Action::NextPane));
+        app.handle_mouse_event(click(column("[`] Configuration"), 40))
+            .unwrap();
+        assert_eq!(
+            app.action_rx.try_recv().ok(),
+            Some(Action::ToggleSettingsDisplay)
+        );
+        // Navigate has no action, and the row above isn't the footer
+        app.handle_mouse_event(click(column("[Up/Down] Navigate"), 40))
+            .unwrap();
+        app.handle_mouse_event(click(column("[q] Quit"), 39))
+            .unwrap();
+        assert!(app.action_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_click_pane() {
+        let mut app = App::with_config(config(), None, None).unwrap();
+        app.pane_areas = [
+            Rect::new(0, 20, 30, 10),
+            Rect::new(0, 0, 30, 20),
+            Rect::new(30, 22, 90, 8),
+        ];
+        app.jobs.area = app.pane_areas[2];
+        app.handle_mouse_event(click(40, 25)).unwrap();
+        assert_eq!(app.has_focus, 2);
+        assert!(app.jobs.has_focus && !app.projects.has_focus);
+        // No jobs yet, so there's nothing to select
+        assert_eq!(app.jobs.selected, None);
+
+        app.handle_mouse_event(MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            ..click(5, 5)
+        })
+        .unwrap();
+        assert_eq!(app.has_focus, 1);
+        assert_eq!(app.action_rx.try_recv().ok(), Some(Action::SelectNext));
+
+        app.projects.items = vec![Project::default(), Project::default()];
+        app.projects.area = app.pane_areas[1];
+        app.handle_mouse_event(click(5, 2)).unwrap();
+        assert_eq!(app.projects.selected, Some(1));
+        // Below the last project
+        app.handle_mouse_event(click(5, 10)).unwrap();
+        assert_eq!(app.projects.selected, Some(1));
+    }
+
+    #[test]
+    fn test_resume_restores_partial_output() {
+        let mut app = App::with_config(config(), None, None).unwrap();
+        let dir = std::env::temp_dir().join(format!("fishtank-resume-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("calls.bam");
+        std::fs::write(&output, "partial").unwrap();
+        let job = Job {
+            id: 1,
+            project: "missing".to_string(),
+            run: BasecallRun {
+                output_path: output.display().to_string(),
+                ..Default::default()
+            },
+            resume_from: None,
+            kind: JobKind::Basecall,
+        };
+        app.jobs.add_interrupted(job, BasecallStatus::Failed);
+        app.jobs.selected = Some(0);
+
+        // No project records the basecall, so the partial output stays where it was
+        assert!(app.resume_job().is_err());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "partial");
+        assert!(!partial_output_path(&output).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xff, 0xfe, 0xfd, 0x00]), "//79AA==");
+    }
+}
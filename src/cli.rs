@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
 use crate::config::{get_config_dir, get_data_dir};
@@ -5,13 +7,19 @@ use crate::config::{get_config_dir, get_data_dir};
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
 pub struct Cli {
-    /// Tick rate, i.e. number of ticks per second
-    #[arg(short, long, value_name = "FLOAT", default_value_t = 4.0)]
-    pub tick_rate: f64,
-
-    /// Frame rate, i.e. number of frames per second
-    #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
-    pub frame_rate: f64,
+    /// Tick rate, i.e. number of ticks per second [default: display.tick_rate in the config
+    /// file, or 4]
+    #[arg(short, long, value_name = "FLOAT")]
+    pub tick_rate: Option<f64>,
+
+    /// Frame rate, i.e. number of frames per second [default: display.frame_rate in the config
+    /// file, or 60]
+    #[arg(short, long, value_name = "FLOAT")]
+    pub frame_rate: Option<f64>,
+
+    /// Preview importing every MinKNOW run directory under DIR as a project
+    #[arg(long, value_name = "DIR")]
+    pub import: Option<PathBuf>,
 }
 
 const VERSION_MESSAGE: &str = concat!(
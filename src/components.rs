@@ -9,22 +9,46 @@ use tokio::sync::mpsc::UnboundedSender;
 use crate::{action::Action, config::Config, tui::Event};
 
 // Side bar components
+pub mod job_list;
 pub mod project_list;
 pub mod software_list;
 
 // Main area components
 pub mod fishtank;
+pub mod import_preview;
+pub mod launch_preview;
+pub mod lifecycle_preview;
 pub mod project_editor;
+pub mod project_view;
+pub mod reference_list;
+pub mod resource_monitor;
+pub mod run_charts;
 pub mod settings;
+pub mod storage_view;
+pub mod throughput_view;
+
+// Overlays
+pub mod fps;
 
 pub mod utils;
 
+pub use job_list::JobList;
 pub use project_list::ProjectList;
 pub use software_list::SoftwareList;
 // pub use project_editor::ProjectEditor;
 pub use fishtank::Fishtank;
-pub use utils::*;
+pub use fps::FpsCounter;
+pub use import_preview::ImportPreview;
+pub use launch_preview::LaunchPreview;
+pub use lifecycle_preview::LifecyclePreview;
+pub use project_view::ProjectView;
+pub use reference_list::ReferenceList;
+pub use resource_monitor::ResourceMonitor;
+pub use run_charts::RunCharts;
 pub use settings::Settings;
+pub use storage_view::StorageView;
+pub use throughput_view::ThroughputView;
+pub use utils::*;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 ///
@@ -1,11 +1,14 @@
-use std::time::Instant;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 use color_eyre::Result;
 use ratatui::{
-    layout::{Constraint, Layout, Rect},
-    style::{Style, Stylize},
-    text::Span,
-    widgets::Paragraph,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
@@ -13,6 +16,13 @@ use super::Component;
 
 use crate::action::Action;
 
+/// Actions listed in the overlay, newest first
+const RECENT_ACTIONS: usize = 8;
+/// Long actions, e.g. ones carrying QC results, are cut to fit the overlay
+const ACTION_WIDTH: usize = 44;
+
+/// Debug overlay with the tick and frame rates, the action queue, how long each component took
+/// to draw and the most recent actions
 #[derive(Debug, Clone, PartialEq)]
 pub struct FpsCounter {
     last_tick_update: Instant,
@@ -22,6 +32,15 @@ pub struct FpsCounter {
     last_frame_update: Instant,
     frame_count: u32,
     frames_per_second: f64,
+
+    /// Actions waiting when the queue was last drained, and the most seen in the last second
+    queue_depth: usize,
+    peak_queue_depth: usize,
+    /// Draw time of each component in the last frame, in the order they were drawn
+    render_times: Vec<(&'static str, Duration)>,
+    /// Time to draw and flush the whole last frame
+    frame_time: Duration,
+    recent_actions: VecDeque<String>,
 }
 
 impl Default for FpsCounter {
@@ -39,6 +58,11 @@ impl FpsCounter {
             last_frame_update: Instant::now(),
             frame_count: 0,
             frames_per_second: 0.0,
+            queue_depth: 0,
+            peak_queue_depth: 0,
+            render_times: Vec::new(),
+            frame_time: Duration::ZERO,
+            recent_actions: VecDeque::new(),
         }
     }
 
@@ -50,6 +74,7 @@ impl FpsCounter {
             self.ticks_per_second = self.tick_count as f64 / elapsed;
             self.last_tick_update = now;
             self.tick_count = 0;
+            self.peak_queue_depth = self.queue_depth;
         }
         Ok(())
     }
@@ -65,6 +90,26 @@ impl FpsCounter {
         }
         Ok(())
     }
+
+    pub fn record_queue_depth(&mut self, depth: usize) {
+        self.queue_depth = depth;
+        self.peak_queue_depth = self.peak_queue_depth.max(depth);
+    }
+
+    pub fn record_action(&mut self, action: &Action) {
+        let mut text = format!("{action:?}");
+        if let Some((end, _)) = text.char_indices().nth(ACTION_WIDTH) {
+            text.truncate(end);
+            text.push('…');
+        }
+        self.recent_actions.push_front(text);
+        self.recent_actions.truncate(RECENT_ACTIONS);
+    }
+
+    pub fn record_frame(&mut self, render_times: Vec<(&'static str, Duration)>, frame: Duration) {
+        self.render_times = render_times;
+        self.frame_time = frame;
+    }
 }
 
 impl Component for FpsCounter {
@@ -78,14 +123,55 @@ impl Component for FpsCounter {
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
-        let [top, _] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
-        let message = format!(
-            "{:.2} ticks/sec, {:.2} FPS",
-            self.ticks_per_second, self.frames_per_second
+        let milliseconds = |duration: Duration| format!("{:.2} ms", duration.as_secs_f64() * 1e3);
+        let label = |text: &str| Span::styled(format!("{text:<18}"), Style::new().dim());
+        let mut lines = vec![
+            Line::from(format!(
+                "{:.2} ticks/sec, {:.2} FPS",
+                self.ticks_per_second, self.frames_per_second
+            )),
+            Line::from(vec![
+                label("Action queue"),
+                Span::raw(format!(
+                    "{} (peak {})",
+                    self.queue_depth, self.peak_queue_depth
+                )),
+            ]),
+            Line::from(vec![
+                label("Last frame"),
+                Span::raw(milliseconds(self.frame_time)),
+            ]),
+        ];
+        lines.extend(self.render_times.iter().map(|(component, duration)| {
+            Line::from(vec![
+                label(&format!("  {component}")),
+                Span::raw(milliseconds(*duration)),
+            ])
+        }));
+        lines.push(Line::from(Span::styled(
+            "Recent actions",
+            Style::new().dim(),
+        )));
+        lines.extend(
+            self.recent_actions
+                .iter()
+                .map(|action| Line::from(format!("  {action}"))),
+        );
+
+        let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Start)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(ACTION_WIDTH as u16 + 6)])
+            .flex(Flex::End)
+            .areas(area);
+        let overlay = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title("Debug"),
         );
-        let span = Span::styled(message, Style::new().dim());
-        let paragraph = Paragraph::new(span).right_aligned();
-        frame.render_widget(paragraph, top);
+        frame.render_widget(Clear, area);
+        frame.render_widget(overlay, area);
         Ok(())
     }
 }
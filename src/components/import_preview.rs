@@ -0,0 +1,150 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Row, Table, TableState},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::components::utils::*;
+use crate::data::import::{FailedRun, RunImport};
+
+/// Dry run of a bulk import, listing the project each run directory would become
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub items: Vec<RunImport>,
+    /// Runs that couldn't be read, listed after the importable ones
+    pub failed: Vec<FailedRun>,
+    pub selected: Option<usize>,
+}
+
+impl Default for ImportPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImportPreview {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            failed: Vec::new(),
+            selected: None,
+        }
+    }
+
+    /// Runs that would become new projects
+    pub fn new_projects(&self) -> impl Iterator<Item = &RunImport> {
+        self.items.iter().filter(|run| !run.exists)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len() + self.failed.len()
+    }
+}
+
+impl Component for ImportPreview {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ImportPreview(plan) => {
+                self.items = plan.runs;
+                self.failed = plan.failed;
+                self.selected = (self.len() > 0).then_some(0);
+            }
+            Action::SelectNext => self.selected = select_next(self.selected, self.len()),
+            Action::SelectPrevious => self.selected = select_previous(self.selected, self.len()),
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let rows = self.items.iter().map(|run| {
+            let project = &run.project;
+            let (status, style) = if run.exists {
+                ("exists", Style::default().fg(Color::DarkGray))
+            } else {
+                ("new", Style::default().fg(Color::Green))
+            };
+            Row::new([
+                status.to_string(),
+                project.name.clone(),
+                project.sequencing.flow_cell_id.clone(),
+                project.sequencing.kit.clone(),
+                project.basecall_logs().count().to_string(),
+                project
+                    .barcoding
+                    .as_ref()
+                    .map(|barcoding| barcoding.samples.len().to_string())
+                    .unwrap_or_default(),
+            ])
+            .style(style)
+        });
+        let failed = self.failed.iter().map(|run| {
+            let name = run
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| run.path.display().to_string());
+            // Sample sheet errors list each problem on its own line
+            let error = run.error.lines().collect::<Vec<_>>().join("; ");
+            Row::new(["failed".to_string(), format!("{name}: {error}")])
+                .style(Style::default().fg(Color::Red))
+        });
+
+        let title_bottom = Line::from(vec![
+            // Enter import
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("] Import", Style::default()),
+            // Esc cancel
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Cancel", Style::default()),
+        ])
+        .centered();
+
+        let table = Table::new(
+            rows.chain(failed),
+            [
+                Constraint::Length(7),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(14),
+                Constraint::Length(10),
+                Constraint::Length(8),
+            ],
+        )
+        .header(
+            Row::new(["", "Project", "Flow cell", "Kit", "Basecalls", "Samples"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Import preview: {} new of {} runs{}",
+                    self.new_projects().count(),
+                    self.items.len(),
+                    if self.failed.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", {} unreadable", self.failed.len())
+                    }
+                ))
+                .title_bottom(title_bottom),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+        let mut state = TableState::default().with_selected(self.selected);
+        frame.render_stateful_widget(table, area, &mut state);
+        Ok(())
+    }
+}
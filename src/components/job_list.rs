@@ -0,0 +1,266 @@
+use color_eyre::Result;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::components::utils::*;
+use crate::data::{now, project::BasecallStatus, throughput::format_duration};
+use crate::executor::{Job, JobKind};
+
+/// Basecall jobs started this session, local or remote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobList {
+    pub items: Vec<JobEntry>,
+    pub has_focus: bool,
+    /// Index into `items`, which are drawn newest first
+    pub selected: Option<usize>,
+    /// Seconds a job may take, those expected to take longer are marked
+    pub window: Option<u64>,
+    /// Where the list was last drawn and how far it was scrolled, to find clicked jobs
+    pub area: Rect,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobEntry {
+    pub job: Job,
+    pub backend: String,
+    pub status: BasecallStatus,
+    /// Most recent line of output from the job
+    pub progress: String,
+    /// Started this session and still running
+    pub active: bool,
+    /// Predicted wall-clock seconds, from the throughput of earlier runs
+    pub estimate: Option<u64>,
+    /// Seconds since the unix epoch the job was started
+    pub started_at: u64,
+    /// Process running the job's current command, while it's running here
+    pub pid: Option<u32>,
+}
+
+impl Focusable for JobList {
+    fn focus(&mut self) {
+        self.has_focus = true;
+    }
+
+    fn unfocus(&mut self) {
+        self.has_focus = false;
+    }
+}
+
+impl Default for JobList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobList {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            has_focus: false,
+            selected: None,
+            window: None,
+            area: Rect::default(),
+            offset: 0,
+        }
+    }
+
+    pub fn add(&mut self, job: Job, backend: &str, estimate: Option<u64>) {
+        self.items.push(JobEntry {
+            job,
+            backend: backend.to_string(),
+            status: BasecallStatus::InProgress,
+            progress: String::new(),
+            active: true,
+            estimate,
+            started_at: now(),
+            pid: None,
+        });
+    }
+
+    /// List an unfinished run from a previous session so it can be resumed
+    pub fn add_interrupted(&mut self, job: Job, status: BasecallStatus) {
+        self.items.push(JobEntry {
+            job,
+            backend: "history".to_string(),
+            status,
+            progress: "Interrupted, partial output on disk".to_string(),
+            active: false,
+            estimate: None,
+            started_at: 0,
+            pid: None,
+        });
+    }
+
+    pub fn get(&self, id: usize) -> Option<&JobEntry> {
+        self.items.iter().find(|entry| entry.job.id == id)
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut JobEntry> {
+        self.items.iter_mut().find(|entry| entry.job.id == id)
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        self.items.retain(|entry| entry.job.id != id);
+        self.selected = self.selected.filter(|&i| i < self.items.len());
+    }
+
+    /// Runtime estimate for the job's line, `None` once it has finished
+    fn estimate_span(&self, entry: &JobEntry) -> Option<Span<'static>> {
+        let estimate = entry.estimate.filter(|_| entry.active)?;
+        let elapsed = now().saturating_sub(entry.started_at);
+        let text = if elapsed < estimate {
+            format!(" ~{} left", format_duration(estimate - elapsed))
+        } else {
+            " overdue".to_string()
+        };
+        Some(match self.window {
+            Some(window) if estimate > window => Span::styled(
+                format!("{text}, over the {} window", format_duration(window)),
+                Style::default().fg(Color::Red),
+            ),
+            _ => Span::styled(text, Style::default().fg(Color::DarkGray)),
+        })
+    }
+
+    /// Id, name and process id of every job running here, for the resource monitor
+    pub fn running_processes(&self) -> Vec<(usize, String, u32)> {
+        self.items
+            .iter()
+            .filter(|entry| entry.active)
+            .filter_map(|entry| Some((entry.job.id, entry.job.name(), entry.pid?)))
+            .collect()
+    }
+
+    /// The selected entry, counting from the newest
+    pub fn selected_entry(&self) -> Option<&JobEntry> {
+        self.selected.and_then(|i| self.items.iter().rev().nth(i))
+    }
+}
+
+impl Component for JobList {
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+            // Each job takes two rows, its name and its latest progress
+            if let Some(i) =
+                clicked_item(self.area, self.offset, 2, mouse.row).filter(|&i| i < self.items.len())
+            {
+                self.selected = Some(i);
+            }
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::JobProgress(id, line) => {
+                if let Some(entry) = self.get_mut(id) {
+                    entry.progress = line;
+                }
+            }
+            Action::JobProcess(id, pid) => {
+                if let Some(entry) = self.get_mut(id) {
+                    entry.pid = Some(pid);
+                }
+            }
+            Action::JobStatus(id, status) => {
+                let Some(entry) = self.get_mut(id) else {
+                    return Ok(None);
+                };
+                entry.status = status;
+                entry.active = false;
+                entry.pid = None;
+                return Ok(match status {
+                    // A conversion has only finished once its read counts are verified
+                    BasecallStatus::Success
+                        if matches!(entry.job.kind, JobKind::Convert { .. }) =>
+                    {
+                        None
+                    }
+                    BasecallStatus::Success => Some(Action::Notify(entry.job.notification(None))),
+                    BasecallStatus::Failed => Some(Action::Notify(
+                        entry.job.notification(Some(entry.progress.clone())),
+                    )),
+                    BasecallStatus::InProgress => None,
+                });
+            }
+            Action::SelectNext => self.selected = select_next(self.selected, self.items.len()),
+            Action::SelectPrevious => {
+                self.selected = select_previous(self.selected, self.items.len())
+            }
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let list_items = self.items.iter().rev().map(|entry| {
+            let (status, color) = match entry.status {
+                BasecallStatus::InProgress if !entry.active => ("stopped", Color::Red),
+                BasecallStatus::InProgress => ("running", Color::Yellow),
+                BasecallStatus::Success => ("done", Color::Green),
+                BasecallStatus::Failed => ("failed", Color::Red),
+            };
+            let mut header = vec![
+                Span::styled(format!("{:<8}", status), Style::default().fg(color)),
+                Span::styled(
+                    format!("{} / {}", entry.job.project, entry.job.name()),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" [{}]", entry.backend),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ];
+            header.extend(self.estimate_span(entry));
+            ListItem::new(vec![
+                Line::from(header),
+                Line::from(Span::styled(
+                    format!("        {}", entry.progress),
+                    Style::default().fg(Color::Gray),
+                )),
+            ])
+        });
+
+        // If we have focus, add key bindings to the bottom border
+        let title_bottom = if self.has_focus {
+            Line::from(vec![
+                // R resume
+                Span::styled(" [", Style::default()),
+                Span::styled("R", Style::default().fg(Color::Yellow)),
+                Span::styled("] Resume", Style::default()),
+            ])
+            .centered()
+        } else {
+            Line::from(vec![])
+        };
+
+        let list = List::new(list_items)
+            .block(
+                Block::default()
+                    .border_style(focus_border_style(self.has_focus))
+                    .borders(Borders::ALL)
+                    .title("Jobs")
+                    .title_bottom(title_bottom),
+            )
+            .style(Style::default().fg(Color::White))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        let mut state = ListState::default().with_selected(self.selected);
+        frame.render_stateful_widget(list, area, &mut state);
+        self.area = area;
+        self.offset = state.offset();
+        Ok(())
+    }
+}
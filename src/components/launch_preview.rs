@@ -0,0 +1,123 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::components::utils::*;
+use crate::data::{launch::LaunchPlan, storage::format_size, throughput::format_duration};
+
+/// The command, input, output and estimates of a basecall, shown before it's launched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchPreview {
+    pub plan: Option<LaunchPlan>,
+}
+
+impl Default for LaunchPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LaunchPreview {
+    pub fn new() -> Self {
+        Self { plan: None }
+    }
+}
+
+impl Component for LaunchPreview {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::LaunchPreview(plan) => self.plan = Some(plan),
+            Action::CancelLaunch => self.plan = None,
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let Some(plan) = &self.plan else {
+            return Ok(());
+        };
+        let label =
+            |text: &str| Span::styled(format!("{text:<12}"), Style::default().fg(Color::Gray));
+        let output = match plan.free_space {
+            Some(free) if free < plan.estimated_output => Span::styled(
+                format!(
+                    "about {}, but only {} free",
+                    format_size(plan.estimated_output),
+                    format_size(free)
+                ),
+                Style::default().fg(Color::Red),
+            ),
+            Some(free) => Span::raw(format!(
+                "about {} of {} free",
+                format_size(plan.estimated_output),
+                format_size(free)
+            )),
+            None => Span::raw(format!("about {}", format_size(plan.estimated_output))),
+        };
+        let runtime = match plan.estimated_runtime {
+            Some(seconds) => format!("{} on {}", format_duration(seconds), plan.host),
+            None => format!("unknown, no finished runs on {} yet", plan.host),
+        };
+        let lines = vec![
+            Line::from(vec![
+                label("Preset"),
+                Span::raw(&plan.run.basecaller.config),
+            ]),
+            Line::from(vec![
+                label("Input"),
+                Span::raw(format!(
+                    "{} files, {} in {}",
+                    plan.input_files,
+                    format_size(plan.input_bytes),
+                    plan.run.path
+                )),
+            ]),
+            Line::from(vec![label("Output"), Span::raw(&plan.run.output_path)]),
+            Line::from(vec![label("Disk usage"), output]),
+            Line::from(vec![label("Runtime"), Span::raw(runtime)]),
+            Line::from(""),
+            Line::from(Span::styled("Command", Style::default().fg(Color::Gray))),
+            Line::from(plan.command.as_str()),
+        ];
+
+        let title_bottom = Line::from(vec![
+            // Enter launch
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("] Launch", Style::default()),
+            // y copy
+            Span::styled(" [", Style::default()),
+            Span::styled("y", Style::default().fg(Color::Yellow)),
+            Span::styled("] Copy Command", Style::default()),
+            // s save
+            Span::styled(" [", Style::default()),
+            Span::styled("s", Style::default().fg(Color::Yellow)),
+            Span::styled("] Save Script", Style::default()),
+            // Esc cancel
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Cancel", Style::default()),
+        ])
+        .centered();
+
+        let preview = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title(format!("Basecall {}", plan.project))
+                .title_bottom(title_bottom),
+        );
+        frame.render_widget(preview, area);
+        Ok(())
+    }
+}
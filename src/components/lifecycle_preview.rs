@@ -0,0 +1,123 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Row, Table, TableState},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::components::utils::*;
+use crate::data::{
+    lifecycle::{LifecycleAction, LifecycleStep},
+    storage::format_size,
+};
+
+/// Files a project's lifecycle rules would compress, move or delete, shown before anything is
+/// touched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecyclePreview {
+    pub project: String,
+    pub steps: Vec<LifecycleStep>,
+    pub selected: Option<usize>,
+}
+
+impl Default for LifecyclePreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LifecyclePreview {
+    pub fn new() -> Self {
+        Self {
+            project: String::new(),
+            steps: Vec::new(),
+            selected: None,
+        }
+    }
+}
+
+impl Component for LifecyclePreview {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::LifecyclePreview(project, steps) => {
+                self.project = project;
+                self.steps = steps;
+                self.selected = (!self.steps.is_empty()).then_some(0);
+            }
+            Action::SelectNext => self.selected = select_next(self.selected, self.steps.len()),
+            Action::SelectPrevious => {
+                self.selected = select_previous(self.selected, self.steps.len())
+            }
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let rows = self.steps.iter().map(|step| {
+            let (action, style) = match &step.action {
+                LifecycleAction::Compress => ("compress", Style::default()),
+                LifecycleAction::Archive { .. } => ("move", Style::default().fg(Color::Yellow)),
+                LifecycleAction::Delete => ("delete", Style::default().fg(Color::Red)),
+            };
+            Row::new([
+                action.to_string(),
+                step.path.clone(),
+                step.target.clone().unwrap_or_default(),
+                format_size(step.bytes),
+            ])
+            .style(style)
+        });
+
+        let title_bottom = Line::from(vec![
+            // Enter apply
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("] Apply", Style::default()),
+            // Esc cancel
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Cancel", Style::default()),
+        ])
+        .centered();
+
+        let bytes = self.steps.iter().map(|step| step.bytes).sum();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(9),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+            ],
+        )
+        .header(
+            Row::new(["Action", "File", "Target", "Size"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Lifecycle preview for {}: {} files, {}",
+                    self.project,
+                    self.steps.len(),
+                    format_size(bytes)
+                ))
+                .title_bottom(title_bottom),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+        let mut state = TableState::default().with_selected(self.selected);
+        frame.render_stateful_widget(table, area, &mut state);
+        Ok(())
+    }
+}
@@ -1,11 +1,12 @@
-use std::{borrow::Cow, time::Instant};
+use std::time::Instant;
 
 use color_eyre::Result;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, List, Paragraph},
+    widgets::{Block, Borders, List, ListState, Paragraph, Row, Table},
     Frame,
 };
 use serde::{Deserialize, Serialize};
@@ -14,11 +15,16 @@ use super::Component;
 
 use crate::action::Action;
 use crate::components::utils::*;
+use crate::data::project::Project;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProjectList {
-    pub items: Vec<Project>, // todo - replace with software struct
+    pub items: Vec<Project>,
     pub has_focus: bool,
+    pub selected: Option<usize>,
+    /// Where the list was last drawn and how far it was scrolled, to find clicked rows
+    pub area: Rect,
+    pub offset: usize,
 }
 
 impl Focusable for ProjectList {
@@ -42,11 +48,88 @@ impl ProjectList {
         Self {
             items: Vec::new(),
             has_focus: true,
+            selected: None,
+            area: Rect::default(),
+            offset: 0,
         }
     }
+
+    pub fn selected_project(&self) -> Option<&Project> {
+        self.selected.and_then(|i| self.items.get(i))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Project> {
+        self.items.iter().find(|project| project.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Project> {
+        self.items.iter_mut().find(|project| project.name == name)
+    }
+}
+
+/// Keys for the selected project, listed in full by [`ProjectList::draw_keys`]
+const KEYS: &[(&str, &str)] = &[
+    ("a", "Add a project"),
+    ("r", "Archive the project"),
+    ("t", "Show archived projects"),
+    ("b", "Basecall with a preset"),
+    ("c", "Compute QC"),
+    ("g", "Yield and channel charts"),
+    ("d", "Demultiplex the latest basecall"),
+    ("A", "Align the latest basecall"),
+    ("f", "Reference genomes"),
+    ("i", "Import the MinKNOW sample sheet"),
+    ("e", "Export a sample sheet"),
+    ("p", "Convert FAST5 to POD5"),
+    ("P", "Convert to POD5 and remove the FAST5"),
+    ("m", "Checksum the data into a manifest"),
+    ("v", "Verify the data against its manifest"),
+    ("l", "Preview lifecycle rules"),
+    ("u", "Storage usage"),
+    ("T", "Basecall throughput"),
+    ("M", "Resource monitor"),
+    ("R", "Resume the selected job"),
+    ("F12", "Debug overlay"),
+];
+
+impl ProjectList {
+    /// Every project key, for when the hints on the list don't fit them all
+    pub fn draw_keys(&self, frame: &mut Frame, area: Rect) {
+        let rows = KEYS.iter().map(|(key, description)| {
+            Row::new([
+                Span::styled(*key, Style::default().fg(Color::Yellow)),
+                Span::raw(*description),
+            ])
+        });
+        let title_bottom = Line::from(vec![
+            Span::styled(" [", Style::default()),
+            Span::styled("h", Style::default().fg(Color::Yellow)),
+            Span::styled("] Close", Style::default()),
+        ])
+        .centered();
+        let table = Table::new(rows, [Constraint::Length(5), Constraint::Fill(1)]).block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title("Keys")
+                .title_bottom(title_bottom),
+        );
+        frame.render_widget(table, area);
+    }
 }
 
 impl Component for ProjectList {
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+            if let Some(i) =
+                clicked_item(self.area, self.offset, 1, mouse.row).filter(|&i| i < self.items.len())
+            {
+                self.selected = Some(i);
+            }
+        }
+        Ok(None)
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
             Action::Tick => {
@@ -55,6 +138,10 @@ impl Component for ProjectList {
             Action::Render => {
                 // add any logic here that should run on every render
             }
+            Action::SelectNext => self.selected = select_next(self.selected, self.items.len()),
+            Action::SelectPrevious => {
+                self.selected = select_previous(self.selected, self.items.len())
+            }
             _ => {}
         };
         Ok(None)
@@ -65,23 +152,28 @@ impl Component for ProjectList {
         let list_items = self
             .items
             .iter()
-            .map(|item| Span::styled(item, Style::default()));
+            .map(|item| Span::styled(item.to_list_item(), Style::default()));
 
-        // If we have focus, add key bindings to the bottom border
+        // If we have focus, add the main key bindings to the bottom border, the rest are in the
+        // key list so the hints fit the pane
         let title_bottom = if self.has_focus {
             Line::from(vec![
                 // a add
                 Span::styled(" [", Style::default()),
                 Span::styled("a", Style::default().fg(Color::Yellow)),
                 Span::styled("] Add", Style::default()),
-                // r archive
+                // b basecall
+                Span::styled(" [", Style::default()),
+                Span::styled("b", Style::default().fg(Color::Yellow)),
+                Span::styled("] Basecall", Style::default()),
+                // c compute QC
                 Span::styled(" [", Style::default()),
-                Span::styled("r", Style::default().fg(Color::Yellow)),
-                Span::styled("] Archive", Style::default()),
-                // t show archived
+                Span::styled("c", Style::default().fg(Color::Yellow)),
+                Span::styled("] QC", Style::default()),
+                // h key list
                 Span::styled(" [", Style::default()),
-                Span::styled("t", Style::default().fg(Color::Yellow)),
-                Span::styled("] Show Archived", Style::default()),
+                Span::styled("h", Style::default().fg(Color::Yellow)),
+                Span::styled("] Keys", Style::default()),
             ])
             .centered()
         } else {
@@ -100,57 +192,10 @@ impl Component for ProjectList {
             .highlight_style(Style::default().add_modifier(Modifier::BOLD))
             .highlight_symbol(">> ");
 
-        frame.render_widget(list, area);
+        let mut state = ListState::default().with_selected(self.selected);
+        frame.render_stateful_widget(list, area, &mut state);
+        self.area = area;
+        self.offset = state.offset();
         Ok(())
     }
 }
-
-/// Defines the Nanopore sequencing project
-#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct Project {
-    name: String,
-    pub path: String,
-    pub read_count: u64,
-    pub basecall_runs: Vec<BasecallRun>,
-    pub format: RawBasecallsFormat,
-}
-
-impl Project {
-    pub fn to_list_item(&self) -> String {
-        format!("{} ({})", self.name, self.read_count)
-    }
-}
-
-impl<'a> Into<Cow<'a, str>> for &Project {
-    fn into(self) -> Cow<'a, str> {
-        self.to_list_item().into()
-    }
-}
-
-#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum RawBasecallsFormat {
-    Fast5,
-    Slow5,
-    #[default]
-    Pod5,
-}
-
-/// Defines a basecall run within a project
-#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct BasecallRun {
-    pub name: String,
-    pub path: String,
-    pub read_count: u64,
-    pub output_path: String,
-    pub basecaller: Basecaller,
-}
-
-/// Defines the basecaller used to process raw basecalls
-#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct Basecaller {
-    pub name: String,
-    pub version: String,
-    pub path: String,
-    pub config: String,
-    pub model: String,
-}
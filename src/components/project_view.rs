@@ -0,0 +1,273 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::components::utils::*;
+use crate::data::{
+    now,
+    project::{BarcodeResults, Project, ProjectType},
+};
+
+/// Details of the selected project, with its samples and their per-barcode results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectView {
+    pub project: Option<Project>,
+    pub has_focus: bool,
+}
+
+impl Focusable for ProjectView {
+    fn focus(&mut self) {
+        self.has_focus = true;
+    }
+
+    fn unfocus(&mut self) {
+        self.has_focus = false;
+    }
+}
+
+impl Default for ProjectView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProjectView {
+    pub fn new() -> Self {
+        Self {
+            project: None,
+            has_focus: false,
+        }
+    }
+
+    /// One row per sample, then any barcodes with reads but no sample, then unclassified
+    fn sample_rows(project: &Project) -> Vec<Row<'static>> {
+        let results = project.barcode_results();
+        let find = |barcode: &str| results.iter().find(|r| r.barcode == barcode);
+        let stats = |results: Option<&BarcodeResults>| match results {
+            Some(r) => vec![
+                r.read_count.to_string(),
+                r.n_bases.to_string(),
+                format!("{:.1}", r.mean_qscore),
+            ],
+            None => vec!["-".to_string(); 3],
+        };
+
+        let mut rows = Vec::new();
+        let Some(barcoding) = &project.barcoding else {
+            return rows;
+        };
+        for sample in &barcoding.samples {
+            let mut cells = vec![
+                sample.barcode.clone(),
+                sample.name.clone(),
+                sample.alias.clone(),
+            ];
+            cells.extend(stats(find(&sample.barcode)));
+            rows.push(Row::new(cells));
+        }
+        for result in results {
+            if barcoding.sample(&result.barcode).is_some() {
+                continue;
+            }
+            let mut cells = vec![result.barcode.clone(), String::new(), String::new()];
+            cells.extend(stats(Some(result)));
+            rows.push(Row::new(cells).style(Style::default().fg(Color::DarkGray)));
+        }
+        rows
+    }
+}
+
+impl Component for ProjectView {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => {
+                // add any logic here that should run on every tick
+            }
+            Action::Render => {
+                // add any logic here that should run on every render
+            }
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let Some(project) = &self.project else {
+            return Ok(());
+        };
+
+        let block = Block::default()
+            .border_style(focus_border_style(self.has_focus))
+            .borders(Borders::ALL)
+            .title(project.name.clone());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [details_area, samples_area] =
+            Layout::vertical([Constraint::Length(13), Constraint::Fill(1)]).areas(inner);
+
+        let label =
+            |text: &str| Span::styled(format!("{text:<10}"), Style::default().fg(Color::Gray));
+        let project_type = match &project.project_type {
+            ProjectType::DNA(kind) => format!("DNA {kind}"),
+            ProjectType::RNA(info) => format!("RNA {}", info.describe()),
+        };
+        let kit = project
+            .barcoding
+            .as_ref()
+            .map(|barcoding| barcoding.kit.clone())
+            .unwrap_or_else(|| "not barcoded".to_string());
+        let qc = match project.latest_results() {
+            Some(results) => format!(
+                "{} reads, {} bases, N50 {}, mean Q {:.1}",
+                results.read_count, results.n_bases, results.n50, results.mean_qscore
+            ),
+            None => "not computed".to_string(),
+        };
+        let modifications = match project.latest_results() {
+            Some(results) if !results.modifications.is_empty() => results
+                .modifications
+                .iter()
+                .map(|m| {
+                    format!(
+                        "{} {:.1}% of {} calls",
+                        m.name(),
+                        m.percent_modified(),
+                        m.calls
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => "not called".to_string(),
+        };
+        let duplex = match project
+            .latest_results()
+            .and_then(|results| results.duplex.as_ref())
+        {
+            Some(duplex) => format!(
+                "{:.1}% paired, {} duplex reads Q {:.1} vs simplex Q {:.1} ({:+.1})",
+                duplex.duplex_rate(),
+                duplex.duplex_reads,
+                duplex.duplex_mean_qscore,
+                duplex.simplex_mean_qscore,
+                duplex.qscore_gain()
+            ),
+            None => "simplex only".to_string(),
+        };
+        let rna = match project
+            .latest_results()
+            .and_then(|results| results.rna.as_ref())
+        {
+            Some(rna) => {
+                let tails = if rna.poly_a_reads > 0 {
+                    format!(
+                        "poly-A median {} nt over {} reads",
+                        rna.median_poly_a, rna.poly_a_reads
+                    )
+                } else {
+                    "no poly-A tails".to_string()
+                };
+                match rna.sense_percent() {
+                    Some(_) => format!("{tails}, {}", rna.strand()),
+                    None => format!("{tails}, strand unknown until a spliced alignment"),
+                }
+            }
+            None if matches!(project.project_type, ProjectType::RNA(_)) => {
+                "not computed".to_string()
+            }
+            None => "not an RNA project".to_string(),
+        };
+        let mapping = match project
+            .basecall_logs()
+            .filter_map(|log| log.mapping.as_ref())
+            .last()
+        {
+            Some(stats) => format!(
+                "{:.1}% mapped to {}, {:.1}% identity, {:.1}x coverage",
+                stats.mapped_percent(),
+                stats.reference,
+                stats.identity * 100.0,
+                stats.coverage
+            ),
+            None => "not aligned".to_string(),
+        };
+        let integrity = match (&project.verification, project.manifest_at) {
+            (Some(report), _) if report.is_ok() => Span::raw(format!(
+                "{} files verified {}",
+                report.ok,
+                format_age(now().saturating_sub(report.verified_at))
+            )),
+            (Some(report), _) => Span::styled(
+                format!(
+                    "{} missing, {} extra, {} corrupted as of {}",
+                    report.missing,
+                    report.extra,
+                    report.corrupted,
+                    format_age(now().saturating_sub(report.verified_at))
+                ),
+                Style::default().fg(Color::Red),
+            ),
+            (None, Some(at)) => Span::raw(format!(
+                "manifest generated {}, not verified",
+                format_age(now().saturating_sub(at))
+            )),
+            (None, None) => Span::raw("no manifest"),
+        };
+        let details = Paragraph::new(vec![
+            Line::from(project.description.clone()),
+            Line::from(vec![
+                label("Owner"),
+                Span::raw(project.project_owner.clone()),
+            ]),
+            Line::from(vec![label("Type"), Span::raw(project_type)]),
+            Line::from(vec![
+                label("Data"),
+                Span::raw(project.data_location.clone()),
+            ]),
+            Line::from(vec![label("Kit"), Span::raw(kit)]),
+            Line::from(vec![
+                label("Reference"),
+                Span::raw(
+                    project
+                        .reference
+                        .clone()
+                        .unwrap_or_else(|| "none".to_string()),
+                ),
+            ]),
+            Line::from(vec![label("QC"), Span::raw(qc)]),
+            Line::from(vec![label("Modified"), Span::raw(modifications)]),
+            Line::from(vec![label("Duplex"), Span::raw(duplex)]),
+            Line::from(vec![label("RNA"), Span::raw(rna)]),
+            Line::from(vec![label("Mapping"), Span::raw(mapping)]),
+            Line::from(vec![label("Integrity"), integrity]),
+        ]);
+        frame.render_widget(details, details_area);
+
+        let header = Row::new(["Barcode", "Sample", "Alias", "Reads", "Bases", "Mean Q"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let table = Table::new(
+            Self::sample_rows(project),
+            [
+                Constraint::Length(13),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(14),
+                Constraint::Length(6),
+            ],
+        )
+        .header(header)
+        .block(Block::default().borders(Borders::TOP).title("Samples"));
+        frame.render_widget(table, samples_area);
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::components::utils::*;
+use crate::data::reference::Reference;
+
+const FIELDS: [&str; 4] = ["FASTA path", "Name", "Species", "Assembly"];
+
+/// Registered reference genomes, with a form for adding new ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceList {
+    pub references: Vec<Reference>,
+    /// Default reference of the selected project
+    pub project_default: Option<String>,
+    pub selected: Option<usize>,
+    /// Values typed into the add form and the field being edited, while it's open
+    pub form: Option<([String; 4], usize)>,
+}
+
+impl Default for ReferenceList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReferenceList {
+    pub fn new() -> Self {
+        Self {
+            references: Vec::new(),
+            project_default: None,
+            selected: None,
+            form: None,
+        }
+    }
+
+    pub fn selected_reference(&self) -> Option<&Reference> {
+        self.selected.and_then(|i| self.references.get(i))
+    }
+
+    /// Keep the selection on the list after references are added or removed
+    pub fn set_references(&mut self, references: Vec<Reference>) {
+        self.references = references;
+        self.selected = match self.selected {
+            _ if self.references.is_empty() => None,
+            Some(i) => Some(i.min(self.references.len() - 1)),
+            None => Some(0),
+        };
+    }
+
+    fn draw_form(&self, frame: &mut Frame, area: Rect, values: &[String; 4], field: usize) {
+        let [area] = Layout::vertical([Constraint::Length(FIELDS.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let lines = FIELDS
+            .iter()
+            .zip(values)
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let style = if i == field {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                let cursor = if i == field { "_" } else { "" };
+                Line::from(vec![
+                    Span::styled(format!("{label:<12}"), style),
+                    Span::raw(format!("{value}{cursor}")),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let title_bottom = Line::from(vec![
+            Span::styled(" [", Style::default()),
+            Span::styled("Tab", Style::default().fg(Color::Yellow)),
+            Span::styled("] Next Field", Style::default()),
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("] Register", Style::default()),
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Cancel", Style::default()),
+        ])
+        .centered();
+        let form = Paragraph::new(lines).block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title("Add reference")
+                .title_bottom(title_bottom),
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(form, area);
+    }
+}
+
+impl Component for ReferenceList {
+    /// Typing into the add form, which the app only routes keys to while it's open
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some((values, field)) = &mut self.form else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Esc => return Ok(Some(Action::CancelReferenceEntry)),
+            KeyCode::Enter => {
+                let [path, name, species, assembly] = values.clone().map(|v| v.trim().to_string());
+                return Ok(Some(Action::RegisterReference(Reference {
+                    name,
+                    species,
+                    assembly,
+                    path,
+                    ..Default::default()
+                })));
+            }
+            KeyCode::Tab | KeyCode::Down => *field = (*field + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => *field = (*field + FIELDS.len() - 1) % FIELDS.len(),
+            KeyCode::Backspace => {
+                values[*field].pop();
+            }
+            KeyCode::Char(c) => values[*field].push(c),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::AddReference => self.form = Some(Default::default()),
+            Action::CancelReferenceEntry | Action::RegisterReference(_) => self.form = None,
+            Action::SelectNext => self.selected = select_next(self.selected, self.references.len()),
+            Action::SelectPrevious => {
+                self.selected = select_previous(self.selected, self.references.len())
+            }
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let rows = self.references.iter().map(|reference| {
+            let problems = reference.problems();
+            let (status, style) = if problems.is_empty() {
+                ("ready".to_string(), Style::default())
+            } else {
+                (problems.join(", "), Style::default().fg(Color::Red))
+            };
+            let default = if self.project_default.as_ref() == Some(&reference.name) {
+                "*"
+            } else {
+                ""
+            };
+            Row::new([
+                default.to_string(),
+                reference.name.clone(),
+                reference.species.clone(),
+                reference.assembly.clone(),
+                reference.path.clone(),
+                status,
+            ])
+            .style(style)
+        });
+
+        let title_bottom = Line::from(vec![
+            // a add
+            Span::styled(" [", Style::default()),
+            Span::styled("a", Style::default().fg(Color::Yellow)),
+            Span::styled("] Add", Style::default()),
+            // d remove
+            Span::styled(" [", Style::default()),
+            Span::styled("d", Style::default().fg(Color::Yellow)),
+            Span::styled("] Remove", Style::default()),
+            // v validate
+            Span::styled(" [", Style::default()),
+            Span::styled("v", Style::default().fg(Color::Yellow)),
+            Span::styled("] Validate", Style::default()),
+            // Enter set default
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("] Project Default", Style::default()),
+            // Esc close
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Close", Style::default()),
+        ])
+        .centered();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(1),
+                Constraint::Length(14),
+                Constraint::Length(16),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Length(18),
+            ],
+        )
+        .header(
+            Row::new(["", "Name", "Species", "Assembly", "FASTA", "Status"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title("References")
+                .title_bottom(title_bottom),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+        let mut state = TableState::default().with_selected(self.selected);
+        frame.render_stateful_widget(table, area, &mut state);
+
+        if let Some((values, field)) = &self.form {
+            self.draw_form(frame, area, values, *field);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_form_entry() {
+        let mut list = ReferenceList::new();
+        list.update(Action::AddReference).unwrap();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for c in "/refs/hg38.fa".chars() {
+            list.handle_key_event(key(KeyCode::Char(c))).unwrap();
+        }
+        list.handle_key_event(key(KeyCode::Backspace)).unwrap();
+        list.handle_key_event(key(KeyCode::Char('a'))).unwrap();
+        list.handle_key_event(key(KeyCode::Tab)).unwrap();
+        list.handle_key_event(key(KeyCode::Tab)).unwrap();
+        for c in "Homo sapiens".chars() {
+            list.handle_key_event(key(KeyCode::Char(c))).unwrap();
+        }
+        assert_eq!(
+            list.handle_key_event(key(KeyCode::Enter)).unwrap(),
+            Some(Action::RegisterReference(Reference {
+                path: "/refs/hg38.fa".to_string(),
+                species: "Homo sapiens".to_string(),
+                ..Default::default()
+            }))
+        );
+    }
+}
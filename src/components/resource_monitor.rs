@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Sparkline},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::data::storage::format_size;
+use crate::resources::ResourceSample;
+
+/// Rows taken by each metric, a label and a sparkline underneath
+const METRIC_HEIGHT: u16 = 3;
+
+/// Side pane with CPU, memory, volume throughput and running jobs' usage over recent minutes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceMonitor {
+    /// Oldest first
+    pub history: VecDeque<ResourceSample>,
+    /// Samples kept, enough to cover the configured minutes
+    pub capacity: usize,
+    /// Why sampling failed, e.g. no `/proc` on this system
+    pub error: Option<String>,
+}
+
+impl Default for ResourceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceMonitor {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::new(),
+            capacity: 1,
+            error: None,
+        }
+    }
+
+    pub fn push(&mut self, sample: ResourceSample) {
+        self.error = None;
+        self.history.push_back(sample);
+        while self.history.len() > self.capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// The last `width` values of a metric, oldest first, with gaps where it wasn't sampled
+    fn series(
+        &self,
+        width: u16,
+        value: impl Fn(&ResourceSample) -> Option<u64>,
+    ) -> Vec<Option<u64>> {
+        let skip = self.history.len().saturating_sub(width as usize);
+        self.history.iter().skip(skip).map(value).collect()
+    }
+
+    fn draw_metric(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        label: Line,
+        color: Color,
+        max: Option<u64>,
+        value: impl Fn(&ResourceSample) -> Option<u64>,
+    ) {
+        let [label_area, chart_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        frame.render_widget(Paragraph::new(label), label_area);
+        let mut sparkline = Sparkline::default()
+            .data(self.series(chart_area.width, value))
+            .style(Style::default().fg(color));
+        if let Some(max) = max {
+            sparkline = sparkline.max(max);
+        }
+        frame.render_widget(sparkline, chart_area);
+    }
+}
+
+impl Component for ResourceMonitor {
+    fn update(&mut self, _action: Action) -> Result<Option<Action>> {
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let block = Block::default().borders(Borders::ALL).title("Resources");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some(latest) = self.history.back() else {
+            let message = match &self.error {
+                Some(error) => format!("Unavailable: {error}"),
+                None => "Sampling...".to_string(),
+            };
+            frame.render_widget(
+                Paragraph::new(Span::styled(message, Style::default().fg(Color::Gray))),
+                inner,
+            );
+            return Ok(());
+        };
+
+        let metrics = 2 + latest.volumes.len() + latest.jobs.len();
+        let areas = Layout::vertical(
+            std::iter::repeat_n(Constraint::Length(METRIC_HEIGHT), metrics)
+                .chain([Constraint::Fill(1)]),
+        )
+        .split(inner);
+        let label = |name: String, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{name:<10}"), Style::default().fg(Color::Gray)),
+                Span::raw(value),
+            ])
+        };
+
+        self.draw_metric(
+            frame,
+            areas[0],
+            label("CPU".to_string(), format!("{:.0}%", latest.cpu_percent)),
+            Color::Cyan,
+            Some(100),
+            |sample| Some(sample.cpu_percent as u64),
+        );
+        self.draw_metric(
+            frame,
+            areas[1],
+            label(
+                "Memory".to_string(),
+                format!(
+                    "{} / {}",
+                    format_size(latest.memory_used),
+                    format_size(latest.memory_total)
+                ),
+            ),
+            Color::Magenta,
+            Some(latest.memory_total),
+            |sample| Some(sample.memory_used),
+        );
+        for (i, volume) in latest.volumes.iter().enumerate() {
+            self.draw_metric(
+                frame,
+                areas[2 + i],
+                label(
+                    volume.name.clone(),
+                    format!(
+                        "R {}/s W {}/s",
+                        format_size(volume.read_per_second),
+                        format_size(volume.write_per_second)
+                    ),
+                ),
+                Color::Yellow,
+                None,
+                |sample| {
+                    let volume = sample.volumes.iter().find(|v| v.name == volume.name)?;
+                    Some(volume.read_per_second + volume.write_per_second)
+                },
+            );
+        }
+        for (i, job) in latest.jobs.iter().enumerate() {
+            self.draw_metric(
+                frame,
+                areas[2 + latest.volumes.len() + i],
+                label(
+                    job.name.clone(),
+                    format!(
+                        "{:.0}% {} ({} procs)",
+                        job.cpu_percent,
+                        format_size(job.memory),
+                        job.processes
+                    ),
+                ),
+                Color::Green,
+                None,
+                |sample| {
+                    let usage = sample.jobs.iter().find(|usage| usage.id == job.id)?;
+                    Some(usage.cpu_percent as u64)
+                },
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    symbols::Marker,
+    text::Line,
+    widgets::{
+        canvas::{Canvas, Context, Points},
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Dataset, GraphType, Paragraph,
+    },
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::data::duplex::DuplexResults;
+use crate::data::project::{BasecallResults, ChannelActivity, YieldBin};
+use crate::data::qc::YIELD_BIN_SECONDS;
+use crate::data::rna::RnaResults;
+
+/// Heatmap colours from least to most active channel
+const HEAT: [Color; 5] = [
+    Color::Blue,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Red,
+];
+
+/// Yield, N50 and channel activity charts for a run's sequencing summary, under the pairing
+/// stats of duplex runs and the poly-A tail lengths of RNA runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunCharts {
+    pub results: Option<BasecallResults>,
+}
+
+impl Default for RunCharts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunCharts {
+    pub fn new() -> Self {
+        Self { results: None }
+    }
+}
+
+/// Columns and rows of the heatmap for a flow cell with `channels` channels
+///
+/// MinION flow cells have 512 channels and PromethION ones 2048 in these summaries, laid out
+/// in channel order rather than their physical positions.
+pub fn grid_size(channels: u32) -> (u32, u32) {
+    match channels {
+        0..=512 => (32, 16),
+        513..=2048 => (64, 32),
+        _ => {
+            let columns = (channels as f64).sqrt().ceil() as u32;
+            (columns, channels.div_ceil(columns))
+        }
+    }
+}
+
+/// Cell of channel `channel`, numbered from 1, with row 0 at the top
+pub fn grid_position(channel: u32, columns: u32) -> (u32, u32) {
+    let index = channel.saturating_sub(1);
+    (index % columns, index / columns)
+}
+
+fn line_chart<'a>(
+    title: String,
+    data: &'a [(f64, f64)],
+    y_label: &'a str,
+    color: Color,
+) -> Chart<'a> {
+    let x_max = data.last().map(|&(x, _)| x).unwrap_or(1.0).max(1.0);
+    let y_max = data.iter().map(|&(_, y)| y).fold(0.0, f64::max).max(1.0);
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(data);
+    Chart::new(vec![dataset])
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .x_axis(
+            Axis::default()
+                .title("hours")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, x_max])
+                .labels(["0".to_string(), format!("{x_max:.0}")]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(y_label)
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, y_max])
+                .labels(["0".to_string(), format!("{y_max:.1}")]),
+        )
+}
+
+/// Duplex rate and qscore gain, each on its own line as they're judged separately
+fn duplex_panel(duplex: &DuplexResults) -> Paragraph<'_> {
+    Paragraph::new(vec![
+        Line::from(format!(
+            "Duplex rate {:.1}%: {} of {} simplex reads paired into {} duplex reads",
+            duplex.duplex_rate(),
+            duplex.paired_reads,
+            duplex.simplex_reads,
+            duplex.duplex_reads
+        )),
+        Line::from(format!(
+            "Qscore gain {:+.1}: duplex mean Q {:.1}, simplex mean Q {:.1}",
+            duplex.qscore_gain(),
+            duplex.duplex_mean_qscore,
+            duplex.simplex_mean_qscore
+        )),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("Duplex"))
+}
+
+/// Reads by poly-A tail length, titled with the median and how many reads are sense
+fn poly_a_chart<'a>(rna: &RnaResults, bars: &'a [Bar<'a>]) -> BarChart<'a> {
+    BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Poly-A tail length, median {} nt over {} reads, {}",
+            rna.median_poly_a,
+            rna.poly_a_reads,
+            rna.strand()
+        )))
+        .data(BarGroup::default().bars(bars))
+        .bar_width(3)
+        .bar_style(Style::default().fg(Color::Magenta))
+}
+
+/// Index into `HEAT` for a channel's reads, so only the busiest channel is the hottest colour
+fn heat_level(reads: u64, busiest: u64) -> usize {
+    (reads * (HEAT.len() as u64 - 1) / busiest.max(1)) as usize
+}
+
+/// Yield of the last complete bin as a percentage of the best bin. The last bin is still
+/// filling, so it's left out, and a run with only that bin has no rate yet.
+fn current_rate(yield_over_time: &[YieldBin]) -> Option<f64> {
+    let [complete @ .., _] = yield_over_time else {
+        return None;
+    };
+    let peak = complete.iter().map(|bin| bin.bases).max()?.max(1);
+    let current = complete.last()?.bases;
+    Some(current as f64 / peak as f64 * 100.0)
+}
+
+fn heatmap(channels: &[ChannelActivity]) -> Canvas<'_, impl Fn(&mut Context) + '_> {
+    let count = channels.iter().map(|c| c.channel).max().unwrap_or(0);
+    let (columns, rows) = grid_size(count);
+    let busiest = channels.iter().map(|c| c.reads).max().unwrap_or(0).max(1);
+    Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Channel activity ({count} channels)")),
+        )
+        .marker(Marker::Block)
+        .x_bounds([0.0, columns as f64])
+        .y_bounds([0.0, rows as f64])
+        .paint(move |ctx| {
+            // Silent channels first, then each heat level as one shape
+            let mut levels = vec![Vec::new(); HEAT.len()];
+            let mut active = vec![false; (columns * rows) as usize];
+            for channel in channels {
+                let (x, y) = grid_position(channel.channel, columns);
+                let level = heat_level(channel.reads, busiest);
+                if let Some(cell) = active.get_mut((y * columns + x) as usize) {
+                    *cell = channel.reads > 0;
+                }
+                if channel.reads > 0 {
+                    levels[level].push((x as f64 + 0.5, (rows - y) as f64 - 0.5));
+                }
+            }
+            let silent = (1..=columns * rows)
+                .map(|channel| grid_position(channel, columns))
+                .filter(|&(x, y)| !active[(y * columns + x) as usize])
+                .map(|(x, y)| (x as f64 + 0.5, (rows - y) as f64 - 0.5))
+                .collect::<Vec<_>>();
+            ctx.draw(&Points {
+                coords: &silent,
+                color: Color::DarkGray,
+            });
+            for (coords, color) in levels.iter().zip(HEAT) {
+                ctx.draw(&Points { coords, color });
+            }
+        })
+}
+
+impl Component for RunCharts {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick => {
+                // add any logic here that should run on every tick
+            }
+            Action::Render => {
+                // add any logic here that should run on every render
+            }
+            _ => {}
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let duplex = self
+            .results
+            .as_ref()
+            .and_then(|results| results.duplex.as_ref());
+        let poly_a = self
+            .results
+            .as_ref()
+            .and_then(|results| results.rna.as_ref())
+            .filter(|rna| !rna.poly_a.is_empty());
+        let [duplex_area, poly_a_area, area] = Layout::vertical([
+            Constraint::Length(if duplex.is_some() { 4 } else { 0 }),
+            Constraint::Length(if poly_a.is_some() { 10 } else { 0 }),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        if let Some(duplex) = duplex {
+            frame.render_widget(duplex_panel(duplex), duplex_area);
+        }
+        if let Some(rna) = poly_a {
+            let bars = rna
+                .poly_a
+                .iter()
+                .map(|bin| {
+                    Bar::default()
+                        .value(bin.reads)
+                        .text_value(String::new())
+                        .label(Line::from(bin.start.to_string()))
+                })
+                .collect::<Vec<_>>();
+            frame.render_widget(poly_a_chart(rna, &bars), poly_a_area);
+        }
+
+        let Some(results) = self
+            .results
+            .as_ref()
+            .filter(|results| !results.yield_over_time.is_empty())
+        else {
+            let message = Paragraph::new(vec![
+                Line::from("No sequencing summary for this project's basecalls."),
+                Line::from(
+                    "Charts need a sequencing_summary.txt, press c on the project to compute QC.",
+                ),
+            ])
+            .block(Block::default().borders(Borders::ALL).title("Run charts"));
+            frame.render_widget(message, area);
+            return Ok(());
+        };
+
+        // Cumulative totals at the end of each bin
+        let hours = |start: u64| (start + YIELD_BIN_SECONDS) as f64 / 3600.0;
+        let mut bases = Vec::new();
+        let mut reads = Vec::new();
+        let (mut total_bases, mut total_reads) = (0, 0);
+        for bin in &results.yield_over_time {
+            total_bases += bin.bases;
+            total_reads += bin.reads;
+            bases.push((hours(bin.start), total_bases as f64 / 1e9));
+            reads.push((hours(bin.start), total_reads as f64 / 1e6));
+        }
+        let n50 = results
+            .yield_over_time
+            .iter()
+            .map(|bin| (hours(bin.start), bin.n50 as f64 / 1000.0))
+            .collect::<Vec<_>>();
+
+        // A yield rate well below its peak suggests pores are blocked and a wash would help
+        let yield_title = match current_rate(&results.yield_over_time) {
+            Some(rate) => format!("Cumulative yield, current rate {rate:.0}% of peak"),
+            None => "Cumulative yield".to_string(),
+        };
+
+        let [top, bottom] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+        let [bases_area, reads_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(top);
+        let [n50_area, heatmap_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(bottom);
+
+        frame.render_widget(
+            line_chart(yield_title, &bases, "Gb", Color::Green),
+            bases_area,
+        );
+        frame.render_widget(
+            line_chart(
+                "Cumulative reads".to_string(),
+                &reads,
+                "M reads",
+                Color::Cyan,
+            ),
+            reads_area,
+        );
+        frame.render_widget(
+            line_chart("Read N50 over time".to_string(), &n50, "kb", Color::Yellow),
+            n50_area,
+        );
+        frame.render_widget(heatmap(&results.channels), heatmap_area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_heatmap_grid() {
+        assert_eq!(grid_size(126), (32, 16));
+        assert_eq!(grid_size(512), (32, 16));
+        assert_eq!(grid_size(2048), (64, 32));
+        assert_eq!(grid_size(3000), (55, 55));
+        assert_eq!(grid_position(1, 32), (0, 0));
+        assert_eq!(grid_position(32, 32), (31, 0));
+        assert_eq!(grid_position(33, 32), (0, 1));
+        assert_eq!(grid_position(512, 32), (31, 15));
+    }
+
+    #[test]
+    fn test_heat_level() {
+        assert_eq!(heat_level(1, 100), 0);
+        assert_eq!(heat_level(25, 100), 1);
+        assert_eq!(heat_level(99, 100), 3);
+        assert_eq!(heat_level(100, 100), HEAT.len() - 1);
+    }
+
+    #[test]
+    fn test_current_rate() {
+        let bin = |start, bases| YieldBin {
+            start,
+            reads: 1,
+            bases,
+            n50: 0,
+        };
+        assert_eq!(current_rate(&[]), None);
+        assert_eq!(current_rate(&[bin(0, 100)]), None);
+        // The partly filled last bin isn't compared against the full ones
+        assert_eq!(
+            current_rate(&[bin(0, 400), bin(600, 200), bin(1200, 10)]),
+            Some(50.0)
+        );
+    }
+}
@@ -1,13 +1,10 @@
-use std::{borrow::Cow, time::Instant};
-
 use color_eyre::Result;
-use rand::prelude::*;
-use rand_xoshiro::Xoshiro256PlusPlus;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
-    style::{Color, Modifier, Style, Stylize},
-    text::{Line, Span, Text},
-    widgets::{Block, Borders, List, Paragraph},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState},
     Frame,
 };
 use serde::{Deserialize, Serialize};
@@ -16,21 +13,20 @@ use super::Component;
 
 use crate::action::Action;
 use crate::components::utils::*;
+use crate::data::preset::{Preset, FIELDS};
 
+/// Settings pane listing the basecall presets, with a form for adding and editing them
+///
+/// The same list is shown for picking a preset when a basecall is launched.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Settings {
-    pub has_focus: bool,
-    pub margin: Margin,
-}
-
-impl Focusable for Settings {
-    fn focus(&mut self) {
-        self.has_focus = true;
-    }
-
-    fn unfocus(&mut self) {
-        self.has_focus = false;
-    }
+    pub presets: Vec<Preset>,
+    pub selected: Option<usize>,
+    /// Project a preset is being picked for, `None` while just managing presets
+    pub launching: Option<String>,
+    /// Values typed into the form, the field being edited and the name of the preset being
+    /// edited, while it's open
+    pub form: Option<([String; 9], usize, Option<String>)>,
 }
 
 impl Default for Settings {
@@ -42,23 +38,114 @@ impl Default for Settings {
 impl Settings {
     pub fn new() -> Self {
         Self {
-            has_focus: false,
-            margin: Margin {
-                horizontal: 0,
-                vertical: 0,
-            },
+            presets: Vec::new(),
+            selected: None,
+            launching: None,
+            form: None,
         }
     }
+
+    pub fn selected_preset(&self) -> Option<&Preset> {
+        self.selected.and_then(|i| self.presets.get(i))
+    }
+
+    /// Keep the selection on the list after presets are added or removed
+    pub fn set_presets(&mut self, presets: Vec<Preset>) {
+        self.presets = presets;
+        self.selected = match self.selected {
+            _ if self.presets.is_empty() => None,
+            Some(i) => Some(i.min(self.presets.len() - 1)),
+            None => Some(0),
+        };
+    }
+
+    fn draw_form(&self, frame: &mut Frame, area: Rect, values: &[String; 9], field: usize) {
+        let [area] = Layout::vertical([Constraint::Length(FIELDS.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let lines = FIELDS
+            .iter()
+            .zip(values)
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let style = if i == field {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                let cursor = if i == field { "_" } else { "" };
+                Line::from(vec![
+                    Span::styled(format!("{label:<16}"), style),
+                    Span::raw(format!("{value}{cursor}")),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let title_bottom = Line::from(vec![
+            Span::styled(" [", Style::default()),
+            Span::styled("Tab", Style::default().fg(Color::Yellow)),
+            Span::styled("] Next Field", Style::default()),
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled("] Save", Style::default()),
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Cancel", Style::default()),
+        ])
+        .centered();
+        let form = Paragraph::new(lines).block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title("Preset")
+                .title_bottom(title_bottom),
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(form, area);
+    }
 }
 
 impl Component for Settings {
+    /// Typing into the preset form, which the app only routes keys to while it's open
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some((values, field, editing)) = &mut self.form else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Esc => return Ok(Some(Action::CancelPresetEntry)),
+            KeyCode::Enter => {
+                // Stay in the form so a mistake can be fixed rather than retyped
+                return Ok(Some(match Preset::from_fields(values) {
+                    Ok(preset) => Action::SavePreset(editing.clone(), preset),
+                    Err(e) => Action::Error(e.to_string()),
+                }));
+            }
+            KeyCode::Tab | KeyCode::Down => *field = (*field + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => *field = (*field + FIELDS.len() - 1) % FIELDS.len(),
+            KeyCode::Backspace => {
+                values[*field].pop();
+            }
+            KeyCode::Char(c) => values[*field].push(c),
+            _ => {}
+        }
+        Ok(None)
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
-            Action::Tick => {
-                // add any logic here that should run on every tick
+            Action::AddPreset => self.form = Some((Default::default(), 0, None)),
+            Action::EditPreset => {
+                if let Some(preset) = self.selected_preset() {
+                    self.form = Some((preset.to_fields(), 0, Some(preset.name.clone())));
+                }
             }
-            Action::Render => {
-                // add any logic here that should run on every render
+            Action::CancelPresetEntry | Action::SavePreset(..) => self.form = None,
+            Action::SelectNext => self.selected = select_next(self.selected, self.presets.len()),
+            Action::SelectPrevious => {
+                self.selected = select_previous(self.selected, self.presets.len())
             }
             _ => {}
         };
@@ -66,65 +153,127 @@ impl Component for Settings {
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
-        // Put a border around the fish tank
-        let block = Block::default()
-            .title("Global Settings")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::White))
-            .title_style(
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            );
-
-        // Text Input fields
-
-        // pod5/fast5 Project Base Dir
-        let pod5_project_base_dir = Paragraph::new("pod5/fast5 Project Base Dir")
-            .style(Style::default().fg(Color::White))
-            .alignment(Alignment::Left)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::White))
-                    .title_style(
-                        Style::default()
-                            .fg(Color::White)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-            );
-       
-        // Add the text input fields to the list
-        let settings_list = List::new(vec![pod5_project_base_dir])
-            .block(Block::default().borders(Borders::ALL))
-            .style(Style::default().fg(Color::White))
-            .highlight_style(Style::default().fg(Color::LightBlue))
-            .highlight_symbol(">>");
-
-        // Layout the components
-        let settings_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints(
-                [
-                    Constraint::Length(3),
-                    Constraint::Min(0),
-                ]
-                .as_ref(),
-            )
-            .split(area);
-
-        // Render the list
-        frame.render_widget(settings_list, settings_layout[1]);
-
-        
-
-        
-        // Render the block
-        frame.render_widget(block, area);
-
-        
+        let rows = self.presets.iter().map(|preset| {
+            let source = if preset.file.is_some() {
+                "file"
+            } else {
+                "config"
+            };
+            Row::new([
+                preset.name.clone(),
+                preset.model.clone(),
+                preset.kit.clone().unwrap_or_default(),
+                preset.min_qscore.map(|q| q.to_string()).unwrap_or_default(),
+                preset.emit.extension().to_string(),
+                preset.modified_bases.join(","),
+                format!("{:?}", preset.run_type),
+                preset.extra_args.join(" "),
+                source.to_string(),
+            ])
+        });
+
+        let (title, enter) = match &self.launching {
+            Some(project) => (format!("Basecall {project} with preset"), "] Launch"),
+            None => ("Settings: basecall presets".to_string(), "] Edit"),
+        };
+        let title_bottom = Line::from(vec![
+            // Enter launch or edit
+            Span::styled(" [", Style::default()),
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::styled(enter, Style::default()),
+            // a add
+            Span::styled(" [", Style::default()),
+            Span::styled("a", Style::default().fg(Color::Yellow)),
+            Span::styled("] Add", Style::default()),
+            // e edit
+            Span::styled(" [", Style::default()),
+            Span::styled("e", Style::default().fg(Color::Yellow)),
+            Span::styled("] Edit", Style::default()),
+            // d delete
+            Span::styled(" [", Style::default()),
+            Span::styled("d", Style::default().fg(Color::Yellow)),
+            Span::styled("] Delete", Style::default()),
+            // Esc close
+            Span::styled(" [", Style::default()),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::styled("] Close", Style::default()),
+        ])
+        .centered();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(16),
+                Constraint::Fill(1),
+                Constraint::Length(14),
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Length(12),
+                Constraint::Length(7),
+                Constraint::Fill(1),
+                Constraint::Length(6),
+            ],
+        )
+        .header(
+            Row::new([
+                "Name", "Model", "Kit", "Min Q", "Emit", "Modified", "Type", "Extra", "Source",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .border_style(focus_border_style(true))
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(title_bottom),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+        let mut state = TableState::default().with_selected(self.selected);
+        frame.render_stateful_widget(table, area, &mut state);
 
+        if let Some((values, field, _)) = &self.form {
+            self.draw_form(frame, area, values, *field);
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_edit_preset() {
+        let mut settings = Settings::new();
+        settings.set_presets(vec![Preset {
+            name: "hac".to_string(),
+            model: "hac".to_string(),
+            ..Default::default()
+        }]);
+        settings.update(Action::EditPreset).unwrap();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for _ in 0..3 {
+            settings.handle_key_event(key(KeyCode::Tab)).unwrap();
+        }
+        for c in "SQK-NBD114-24".chars() {
+            settings.handle_key_event(key(KeyCode::Char(c))).unwrap();
+        }
+        assert_eq!(
+            settings.handle_key_event(key(KeyCode::Enter)).unwrap(),
+            Some(Action::SavePreset(
+                Some("hac".to_string()),
+                Preset {
+                    name: "hac".to_string(),
+                    model: "hac".to_string(),
+                    kit: Some("SQK-NBD114-24".to_string()),
+                    ..Default::default()
+                }
+            ))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Row, Table},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::data::{
+    now,
+    project::Project,
+    storage::{format_size, VolumeUsage},
+};
+
+/// Growth is reported over the last week
+const GROWTH_WINDOW: u64 = 7 * 24 * 60 * 60;
+
+/// Disk usage of every project, largest first, and free space on the configured volumes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageView {
+    pub projects: Vec<Project>,
+    pub volumes: Vec<VolumeUsage>,
+}
+
+impl Default for StorageView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StorageView {
+    pub fn new() -> Self {
+        Self {
+            projects: Vec::new(),
+            volumes: Vec::new(),
+        }
+    }
+}
+
+impl Component for StorageView {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if let Action::StorageUsage(_, volumes) = action {
+            self.volumes = volumes;
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let volume_rows = self.volumes.iter().map(|volume| {
+            let used = volume.total.saturating_sub(volume.free);
+            let percent = used as f64 / volume.total.max(1) as f64 * 100.0;
+            let style = if percent >= 90.0 {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Row::new([
+                volume.name.clone(),
+                volume.path.clone(),
+                format_size(volume.free),
+                format_size(volume.total),
+                format!("{percent:.0}%"),
+            ])
+            .style(style)
+        });
+        let volumes = Table::new(
+            volume_rows,
+            [
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(6),
+            ],
+        )
+        .header(
+            Row::new(["Volume", "Path", "Free", "Size", "Used"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL).title("Volumes"));
+
+        let mut projects = self.projects.iter().collect::<Vec<_>>();
+        projects.sort_by_key(|project| std::cmp::Reverse(project.data_size));
+        let since = now().saturating_sub(GROWTH_WINDOW);
+        let project_rows = projects.into_iter().map(|project| {
+            let usage = &project.storage;
+            let growth = project.growth_since(since);
+            let growth = match growth {
+                0 => "-".to_string(),
+                bytes if bytes > 0 => format!("+{}", format_size(bytes as u64)),
+                bytes => format!("-{}", format_size(bytes.unsigned_abs())),
+            };
+            Row::new([
+                project.name.clone(),
+                format_size(usage.raw),
+                format_size(usage.basecalls),
+                format_size(usage.alignments),
+                format_size(usage.reports + usage.other),
+                format_size(project.data_size),
+                growth,
+            ])
+        });
+        let projects = Table::new(
+            project_rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(11),
+            ],
+        )
+        .header(
+            Row::new([
+                "Project",
+                "Raw",
+                "Basecalls",
+                "Alignments",
+                "Reports",
+                "Total",
+                "Growth (7d)",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Projects by size"),
+        );
+
+        let [volumes_area, projects_area] = Layout::vertical([
+            Constraint::Length(self.volumes.len() as u16 + 3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        frame.render_widget(volumes, volumes_area);
+        frame.render_widget(projects, projects_area);
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use color_eyre::Result;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Row, Table},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+
+use crate::action::Action;
+use crate::data::{storage::format_size, throughput::ThroughputTable};
+
+/// Basecall throughput of each machine and model, from the runs they've finished
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputView {
+    pub table: ThroughputTable,
+}
+
+impl Default for ThroughputView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThroughputView {
+    pub fn new() -> Self {
+        Self {
+            table: ThroughputTable::default(),
+        }
+    }
+}
+
+impl Component for ThroughputView {
+    fn update(&mut self, _action: Action) -> Result<Option<Action>> {
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let rows = self.table.entries.iter().map(|entry| {
+            Row::new([
+                entry.host.clone(),
+                entry.hardware.clone(),
+                entry.model.clone(),
+                entry.runs.to_string(),
+                format_size(entry.input_bytes),
+                entry
+                    .samples_per_second
+                    .map(|rate| format!("{rate:.3e}"))
+                    .unwrap_or_default(),
+                format!(
+                    "{}/h",
+                    format_size((entry.bytes_per_second() * 3600.0) as u64)
+                ),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(16),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Length(5),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(12),
+            ],
+        )
+        .header(
+            Row::new([
+                "Host",
+                "Hardware",
+                "Model",
+                "Runs",
+                "Input",
+                "Samples/s",
+                "Throughput",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Basecall throughput by machine"),
+        );
+        frame.render_widget(table, area);
+        Ok(())
+    }
+}
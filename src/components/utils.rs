@@ -18,3 +18,66 @@ pub fn focus_border_style(has_focus: bool) -> Style {
         Style::default().fg(Color::DarkGray)
     }
 }
+
+/// How long ago something happened, to the largest whole unit, e.g. `3 days ago`
+pub fn format_age(seconds: u64) -> String {
+    let (count, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+/// Index of the item under a click at `row` in a bordered list drawn in `area`, scrolled down
+/// `offset` items, whose items are each `item_height` rows tall
+pub fn clicked_item(area: Rect, offset: usize, item_height: u16, row: u16) -> Option<usize> {
+    let top = area.y + 1;
+    let bottom = area.bottom().saturating_sub(1);
+    (row >= top && row < bottom).then(|| offset + ((row - top) / item_height.max(1)) as usize)
+}
+
+/// Move a list selection down one row, wrapping to the top
+pub fn select_next(selected: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(selected.map_or(0, |i| (i + 1) % len))
+}
+
+/// Move a list selection up one row, wrapping to the bottom
+pub fn select_previous(selected: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(selected.map_or(len - 1, |i| (i + len - 1) % len))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_clicked_item() {
+        // A bordered list with rows 11 to 18 inside its border
+        let area = Rect::new(0, 10, 40, 10);
+        assert_eq!(clicked_item(area, 0, 1, 10), None);
+        assert_eq!(clicked_item(area, 0, 1, 11), Some(0));
+        assert_eq!(clicked_item(area, 0, 1, 18), Some(7));
+        assert_eq!(clicked_item(area, 0, 1, 19), None);
+        assert_eq!(clicked_item(area, 0, 1, 25), None);
+
+        // Jobs take two rows each
+        assert_eq!(clicked_item(area, 0, 2, 11), Some(0));
+        assert_eq!(clicked_item(area, 0, 2, 12), Some(0));
+        assert_eq!(clicked_item(area, 0, 2, 13), Some(1));
+
+        // Scrolled down past the first items
+        assert_eq!(clicked_item(area, 5, 1, 11), Some(5));
+        assert_eq!(clicked_item(area, 3, 2, 15), Some(5));
+    }
+}
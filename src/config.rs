@@ -11,7 +11,15 @@ use ratatui::style::{Color, Modifier, Style};
 use serde::{de::Deserializer, Deserialize};
 use tracing::error;
 
-use crate::{action::Action, app::Mode};
+use crate::{
+    action::Action,
+    app::Mode,
+    data::{alignment::AlignmentConfig, preset::Preset, storage::StorageConfig},
+    executor::ExecutionConfig,
+    notifications::NotificationConfig,
+    resources::MonitorConfig,
+    tui::DisplayConfig,
+};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
@@ -31,6 +39,21 @@ pub struct Config {
     pub keybindings: KeyBindings,
     #[serde(default)]
     pub styles: Styles,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub alignment: AlignmentConfig,
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    /// Basecall presets, alongside any shared as files in the `presets` config directory
+    #[serde(default)]
+    pub presets: Vec<Preset>,
 }
 
 lazy_static! {
@@ -1 +1,48 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod alignment;
+pub mod bam;
+pub mod barcoding;
+pub mod conversion;
+pub mod duplex;
+pub mod import;
+pub mod launch;
+pub mod lifecycle;
+pub mod manifest;
+pub mod modifications;
+pub mod preset;
 pub mod project;
+pub mod qc;
+pub mod reference;
+pub mod rna;
+pub mod sample_sheet;
+pub mod storage;
+pub mod throughput;
+
+/// Seconds since the unix epoch, as stored in `created_at`/`last_updated` fields
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Login name recorded in `created_by` fields
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// Keep names usable as a single path component
+pub fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
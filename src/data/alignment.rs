@@ -0,0 +1,211 @@
+//! Aligning basecalled reads to a registered reference and summarising how well they mapped
+//!
+//! Alignment runs either `dorado aligner` or `minimap2` piped into `samtools sort` as a job.
+//! Mapping statistics are then read natively from the aligned BAM and stored on the basecall.
+
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{bam, project::BasecallStatus, reference::Reference};
+
+/// Alignment settings, read from the `alignment` section of the config file
+#[derive(Clone, Debug, Deserialize)]
+pub struct AlignmentConfig {
+    #[serde(default)]
+    pub aligner: Aligner,
+    /// Name of a registered reference or path to a FASTA to register, used when aligning
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// Paths to `minimap2` and `samtools`, found on `PATH` if unset
+    #[serde(default)]
+    pub minimap2: Option<String>,
+    #[serde(default)]
+    pub samtools: Option<String>,
+    /// minimap2 preset passed to `-x`
+    #[serde(default = "default_preset")]
+    pub preset: String,
+}
+
+impl Default for AlignmentConfig {
+    fn default() -> Self {
+        Self {
+            aligner: Aligner::default(),
+            reference: None,
+            minimap2: None,
+            samtools: None,
+            preset: default_preset(),
+        }
+    }
+}
+
+fn default_preset() -> String {
+    "map-ont".to_string()
+}
+
+impl AlignmentConfig {
+    /// Settings for a minimap2 job, `None` when aligning with dorado
+    pub fn minimap2(&self) -> Option<Minimap2> {
+        (self.aligner == Aligner::Minimap2).then(|| Minimap2 {
+            minimap2: self
+                .minimap2
+                .clone()
+                .unwrap_or_else(|| "minimap2".to_string()),
+            samtools: self
+                .samtools
+                .clone()
+                .unwrap_or_else(|| "samtools".to_string()),
+            preset: self.preset.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aligner {
+    #[default]
+    Dorado,
+    Minimap2,
+}
+
+/// Tools for aligning with `minimap2 | samtools sort`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Minimap2 {
+    pub minimap2: String,
+    pub samtools: String,
+    pub preset: String,
+}
+
+/// How a basecall's reads mapped to a reference
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MappingStats {
+    /// Name of the registered reference
+    pub reference: String,
+    pub reads: u64,
+    pub mapped_reads: u64,
+    /// Matching bases over aligned columns of the primary alignments
+    pub identity: f64,
+    /// Mean depth across the reference
+    pub coverage: f64,
+}
+
+impl MappingStats {
+    pub fn mapped_percent(&self) -> f64 {
+        if self.reads == 0 {
+            0.0
+        } else {
+            self.mapped_reads as f64 * 100.0 / self.reads as f64
+        }
+    }
+}
+
+/// An alignment of one basecall's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlignLog {
+    pub status: BasecallStatus,
+    /// `dorado` or `minimap2`
+    pub aligner: String,
+    /// The reference as registered when the alignment started, with its checksum
+    pub reference: Reference,
+    /// Basecalled BAM that was aligned
+    pub input: String,
+    pub output_path: String,
+    pub command: Vec<String>,
+}
+
+/// Aligned BAM for `input`, next to it and named after the reference
+pub fn output_path(input: &str, reference: &str) -> String {
+    let stem = input.strip_suffix(".bam").unwrap_or(input);
+    format!("{stem}.{}.aligned.bam", super::sanitize(reference))
+}
+
+/// Mapping statistics of an aligned BAM
+pub fn mapping_stats(path: &Path, reference: &str) -> io::Result<MappingStats> {
+    let mut reader = bam::Reader::from_path(path)?;
+    let mut record = bam::Record::default();
+    let mut stats = MappingStats {
+        reference: reference.to_string(),
+        ..Default::default()
+    };
+    let (mut matches, mut columns, mut covered) = (0u64, 0u64, 0u64);
+    while reader.read(&mut record)? {
+        if !record.is_mapped() {
+            if record.is_primary() {
+                stats.reads += 1;
+            }
+            continue;
+        }
+        let mut aligned = 0;
+        for (len, op) in record.cigar() {
+            match op {
+                b'M' | b'=' | b'X' | b'D' => {
+                    aligned += len as u64;
+                    covered += len as u64;
+                }
+                b'I' => aligned += len as u64,
+                _ => {}
+            }
+        }
+        // Supplementary alignments add depth but aren't separate reads
+        if !record.is_primary() {
+            continue;
+        }
+        stats.reads += 1;
+        stats.mapped_reads += 1;
+        if let Some(edits) = record.aux_int(b"NM") {
+            columns += aligned;
+            matches += aligned.saturating_sub(edits.max(0) as u64);
+        }
+    }
+    if columns > 0 {
+        stats.identity = matches as f64 / columns as f64;
+    }
+    let reference_length = reader.reference_lengths.iter().sum::<u64>();
+    if reference_length > 0 {
+        stats.coverage = covered as f64 / reference_length as f64;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::bam::testing::*;
+
+    #[test]
+    fn test_mapping_stats() {
+        let path = std::env::temp_dir().join(format!("fishtank-align-{}.bam", std::process::id()));
+        // 95 matches then 5 inserted bases, with 10 edits in total
+        let mut mapped = TestRecord::new("read1", vec![20; 100]);
+        mapped.flag = 0;
+        mapped.ref_id = 0;
+        mapped.cigar = vec![95 << 4, 5 << 4 | 1];
+        mapped.aux = b"NMC\x0a".to_vec();
+        let mut supplementary = TestRecord::new("read1", vec![20; 50]);
+        supplementary.flag = 0x800;
+        supplementary.ref_id = 0;
+        supplementary.cigar = vec![50 << 4];
+        write_aligned_bam(
+            &path,
+            &[("chr1", 500)],
+            &[
+                mapped,
+                supplementary,
+                TestRecord::new("read2", vec![20; 10]),
+            ],
+        );
+
+        let stats = mapping_stats(&path, "test").unwrap();
+        assert_eq!((stats.reads, stats.mapped_reads), (2, 1));
+        assert_eq!(stats.mapped_percent(), 50.0);
+        assert!((stats.identity - 0.9).abs() < 1e-9);
+        assert!((stats.coverage - 0.29).abs() < 1e-9);
+        assert_eq!(
+            output_path("/data/run1/calls.bam", "hg38"),
+            "/data/run1/calls.hg38.aligned.bam"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! Minimal streaming BAM reader for summarising basecaller output
+//!
+//! Only what fishtank reports on is decoded, the header and sequences are skipped over.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+use flate2::read::MultiGzDecoder;
+
+pub const FLAG_UNMAPPED: u16 = 0x4;
+pub const FLAG_SECONDARY: u16 = 0x100;
+pub const FLAG_SUPPLEMENTARY: u16 = 0x800;
+
+/// Reads records from a BGZF-compressed BAM file
+pub struct Reader<R: Read> {
+    inner: MultiGzDecoder<R>,
+    /// Length of each reference sequence in the header, indexed by a record's `ref_id`
+    pub reference_lengths: Vec<u64>,
+}
+
+impl Reader<BufReader<File>> {
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut inner = MultiGzDecoder::new(reader);
+        let mut magic = [0u8; 4];
+        inner.read_exact(&mut magic)?;
+        if &magic != b"BAM\x01" {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a BAM file"));
+        }
+        // SAM text header, then the reference dictionary
+        let l_text = read_u32(&mut inner)? as u64;
+        io::copy(&mut (&mut inner).take(l_text), &mut io::sink())?;
+        let mut reference_lengths = Vec::new();
+        for _ in 0..read_u32(&mut inner)? {
+            let l_name = read_u32(&mut inner)? as u64;
+            io::copy(&mut (&mut inner).take(l_name), &mut io::sink())?;
+            reference_lengths.push(read_u32(&mut inner)? as u64);
+        }
+
+        Ok(Self {
+            inner,
+            reference_lengths,
+        })
+    }
+
+    /// Read the next record into `record`, returning false at the end of the file
+    pub fn read(&mut self, record: &mut Record) -> io::Result<bool> {
+        let mut size = [0u8; 4];
+        match self.inner.read_exact(&mut size) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        let size = u32::from_le_bytes(size) as usize;
+        if size < 32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated BAM record",
+            ));
+        }
+        record.data.resize(size, 0);
+        self.inner.read_exact(&mut record.data)?;
+        Ok(true)
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// A single alignment record, stored as its raw bytes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    data: Vec<u8>,
+}
+
+impl Record {
+    pub fn flag(&self) -> u16 {
+        u16::from_le_bytes([self.data[14], self.data[15]])
+    }
+
+    /// Secondary and supplementary records repeat a read that has a primary record
+    pub fn is_primary(&self) -> bool {
+        self.flag() & (FLAG_SECONDARY | FLAG_SUPPLEMENTARY) == 0
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        self.flag() & FLAG_UNMAPPED == 0
+    }
+
+    /// CIGAR operations as `(length, op)`, with `op` one of `MIDNSHP=X`
+    pub fn cigar(&self) -> impl Iterator<Item = (u32, u8)> + '_ {
+        let l_read_name = self.data[8] as usize;
+        let n_cigar_op = u16::from_le_bytes([self.data[12], self.data[13]]) as usize;
+        let start = 32 + l_read_name;
+        self.data
+            .get(start..start + n_cigar_op * 4)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .map(|op| {
+                let op = u32::from_le_bytes([op[0], op[1], op[2], op[3]]);
+                (op >> 4, b"MIDNSHP=X"[(op & 0xf).min(8) as usize])
+            })
+    }
+
+    pub fn seq_len(&self) -> usize {
+        u32::from_le_bytes([self.data[16], self.data[17], self.data[18], self.data[19]]) as usize
+    }
+
+    /// Phred base qualities, without the +33 offset
+    pub fn qual(&self) -> &[u8] {
+        let l_read_name = self.data[8] as usize;
+        let n_cigar_op = u16::from_le_bytes([self.data[12], self.data[13]]) as usize;
+        let start = 32 + l_read_name + n_cigar_op * 4 + self.seq_len().div_ceil(2);
+        self.data
+            .get(start..start + self.seq_len())
+            .unwrap_or_default()
+    }
+
+    /// Type code and raw value of an aux tag
+    fn aux(&self, tag: &[u8; 2]) -> Option<(u8, &[u8])> {
+        let l_read_name = self.data[8] as usize;
+        let n_cigar_op = u16::from_le_bytes([self.data[12], self.data[13]]) as usize;
+        let mut aux = self.data.get(
+            32 + l_read_name + n_cigar_op * 4 + self.seq_len().div_ceil(2) + self.seq_len()..,
+        )?;
+        while aux.len() >= 3 {
+            let (name, kind, rest) = (&aux[..2], aux[2], &aux[3..]);
+            let size = match kind {
+                b'A' | b'c' | b'C' => 1,
+                b's' | b'S' => 2,
+                b'i' | b'I' | b'f' => 4,
+                b'Z' | b'H' => rest.iter().position(|&b| b == 0)? + 1,
+                b'B' => {
+                    let width = match *rest.first()? {
+                        b'c' | b'C' => 1,
+                        b's' | b'S' => 2,
+                        _ => 4,
+                    };
+                    let count = u32::from_le_bytes(rest.get(1..5)?.try_into().ok()?) as usize;
+                    5 + width * count
+                }
+                _ => return None,
+            };
+            let value = rest.get(..size)?;
+            if name == tag {
+                return Some((kind, value));
+            }
+            aux = &rest[size..];
+        }
+        None
+    }
+
+    /// Value of an integer aux tag such as `NM`
+    pub fn aux_int(&self, tag: &[u8; 2]) -> Option<i64> {
+        let (kind, value) = self.aux(tag)?;
+        match kind {
+            b'c' => Some(value[0] as i8 as i64),
+            b'C' => Some(value[0] as i64),
+            b's' => Some(i16::from_le_bytes([value[0], value[1]]) as i64),
+            b'S' => Some(u16::from_le_bytes([value[0], value[1]]) as i64),
+            b'i' => Some(i32::from_le_bytes(value.try_into().ok()?) as i64),
+            b'I' => Some(u32::from_le_bytes(value.try_into().ok()?) as i64),
+            _ => None,
+        }
+    }
+
+    /// Value of a single character aux tag such as `ts`
+    pub fn aux_char(&self, tag: &[u8; 2]) -> Option<u8> {
+        match self.aux(tag)? {
+            (b'A', value) => Some(value[0]),
+            _ => None,
+        }
+    }
+
+    /// Value of a string aux tag such as `MM`, without its terminating nul
+    pub fn aux_str(&self, tag: &[u8; 2]) -> Option<&str> {
+        match self.aux(tag)? {
+            (b'Z', value) => std::str::from_utf8(&value[..value.len() - 1]).ok(),
+            _ => None,
+        }
+    }
+
+    /// Values of a `B:C` byte array aux tag such as `ML`
+    pub fn aux_bytes(&self, tag: &[u8; 2]) -> Option<&[u8]> {
+        match self.aux(tag)? {
+            (b'B', value) if value[0] == b'C' => Some(&value[5..]),
+            _ => None,
+        }
+    }
+
+    /// Mean quality of the read, averaged as error probabilities the way dorado does
+    pub fn mean_qscore(&self) -> f64 {
+        let qual = self.qual();
+        if qual.is_empty() || qual[0] == 0xff {
+            return 0.0;
+        }
+        let error = qual
+            .iter()
+            .map(|&q| 10f64.powf(-(q as f64) / 10.0))
+            .sum::<f64>()
+            / qual.len() as f64;
+        -10.0 * error.log10()
+    }
+}
+
+/// Count the primary records in a BAM file, i.e. the number of reads it holds
+///
+/// Counting stops at a truncated record, as left behind by an interrupted basecaller.
+pub fn count_reads(path: impl AsRef<Path>) -> io::Result<u64> {
+    let mut reader = Reader::from_path(path)?;
+    let mut record = Record::default();
+    let mut count = 0;
+    loop {
+        match reader.read(&mut record) {
+            Ok(true) if record.is_primary() => count += 1,
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(count)
+}
+
+/// Builds small unaligned BAM files for tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    /// An unaligned record with optional pre-encoded aux data
+    pub struct TestRecord<'a> {
+        pub name: &'a str,
+        pub flag: u16,
+        pub ref_id: i32,
+        /// Encoded CIGAR operations, `length << 4 | op`
+        pub cigar: Vec<u32>,
+        pub qual: Vec<u8>,
+        pub aux: Vec<u8>,
+    }
+
+    impl<'a> TestRecord<'a> {
+        pub fn new(name: &'a str, qual: Vec<u8>) -> Self {
+            Self {
+                name,
+                flag: 4,
+                ref_id: -1,
+                cigar: Vec::new(),
+                qual,
+                aux: Vec::new(),
+            }
+        }
+
+        fn encode(&self) -> Vec<u8> {
+            let l_seq = self.qual.len();
+            let mut data = Vec::new();
+            data.extend_from_slice(&self.ref_id.to_le_bytes());
+            data.extend_from_slice(&(-1i32).to_le_bytes()); // pos
+            data.push(self.name.len() as u8 + 1);
+            data.push(255); // mapq
+            data.extend_from_slice(&4680u16.to_le_bytes()); // bin
+            data.extend_from_slice(&(self.cigar.len() as u16).to_le_bytes());
+            data.extend_from_slice(&self.flag.to_le_bytes());
+            data.extend_from_slice(&(l_seq as u32).to_le_bytes());
+            data.extend_from_slice(&(-1i32).to_le_bytes()); // next refID
+            data.extend_from_slice(&(-1i32).to_le_bytes()); // next pos
+            data.extend_from_slice(&0i32.to_le_bytes()); // tlen
+            data.extend_from_slice(self.name.as_bytes());
+            data.push(0);
+            for op in &self.cigar {
+                data.extend_from_slice(&op.to_le_bytes());
+            }
+            data.extend(std::iter::repeat_n(0x11, l_seq.div_ceil(2))); // all A
+            data.extend_from_slice(&self.qual);
+            data.extend_from_slice(&self.aux);
+
+            let mut block = (data.len() as u32).to_le_bytes().to_vec();
+            block.extend(data);
+            block
+        }
+    }
+
+    pub fn write_bam(path: &std::path::Path, records: &[TestRecord]) {
+        write_aligned_bam(path, &[], records);
+    }
+
+    /// A BAM whose header lists the given reference names and lengths
+    pub fn write_aligned_bam(
+        path: &std::path::Path,
+        references: &[(&str, u32)],
+        records: &[TestRecord],
+    ) {
+        let header = "@HD\tVN:1.6\tSO:unknown\n";
+        let mut bytes = b"BAM\x01".to_vec();
+        bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(&(references.len() as u32).to_le_bytes());
+        for (name, length) in references {
+            bytes.extend_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&length.to_le_bytes());
+        }
+        for record in records {
+            bytes.extend(record.encode());
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&bytes).unwrap();
+        std::fs::write(path, encoder.finish().unwrap()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn test_count_reads() {
+        let path = std::env::temp_dir().join(format!("fishtank-bam-{}.bam", std::process::id()));
+        let mut supplementary = TestRecord::new("read1", vec![20; 3]);
+        supplementary.flag = 0x800;
+        write_bam(
+            &path,
+            &[
+                TestRecord::new("read1", vec![20, 30, 40]),
+                supplementary,
+                TestRecord::new("read2", vec![10; 5]),
+            ],
+        );
+        assert_eq!(count_reads(&path).unwrap(), 2);
+
+        // Cut off partway through the last record
+        let bytes = std::fs::read(&path).unwrap();
+        let mut decoder = flate2::read::GzDecoder::new(&bytes[..]);
+        let mut raw = Vec::new();
+        decoder.read_to_end(&mut raw).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut encoder, &raw[..raw.len() - 3]).unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+        assert_eq!(count_reads(&path).unwrap(), 1);
+
+        let mut reader = Reader::from_path(&path).unwrap();
+        let mut record = Record::default();
+        assert!(reader.read(&mut record).unwrap());
+        assert_eq!(record.seq_len(), 3);
+        assert_eq!(record.qual(), &[20, 30, 40]);
+        assert!((record.mean_qscore() - 24.32).abs() < 0.01);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
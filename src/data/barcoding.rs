@@ -0,0 +1,174 @@
+//! Barcode kits and per-barcode summaries of demultiplexed output
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::{bam, project::BarcodeResults};
+
+/// Barcode kits dorado can classify, with the number of barcodes in each
+pub const BARCODE_KITS: &[(&str, u32)] = &[
+    ("EXP-NBD104", 12),
+    ("EXP-NBD114", 12),
+    ("EXP-NBD196", 96),
+    ("EXP-PBC001", 12),
+    ("EXP-PBC096", 96),
+    ("SQK-16S024", 24),
+    ("SQK-16S114-24", 24),
+    ("SQK-NBD111-24", 24),
+    ("SQK-NBD111-96", 96),
+    ("SQK-NBD114-24", 24),
+    ("SQK-NBD114-96", 96),
+    ("SQK-PCB111-24", 24),
+    ("SQK-PCB114-24", 24),
+    ("SQK-RAB204", 12),
+    ("SQK-RBK004", 12),
+    ("SQK-RBK110-96", 96),
+    ("SQK-RBK111-24", 24),
+    ("SQK-RBK111-96", 96),
+    ("SQK-RBK114-24", 24),
+    ("SQK-RBK114-96", 96),
+    ("SQK-RPB004", 12),
+    ("SQK-RPB114-24", 24),
+    ("VSK-VMK004", 12),
+];
+
+pub fn barcode_count(kit: &str) -> Option<u32> {
+    BARCODE_KITS
+        .iter()
+        .find(|(name, _)| *name == kit)
+        .map(|&(_, count)| count)
+}
+
+/// The number in a `barcodeNN` name
+pub fn barcode_number(barcode: &str) -> Option<u32> {
+    barcode.strip_prefix("barcode")?.parse().ok()
+}
+
+/// Find the barcode a demux output file belongs to from its path
+///
+/// dorado names files `<kit>_barcodeNN.bam`, or nests them in a `barcodeNN` directory.
+fn barcode_of(path: &Path) -> String {
+    path.iter()
+        .rev()
+        .filter_map(|part| part.to_str())
+        .flat_map(|part| part.trim_end_matches(".bam").rsplit('_').next())
+        .find(|part| barcode_number(part).is_some())
+        .unwrap_or("unclassified")
+        .to_string()
+}
+
+fn bam_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            bam_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "bam") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Count reads, bases and mean read quality per barcode in a `dorado demux` output directory
+pub fn summarise(dir: &Path) -> io::Result<Vec<BarcodeResults>> {
+    let mut files = Vec::new();
+    bam_files(dir, &mut files)?;
+
+    // Sum of per-read mean qscores alongside the results, averaged at the end
+    let mut totals: BTreeMap<String, (BarcodeResults, f64)> = BTreeMap::new();
+    let mut record = bam::Record::default();
+    for path in files {
+        let barcode = barcode_of(&path);
+        let (results, qscores) = totals.entry(barcode.clone()).or_insert_with(|| {
+            (
+                BarcodeResults {
+                    barcode,
+                    ..Default::default()
+                },
+                0.0,
+            )
+        });
+        let mut reader = bam::Reader::from_path(&path)?;
+        while reader.read(&mut record)? {
+            if !record.is_primary() {
+                continue;
+            }
+            results.read_count += 1;
+            results.n_bases += record.seq_len() as u64;
+            *qscores += record.mean_qscore();
+        }
+    }
+
+    Ok(totals
+        .into_values()
+        .map(|(mut results, qscores)| {
+            if results.read_count > 0 {
+                results.mean_qscore = qscores / results.read_count as f64;
+            }
+            results
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::bam::testing::*;
+
+    #[test]
+    fn test_barcode_of() {
+        assert_eq!(
+            barcode_of(Path::new("demux/SQK-NBD114-24_barcode07.bam")),
+            "barcode07"
+        );
+        assert_eq!(
+            barcode_of(Path::new("demux/run1/barcode12/reads_0.bam")),
+            "barcode12"
+        );
+        assert_eq!(
+            barcode_of(Path::new("demux/unclassified.bam")),
+            "unclassified"
+        );
+    }
+
+    #[test]
+    fn test_summarise() {
+        let dir = std::env::temp_dir().join(format!("fishtank-demux-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_bam(
+            &dir.join("SQK-NBD114-24_barcode01.bam"),
+            &[
+                TestRecord::new("read1", vec![10; 4]),
+                TestRecord::new("read2", vec![20; 6]),
+            ],
+        );
+        write_bam(
+            &dir.join("unclassified.bam"),
+            &[TestRecord::new("read3", vec![5; 2])],
+        );
+
+        assert_eq!(
+            summarise(&dir).unwrap(),
+            vec![
+                BarcodeResults {
+                    barcode: "barcode01".to_string(),
+                    read_count: 2,
+                    n_bases: 10,
+                    mean_qscore: 15.0,
+                },
+                BarcodeResults {
+                    barcode: "unclassified".to_string(),
+                    read_count: 1,
+                    n_bases: 2,
+                    mean_qscore: 5.0,
+                },
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
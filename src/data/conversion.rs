@@ -0,0 +1,229 @@
+//! Converting legacy FAST5 raw data to POD5 and checking nothing was lost
+//!
+//! The conversion itself runs `pod5 convert fast5` as a job. Afterwards the reads in the FAST5
+//! input are counted natively and compared with `pod5 view`'s listing of the output, and only a
+//! matching conversion can have its originals removed.
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::project::Project;
+
+const UUID_LEN: usize = 36;
+/// Each read's group is named `read_` followed by its UUID
+const READ_GROUP_LEN: usize = 5 + UUID_LEN;
+/// FAST5 files run to gigabytes, so they're scanned this much at a time
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Where MinKNOW writes FAST5 inside a run directory
+const FAST5_DIRS: &[&str] = &["fast5_pass", "fast5"];
+
+/// Read counts on either side of a finished conversion
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversionCheck {
+    pub fast5_reads: u64,
+    pub pod5_reads: u64,
+    pub removed_originals: bool,
+}
+
+impl ConversionCheck {
+    pub fn verified(&self) -> bool {
+        self.fast5_reads > 0 && self.fast5_reads == self.pod5_reads
+    }
+}
+
+fn is_fast5(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "fast5")
+}
+
+fn fast5_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if dir.is_file() {
+        if is_fast5(dir) {
+            files.push(dir.to_path_buf());
+        }
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            fast5_files(&path, files)?;
+        } else if is_fast5(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_uuid(bytes: &[u8]) -> bool {
+    bytes.len() == UUID_LEN
+        && bytes.iter().enumerate().all(|(i, &b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// Reads in one FAST5 file, read `chunk_size` bytes at a time
+///
+/// Multi-read files name a group `read_<uuid>` per read, which is found by scanning for the
+/// group names rather than parsing HDF5. Chunks overlap by a group name so none is split.
+fn reads_in_fast5(mut reader: impl Read, chunk_size: usize) -> io::Result<u64> {
+    let mut ids = HashSet::new();
+    let mut buffer = Vec::with_capacity(chunk_size + READ_GROUP_LEN);
+    loop {
+        let carried = buffer.len();
+        buffer.resize(carried + chunk_size, 0);
+        let read = reader.read(&mut buffer[carried..])?;
+        buffer.truncate(carried + read);
+        if read == 0 {
+            break;
+        }
+        let mut rest = buffer.as_slice();
+        while let Some(i) = rest.windows(5).position(|window| window == b"read_") {
+            let id = &rest[i + 5..(i + READ_GROUP_LEN).min(rest.len())];
+            if is_uuid(id) {
+                ids.insert(id.to_vec());
+            }
+            rest = &rest[i + 5..];
+        }
+        let keep = buffer.len().saturating_sub(READ_GROUP_LEN);
+        buffer.drain(..keep);
+    }
+    Ok(ids.len() as u64)
+}
+
+/// Reads in every FAST5 file below `path`
+///
+/// A file without any read groups, e.g. a legacy single-read file, can't be counted and fails
+/// the count rather than letting a conversion be verified without it.
+pub fn count_fast5_reads(path: &Path) -> Result<u64> {
+    let mut files = Vec::new();
+    fast5_files(path, &mut files)?;
+    let mut reads = 0;
+    for file in files {
+        match reads_in_fast5(File::open(&file)?, CHUNK_SIZE)? {
+            0 => {
+                return Err(eyre!(
+                    "Unrecognised FAST5 {}, no multi-read groups found",
+                    file.display()
+                ))
+            }
+            count => reads += count,
+        }
+    }
+    Ok(reads)
+}
+
+/// Reads in every POD5 file below `path`, as listed by `pod5 view`
+pub fn count_pod5_reads(pod5: &str, path: &Path) -> Result<u64> {
+    let output = Command::new(pod5)
+        .args(["view", "--recursive", "--ids", "--no-header"])
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "{pod5} view failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count() as u64)
+}
+
+/// Compare read counts, removing the FAST5 files only once they match
+pub fn verify(
+    pod5: &str,
+    input: &Path,
+    output: &Path,
+    remove_originals: bool,
+) -> Result<ConversionCheck> {
+    let mut check = ConversionCheck {
+        fast5_reads: count_fast5_reads(input)?,
+        pod5_reads: count_pod5_reads(pod5, output)?,
+        removed_originals: false,
+    };
+    if remove_originals && check.verified() {
+        let mut files = Vec::new();
+        fast5_files(input, &mut files)?;
+        for file in files {
+            std::fs::remove_file(file)?;
+        }
+        check.removed_originals = true;
+    }
+    Ok(check)
+}
+
+/// The project's FAST5, in a MinKNOW `fast5_pass`/`fast5` directory or its data location
+pub fn fast5_input(project: &Project) -> Option<PathBuf> {
+    let data = Path::new(&project.data_location);
+    if project.data_location.is_empty() || !data.is_dir() {
+        return None;
+    }
+    FAST5_DIRS
+        .iter()
+        .map(|dir| data.join(dir))
+        .find(|dir| dir.is_dir())
+        .or_else(|| Some(data.to_path_buf()))
+}
+
+/// Directory the POD5 for `input` is written to, next to it with `fast5` swapped for `pod5`
+pub fn output_dir(input: &Path) -> PathBuf {
+    let name = input
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let converted = name.replace("fast5", "pod5");
+    if converted == name || input.with_file_name(&converted).exists() {
+        input.with_file_name(format!("{name}_pod5"))
+    } else {
+        input.with_file_name(converted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_count_fast5_reads() {
+        let dir = std::env::temp_dir().join(format!("fishtank-fast5-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("fast5_pass")).unwrap();
+        let a = "0a1b2c3d-0000-4000-8000-000000000001";
+        let b = "0a1b2c3d-0000-4000-8000-000000000002";
+        // Group names can appear more than once in the file's heaps and B-trees
+        let multi = format!("\u{89}HDF\0read_{a}\0\0read_{b}\0read_{a}\0read_xyz");
+        // Small chunks split group names across reads
+        for chunk_size in [1, 7, 41, 1024] {
+            assert_eq!(reads_in_fast5(multi.as_bytes(), chunk_size).unwrap(), 2);
+        }
+        std::fs::write(dir.join("fast5_pass/batch0.fast5"), multi).unwrap();
+        std::fs::write(dir.join("fast5_pass/notes.txt"), format!("read_{a}")).unwrap();
+        assert_eq!(count_fast5_reads(&dir.join("fast5_pass")).unwrap(), 2);
+
+        std::fs::write(dir.join("fast5_pass/single.fast5"), "\u{89}HDF\0Raw\0Reads").unwrap();
+        assert!(count_fast5_reads(&dir.join("fast5_pass"))
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unrecognised FAST5"));
+        assert_eq!(output_dir(&dir.join("fast5_pass")), dir.join("pod5_pass"));
+        std::fs::create_dir_all(dir.join("pod5_pass")).unwrap();
+        assert_eq!(
+            output_dir(&dir.join("fast5_pass")),
+            dir.join("fast5_pass_pod5")
+        );
+        assert_eq!(output_dir(&dir.join("raw")), dir.join("raw_pod5"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+//! Pairing statistics of `dorado duplex` output
+//!
+//! Duplex basecalling writes every simplex read plus one duplex read for each template and
+//! complement pair it finds. The `dx` tag tells them apart: `1` for a duplex read, `-1` for a
+//! simplex read that was paired into one and `0` for a simplex read without a partner.
+
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::bam;
+
+/// Simplex and duplex reads of a duplex run, counted and scored separately
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DuplexResults {
+    pub simplex_reads: u64,
+    pub simplex_mean_qscore: f64,
+    /// Simplex reads that were paired into a duplex read, two per pair
+    pub paired_reads: u64,
+    pub duplex_reads: u64,
+    pub duplex_mean_qscore: f64,
+}
+
+impl DuplexResults {
+    /// Percentage of simplex reads that ended up in a duplex pair
+    pub fn duplex_rate(&self) -> f64 {
+        if self.simplex_reads == 0 {
+            0.0
+        } else {
+            self.paired_reads as f64 * 100.0 / self.simplex_reads as f64
+        }
+    }
+
+    /// How much higher the mean qscore of duplex reads is than that of simplex reads
+    pub fn qscore_gain(&self) -> f64 {
+        if self.duplex_reads == 0 {
+            0.0
+        } else {
+            self.duplex_mean_qscore - self.simplex_mean_qscore
+        }
+    }
+}
+
+/// Count and score the simplex and duplex reads in a BAM written by `dorado duplex`
+pub fn summarise(path: &Path) -> io::Result<DuplexResults> {
+    let mut reader = bam::Reader::from_path(path)?;
+    let mut record = bam::Record::default();
+    let mut results = DuplexResults::default();
+    let (mut simplex_qscores, mut duplex_qscores) = (0.0, 0.0);
+    while reader.read(&mut record)? {
+        if !record.is_primary() {
+            continue;
+        }
+        match record.aux_int(b"dx") {
+            Some(1) => {
+                results.duplex_reads += 1;
+                duplex_qscores += record.mean_qscore();
+            }
+            dx => {
+                results.simplex_reads += 1;
+                simplex_qscores += record.mean_qscore();
+                if dx == Some(-1) {
+                    results.paired_reads += 1;
+                }
+            }
+        }
+    }
+    if results.simplex_reads > 0 {
+        results.simplex_mean_qscore = simplex_qscores / results.simplex_reads as f64;
+    }
+    if results.duplex_reads > 0 {
+        results.duplex_mean_qscore = duplex_qscores / results.duplex_reads as f64;
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::bam::testing::*;
+
+    fn read(name: &str, qual: u8, dx: i8) -> TestRecord<'_> {
+        let mut record = TestRecord::new(name, vec![qual; 10]);
+        record.aux = vec![b'd', b'x', b'c', dx as u8];
+        record
+    }
+
+    #[test]
+    fn test_summarise() {
+        let path = std::env::temp_dir().join(format!("fishtank-duplex-{}.bam", std::process::id()));
+        write_bam(
+            &path,
+            &[
+                read("template", 20, -1),
+                read("complement", 20, -1),
+                read("template;complement", 30, 1),
+                read("unpaired", 10, 0),
+            ],
+        );
+
+        let results = summarise(&path).unwrap();
+        assert_eq!(
+            (
+                results.simplex_reads,
+                results.paired_reads,
+                results.duplex_reads
+            ),
+            (3, 2, 1)
+        );
+        assert!((results.duplex_rate() - 66.67).abs() < 0.01);
+        assert!((results.simplex_mean_qscore - 16.67).abs() < 0.01);
+        assert!((results.qscore_gain() - 13.33).abs() < 0.01);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,536 @@
+//! Importing existing MinKNOW run directories as projects
+//!
+//! A run is any directory holding a `final_summary_*.txt`, or raw data in `pod5_pass/`,
+//! `pod5/`, `fast5_pass/` or `fast5/` for runs that never finished. Reads MinKNOW basecalled
+//! live, in `bam_pass/` or `fastq_pass/`, are registered as finished basecalls.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    current_user, now,
+    project::{
+        BasecallLog, BasecallRun, BasecallStatus, Basecaller, Project, ProjectAction,
+        ProjectHistory, ProjectType, RawBasecallsFormat, SequencingInfo,
+    },
+    rna::RnaInfo,
+    sample_sheet::{self, SampleSheet},
+    sanitize, storage,
+};
+
+const RAW_DIRS: &[&str] = &["pod5_pass", "pod5", "fast5_pass", "fast5"];
+const BASECALL_DIRS: &[&str] = &["bam_pass", "fastq_pass"];
+
+/// A project that importing a run directory would create
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunImport {
+    pub path: PathBuf,
+    pub project: Project,
+    /// A project with this name already exists, so the run is skipped
+    pub exists: bool,
+}
+
+/// A run directory that couldn't be scanned, skipped from the import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedRun {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Everything a bulk import found under its root
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportPlan {
+    pub runs: Vec<RunImport>,
+    pub failed: Vec<FailedRun>,
+}
+
+/// The `key=value` lines of a MinKNOW `final_summary_*.txt`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FinalSummary {
+    fields: HashMap<String, String>,
+}
+
+impl FinalSummary {
+    pub fn parse(text: &str) -> Self {
+        let fields = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Self { fields }
+    }
+
+    pub fn get(&self, key: &str) -> &str {
+        self.fields.get(key).map(String::as_str).unwrap_or_default()
+    }
+
+    /// Sequencing kit, the third part of `protocol`
+    pub fn kit(&self) -> &str {
+        self.get("protocol").split(':').nth(2).unwrap_or_default()
+    }
+
+    pub fn started_at(&self) -> Option<u64> {
+        parse_timestamp(self.get("started"))
+    }
+}
+
+/// Find run directories under `root`, without descending into a run once found.
+/// Only an unreadable `root` is an error, directories below it that can't be read are
+/// returned alongside the runs.
+pub fn find_runs(root: &Path) -> io::Result<(Vec<PathBuf>, Vec<FailedRun>)> {
+    let mut runs = Vec::new();
+    let mut failed = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if is_run(&dir) {
+            runs.push(dir);
+            continue;
+        }
+        match subdirectories(&dir) {
+            Ok(dirs) => pending.extend(dirs),
+            Err(e) if dir == root => return Err(e),
+            Err(e) => failed.push(FailedRun {
+                path: dir,
+                error: e.to_string(),
+            }),
+        }
+    }
+    runs.sort();
+    failed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((runs, failed))
+}
+
+/// Visible directories directly under `dir`
+fn subdirectories(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        // file_type doesn't follow symlinks, so links back up the tree can't loop
+        if entry.file_type()?.is_dir() && !hidden {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+fn is_run(dir: &Path) -> bool {
+    final_summary_path(dir).is_some() || RAW_DIRS.iter().any(|raw| dir.join(raw).is_dir())
+}
+
+fn final_summary_path(dir: &Path) -> Option<PathBuf> {
+    find_file(dir, "final_summary", "txt")
+}
+
+/// First file in `dir` named `<prefix>*.<extension>`
+fn find_file(dir: &Path, prefix: &str, extension: &str) -> Option<PathBuf> {
+    let mut files = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == extension)
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(prefix))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files.into_iter().next()
+}
+
+/// Describe the project importing the run in `dir` would create
+pub fn project_for_run(dir: &Path) -> Result<Project> {
+    let summary = match final_summary_path(dir) {
+        Some(path) => FinalSummary::parse(&std::fs::read_to_string(path)?),
+        None => FinalSummary::default(),
+    };
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.display().to_string());
+    let raw = RAW_DIRS
+        .iter()
+        .map(|raw| dir.join(raw))
+        .find(|raw| raw.is_dir());
+    let format = match raw.as_ref().and_then(|raw| raw.file_name()) {
+        Some(raw) if raw.to_string_lossy().starts_with("fast5") => RawBasecallsFormat::Fast5,
+        _ => RawBasecallsFormat::Pod5,
+    };
+    let protocol = summary.get("protocol");
+    let project_type = match RnaInfo::from_kit(summary.kit()) {
+        Some(info) => ProjectType::RNA(info),
+        None if protocol.contains("_RNA") => ProjectType::RNA(RnaInfo {
+            kit: summary.kit().to_string(),
+            ..Default::default()
+        }),
+        None => ProjectType::DNA(String::new()),
+    };
+
+    let mut project = Project {
+        name: name.clone(),
+        description: format!("Imported from {}", dir.display()),
+        created_by: current_user(),
+        created_at: now(),
+        last_updated: now(),
+        project_type,
+        data_location: dir.display().to_string(),
+        format,
+        sequencing: SequencingInfo {
+            flow_cell_id: summary.get("flow_cell_id").to_string(),
+            position_id: summary.get("position").to_string(),
+            experiment_id: summary.get("protocol_group_id").to_string(),
+            sample_id: summary.get("sample_id").to_string(),
+            kit: summary.kit().to_string(),
+            protocol: protocol.to_string(),
+            started_at: summary.started_at().unwrap_or_default(),
+        },
+        ..Default::default()
+    };
+
+    let basecaller = report_basecaller(dir);
+    for output in BASECALL_DIRS.iter().map(|output| dir.join(output)) {
+        if !output.is_dir() {
+            continue;
+        }
+        let run = BasecallRun {
+            name: output
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: raw
+                .as_ref()
+                .map(|raw| raw.display().to_string())
+                .unwrap_or_default(),
+            output_path: output.display().to_string(),
+            basecaller: basecaller.clone(),
+            ..Default::default()
+        };
+        project.history.push(ProjectHistory {
+            action: ProjectAction::Basecall(BasecallLog {
+                status: BasecallStatus::Success,
+                command: Vec::new(),
+                ..BasecallLog::new(&run, String::new())
+            }),
+            created_by: current_user(),
+            created_at: project.sequencing.started_at,
+        });
+    }
+
+    if let Some(path) = sample_sheet::find_in(dir) {
+        SampleSheet::load(&path)?.apply(&mut project);
+    }
+    let usage = storage::measure(&project)?;
+    project.record_usage(usage);
+    Ok(project)
+}
+
+/// Work out what importing every run under `root` would create, without writing anything.
+/// Runs that can't be read, whose sample sheet is malformed, or that would be saved under the
+/// same name as an earlier run in the scan are skipped and listed in [`ImportPlan::failed`]
+/// rather than failing the whole scan.
+pub fn plan(root: &Path, existing: &[String]) -> Result<ImportPlan> {
+    let (paths, mut failed) = find_runs(root)?;
+    // Projects are saved as `<sanitized name>.yaml`, so names are compared sanitized
+    let existing = existing
+        .iter()
+        .map(|name| sanitize(name))
+        .collect::<HashSet<_>>();
+    let mut planned = HashMap::<String, PathBuf>::new();
+    let mut runs = Vec::new();
+    for path in paths {
+        match project_for_run(&path) {
+            Ok(project) => {
+                let file_name = sanitize(&project.name);
+                if let Some(first) = planned.get(&file_name) {
+                    failed.push(FailedRun {
+                        error: format!(
+                            "Same project name as {}, rename one of the runs to import both",
+                            first.display()
+                        ),
+                        path,
+                    });
+                    continue;
+                }
+                let exists = existing.contains(&file_name);
+                planned.insert(file_name, path.clone());
+                runs.push(RunImport {
+                    path,
+                    project,
+                    exists,
+                });
+            }
+            Err(e) => failed.push(FailedRun {
+                path,
+                error: e.to_string(),
+            }),
+        }
+    }
+    failed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ImportPlan { runs, failed })
+}
+
+/// The live basecaller's version and model, from the run's `report_*.json`
+fn report_basecaller(dir: &Path) -> Basecaller {
+    let report = find_file(dir, "report", "json")
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .unwrap_or_default();
+    let version = find_key(&report, "basecaller_build_version")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default();
+    let model = find_key(&report, "default basecall model")
+        .and_then(|value| value.get("string_value"))
+        .and_then(|value| value.as_str())
+        .unwrap_or_default();
+    Basecaller {
+        name: "MinKNOW".to_string(),
+        version: version.to_string(),
+        model: model.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Depth-first search for `key` anywhere in a JSON document
+fn find_key<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|value| find_key(value, key))),
+        serde_json::Value::Array(values) => values.iter().find_map(|value| find_key(value, key)),
+        _ => None,
+    }
+}
+
+/// Seconds since the unix epoch for an RFC 3339 timestamp such as
+/// `2023-03-09T13:34:49.117389+13:00`
+fn parse_timestamp(text: &str) -> Option<u64> {
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    let (clock, offset) = match time.strip_suffix('Z') {
+        Some(clock) => (clock, 0),
+        None => {
+            let i = time.rfind(['+', '-'])?;
+            let (hours, minutes) = time[i + 1..].split_once(':')?;
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            let sign = if time[i..].starts_with('-') { -1 } else { 1 };
+            (&time[..i], sign * offset)
+        }
+    };
+    let mut clock = clock.splitn(3, ':');
+    let hours = clock.next()?.parse::<i64>().ok()?;
+    let minutes = clock.next()?.parse::<i64>().ok()?;
+    let seconds = clock.next()?.parse::<f64>().ok()? as i64;
+
+    let seconds =
+        days_from_civil(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds - offset;
+    u64::try_from(seconds).ok()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const FINAL_SUMMARY: &str = "\
+instrument=MN34567
+position=MN34567
+flow_cell_id=FAW12345
+sample_id=liver
+protocol_group_id=mouse_2023
+protocol=sequencing/sequencing_MIN114_DNA_e8_2_400K:FLO-MIN114:SQK-LSK114:400
+protocol_run_id=5e5c3f04
+started=2023-03-09T13:34:49.117389+13:00
+acquisition_stopped=2023-03-10T13:34:50.123456+13:00
+basecalling_enabled=1
+";
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2023-03-09T13:34:49.117389+13:00"),
+            Some(1678322089)
+        );
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2024-02-29T23:00:00-01:00"),
+            Some(1709251200)
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_plan_import() {
+        let root = std::env::temp_dir().join(format!("fishtank-import-{}", std::process::id()));
+        let finished = root.join("mouse_2023/liver/20230309_1334_MN34567_FAW12345_5e5c3f04");
+        let unfinished = root.join("2024/20240101_0900_1A_PAQ00001_abcdef12");
+        for dir in [
+            finished.join("pod5_pass"),
+            finished.join("bam_pass"),
+            finished.join("fastq_pass"),
+            unfinished.join("fast5_pass"),
+        ] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(
+            finished.join("final_summary_FAW12345_5e5c3f04.txt"),
+            FINAL_SUMMARY,
+        )
+        .unwrap();
+        std::fs::write(
+            finished.join("report_FAW12345_20230309_1334_5e5c3f04.json"),
+            r#"{"protocol_run_info": {"meta_info": {"tags": {"default basecall model": {"string_value": "dna_r10.4.1_e8.2_400bps_hac@v4.1.0"}}}, "software_versions": {"basecaller_build_version": "7.0.9"}}}"#,
+        )
+        .unwrap();
+        std::fs::write(finished.join("pod5_pass/reads.pod5"), [0u8; 100]).unwrap();
+
+        let plan = plan(&root, &["20240101_0900_1A_PAQ00001_abcdef12".to_string()])
+            .unwrap()
+            .runs;
+        assert_eq!(
+            plan.iter()
+                .map(|run| (run.project.name.as_str(), run.exists))
+                .collect::<Vec<_>>(),
+            vec![
+                ("20240101_0900_1A_PAQ00001_abcdef12", true),
+                ("20230309_1334_MN34567_FAW12345_5e5c3f04", false),
+            ]
+        );
+
+        let project = &plan[1].project;
+        assert_eq!(
+            project.sequencing,
+            SequencingInfo {
+                flow_cell_id: "FAW12345".to_string(),
+                position_id: "MN34567".to_string(),
+                experiment_id: "mouse_2023".to_string(),
+                sample_id: "liver".to_string(),
+                kit: "SQK-LSK114".to_string(),
+                protocol: "sequencing/sequencing_MIN114_DNA_e8_2_400K:FLO-MIN114:SQK-LSK114:400"
+                    .to_string(),
+                started_at: 1678322089,
+            }
+        );
+        assert!(project.data_size >= 100);
+        let runs = project
+            .basecall_logs()
+            .map(|log| {
+                (
+                    log.basecall_run.name.as_str(),
+                    log.basecall_run.basecaller.model.as_str(),
+                    log.status,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            runs,
+            vec![
+                (
+                    "bam_pass",
+                    "dna_r10.4.1_e8.2_400bps_hac@v4.1.0",
+                    BasecallStatus::Success
+                ),
+                (
+                    "fastq_pass",
+                    "dna_r10.4.1_e8.2_400bps_hac@v4.1.0",
+                    BasecallStatus::Success
+                ),
+            ]
+        );
+        assert_eq!(plan[0].project.format, RawBasecallsFormat::Fast5);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_plan_skips_failed_runs() {
+        let root =
+            std::env::temp_dir().join(format!("fishtank-import-failed-{}", std::process::id()));
+        let good = root.join("20240101_0900_1A_PAQ00001_abcdef12");
+        let bad = root.join("20240102_0900_1A_PAQ00002_abcdef13");
+        for dir in [good.join("pod5_pass"), bad.join("pod5_pass")] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(bad.join("sample_sheet_PAQ00002.csv"), "kit\nSQK-LSK114\n").unwrap();
+
+        let plan = plan(&root, &[]).unwrap();
+        assert_eq!(
+            plan.runs
+                .iter()
+                .map(|run| run.path.clone())
+                .collect::<Vec<_>>(),
+            vec![good]
+        );
+        assert_eq!(plan.failed.len(), 1);
+        assert_eq!(plan.failed[0].path, bad);
+        assert!(plan.failed[0].error.starts_with("Invalid sample sheet"));
+
+        assert!(super::plan(&root.join("missing"), &[]).is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_plan_duplicate_names() {
+        let root = std::env::temp_dir().join(format!("fishtank-import-dup-{}", std::process::id()));
+        let run = root.join("a/PAQ00001");
+        let backup = root.join("b/PAQ00001");
+        let spaced = root.join("c/run 2");
+        let underscored = root.join("d/run_2");
+        let imported = root.join("e/run#3");
+        for dir in [&run, &backup, &spaced, &underscored, &imported] {
+            std::fs::create_dir_all(dir.join("pod5")).unwrap();
+        }
+
+        let plan = plan(&root, &["run 3".to_string()]).unwrap();
+        assert_eq!(
+            plan.runs
+                .iter()
+                .map(|run| (run.path.clone(), run.exists))
+                .collect::<Vec<_>>(),
+            vec![
+                (run.clone(), false),
+                (spaced.clone(), false),
+                (imported, true)
+            ]
+        );
+        assert_eq!(
+            plan.failed,
+            vec![
+                FailedRun {
+                    path: backup,
+                    error: format!(
+                        "Same project name as {}, rename one of the runs to import both",
+                        run.display()
+                    ),
+                },
+                FailedRun {
+                    path: underscored,
+                    error: format!(
+                        "Same project name as {}, rename one of the runs to import both",
+                        spaced.display()
+                    ),
+                },
+            ]
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+//! What launching a basecall would do, shown for confirmation before anything runs
+//!
+//! The plan holds the exact command line that would be recorded in `BasecallLog::command`, so it
+//! can also be copied or saved as a shell script and run by hand instead.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    project::{BasecallRun, Project},
+    sanitize, storage,
+    throughput::ThroughputTable,
+};
+use crate::executor::shell_quote;
+
+/// Extensions of the raw data files a basecall reads
+const RAW_EXTENSIONS: [&str; 4] = ["pod5", "fast5", "slow5", "blow5"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LaunchPlan {
+    pub project: String,
+    pub run: BasecallRun,
+    /// Fully expanded command line, as it would be recorded in `BasecallLog::command`
+    pub command: String,
+    /// Executor the run would start on
+    pub host: String,
+    pub input_files: usize,
+    pub input_bytes: u64,
+    pub estimated_output: u64,
+    /// Free space on the output's volume, `None` if it couldn't be read
+    pub free_space: Option<u64>,
+    /// Wall-clock seconds predicted from earlier runs, `None` until a run has finished on the
+    /// same executor
+    pub estimated_runtime: Option<u64>,
+}
+
+impl LaunchPlan {
+    /// Measure the run's input and estimate its output and runtime
+    pub fn new(
+        project: String,
+        run: BasecallRun,
+        command: String,
+        host: String,
+        output_ratio: f64,
+        projects: &[Project],
+    ) -> io::Result<Self> {
+        let (input_files, input_bytes) = count_input(Path::new(&run.path))?;
+        let free_space = storage::free_space_for(Path::new(&run.output_path)).ok();
+        let estimated_runtime = ThroughputTable::from_projects(projects).estimate(
+            &host,
+            &run.basecaller.model,
+            input_bytes,
+        );
+        Ok(Self {
+            project,
+            command,
+            host,
+            input_files,
+            input_bytes,
+            estimated_output: (input_bytes as f64 * output_ratio) as u64,
+            free_space,
+            estimated_runtime,
+            run,
+        })
+    }
+
+    /// Where the script for running this by hand is saved in `dir`
+    pub fn script_path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.sh", sanitize(&self.run.name)))
+    }
+
+    /// A shell script running the command, creating the output directory first
+    pub fn script(&self) -> String {
+        let output_dir = Path::new(&self.run.output_path)
+            .parent()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| ".".to_string());
+        format!(
+            "#!/usr/bin/env bash\n# Basecall of {} with {}, planned by fishtank\nset -euo pipefail\nmkdir -p {}\n{}\n",
+            self.project,
+            self.run.basecaller.config,
+            shell_quote(&output_dir),
+            self.command
+        )
+    }
+}
+
+/// Raw data files under `path` and their total size
+fn count_input(path: &Path) -> io::Result<(usize, u64)> {
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok((1, metadata.len()));
+    }
+    let (mut files, mut bytes) = (0, 0);
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let (dir_files, dir_bytes) = count_input(&entry.path())?;
+            files += dir_files;
+            bytes += dir_bytes;
+        } else if entry
+            .path()
+            .extension()
+            .is_some_and(|ext| RAW_EXTENSIONS.iter().any(|raw| ext == *raw))
+        {
+            files += 1;
+            bytes += entry.metadata()?.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::{BasecallLog, BasecallStatus, ProjectAction, ProjectHistory};
+
+    fn finished(host: &str, input_bytes: u64, seconds: u64) -> ProjectHistory {
+        ProjectHistory {
+            action: ProjectAction::Basecall(BasecallLog {
+                status: BasecallStatus::Success,
+                host: host.to_string(),
+                input_bytes,
+                finished_at: Some(1000 + seconds),
+                ..BasecallLog::new(&BasecallRun::default(), "dorado".to_string())
+            }),
+            created_by: String::new(),
+            created_at: 1000,
+        }
+    }
+
+    #[test]
+    fn test_plan() {
+        let dir = std::env::temp_dir().join(format!("fishtank-launch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("pod5/nested")).unwrap();
+        std::fs::write(dir.join("pod5/a.pod5"), [0; 600]).unwrap();
+        std::fs::write(dir.join("pod5/nested/b.pod5"), [0; 400]).unwrap();
+        std::fs::write(dir.join("pod5/notes.txt"), "not raw data").unwrap();
+
+        let projects = [Project {
+            history: vec![finished("gpu01", 100, 10), finished("local", 1000, 1)],
+            ..Default::default()
+        }];
+        let run = BasecallRun {
+            name: "hac-1".to_string(),
+            path: dir.join("pod5").display().to_string(),
+            output_path: dir.join("basecalls/hac-1.bam").display().to_string(),
+            ..Default::default()
+        };
+        let plan = LaunchPlan::new(
+            "run1".to_string(),
+            run,
+            "dorado basecaller hac pod5 > hac-1.bam".to_string(),
+            "gpu01".to_string(),
+            0.5,
+            &projects,
+        )
+        .unwrap();
+        assert_eq!((plan.input_files, plan.input_bytes), (2, 1000));
+        assert_eq!(plan.estimated_output, 500);
+        assert_eq!(plan.estimated_runtime, Some(100));
+        assert!(plan
+            .script()
+            .ends_with("\ndorado basecaller hac pod5 > hac-1.bam\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,615 @@
+//! Rules that compress, archive or delete a project's raw data once it has been basecalled
+//!
+//! Raw POD5/FAST5 dominates storage but is only needed again for re-basecalling, so once a
+//! basecall has been verified by its QC results the raw data can be moved somewhere cheaper or
+//! removed. Rules are previewed with [`plan`] and only carried out by [`apply`].
+
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    project::{BasecallStatus, Project, ProjectAction, RawBasecallsFormat},
+    sanitize,
+    storage::Volume,
+};
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// What to do with a rule's files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LifecycleAction {
+    /// gzip each file in place
+    Compress,
+    /// Move the files to the named storage volume
+    Archive {
+        volume: String,
+    },
+    Delete,
+}
+
+/// A rule such as "after a verified sup basecall and 90 days, archive POD5"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleRule {
+    /// Raw data the rule applies to
+    pub files: RawBasecallsFormat,
+    pub action: LifecycleAction,
+    /// Days to wait after the verified basecall
+    #[serde(default)]
+    pub after_days: u64,
+    /// Only apply once a basecall with a model containing this (e.g. `sup`) is verified
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Only apply to files a conversion to POD5 has read, once its read counts were verified
+    #[serde(default)]
+    pub converted: bool,
+}
+
+impl LifecycleRule {
+    pub fn describe(&self) -> String {
+        let files = match self.files {
+            RawBasecallsFormat::Fast5 => "FAST5",
+            RawBasecallsFormat::Slow5 => "SLOW5",
+            RawBasecallsFormat::Pod5 => "POD5",
+        };
+        let action = match &self.action {
+            LifecycleAction::Compress => format!("compress {files}"),
+            LifecycleAction::Archive { volume } => format!("move {files} to {volume}"),
+            LifecycleAction::Delete => format!("delete {files}"),
+        };
+        let mut conditions = vec![match &self.model {
+            Some(model) => format!("a verified {model} basecall"),
+            None => "a verified basecall".to_string(),
+        }];
+        if self.after_days > 0 {
+            conditions.push(format!("{} days", self.after_days));
+        }
+        if self.converted {
+            conditions.push("conversion to POD5".to_string());
+        }
+        format!("after {}, {action}", conditions.join(" and "))
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let extension = match self.files {
+            RawBasecallsFormat::Fast5 => ".fast5",
+            RawBasecallsFormat::Slow5 => ".slow5",
+            RawBasecallsFormat::Pod5 => ".pod5",
+        };
+        match self.action {
+            LifecycleAction::Compress => name.ends_with(extension),
+            _ => name.ends_with(extension) || name.ends_with(&format!("{extension}.gz")),
+        }
+    }
+}
+
+/// One file a rule would act on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleStep {
+    /// Index of the rule in the project's rules
+    pub rule: usize,
+    pub action: LifecycleAction,
+    pub path: String,
+    /// Where an archived file is moved to, or the compressed file's name
+    pub target: Option<String>,
+    pub bytes: u64,
+}
+
+/// What a rule did, as recorded in the project history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleLog {
+    pub status: BasecallStatus,
+    pub rule: String,
+    pub action: LifecycleAction,
+    /// Files that were acted on, with their targets if they were moved or compressed
+    pub files: Vec<(String, Option<String>)>,
+    pub bytes: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Raw data paths in the project record that moved, and where to, see [`Project::relocate`]
+    #[serde(default)]
+    pub relocated: Vec<(String, String)>,
+}
+
+/// When the latest basecall verified by QC results finished, optionally with a given model
+fn verified_basecall(project: &Project, model: Option<&str>) -> Option<u64> {
+    project
+        .history
+        .iter()
+        .filter_map(|entry| match &entry.action {
+            ProjectAction::Basecall(log) => Some((entry.created_at, log)),
+            _ => None,
+        })
+        .filter(|(_, log)| log.status == BasecallStatus::Success)
+        .filter(|(_, log)| log.results.as_ref().is_some_and(|r| r.read_count > 0))
+        .filter(|(_, log)| {
+            model.is_none_or(|model| log.basecall_run.basecaller.model.contains(model))
+        })
+        .map(|(created_at, _)| created_at)
+        .next_back()
+}
+
+/// Inputs and outputs of the conversions to POD5 whose read counts matched
+fn verified_conversions(project: &Project) -> Vec<(PathBuf, PathBuf)> {
+    project
+        .history
+        .iter()
+        .filter_map(|entry| match &entry.action {
+            ProjectAction::Convert(log) => Some(log),
+            _ => None,
+        })
+        .filter(|log| log.status == BasecallStatus::Success)
+        .filter(|log| log.check.as_ref().is_some_and(|check| check.verified()))
+        .map(|log| (PathBuf::from(&log.input), PathBuf::from(&log.output_dir)))
+        .collect()
+}
+
+fn is_raw(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    [".pod5", ".fast5", ".slow5"]
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+fn files_in(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            files_in(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Raw data is the only copy of a run until one of its basecalls has been verified
+fn ensure_verified(project: &Project) -> Result<()> {
+    match verified_basecall(project, None) {
+        Some(_) => Ok(()),
+        None => Err(eyre!(
+            "{} has no verified basecall, compute QC for a successful basecall first",
+            project.name
+        )),
+    }
+}
+
+/// Work out what the project's rules would do at `now`, without touching anything
+pub fn plan(
+    project: &Project,
+    rules: &[LifecycleRule],
+    volumes: &[Volume],
+    now: u64,
+) -> Result<Vec<LifecycleStep>> {
+    ensure_verified(project)?;
+    let data = Path::new(&project.data_location);
+    let mut files = Vec::new();
+    if !project.data_location.is_empty() && data.is_dir() {
+        files_in(data, &mut files)?;
+    }
+    files.sort();
+    let conversions = verified_conversions(project);
+
+    let mut steps = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        let Some(verified_at) = verified_basecall(project, rule.model.as_deref()) else {
+            continue;
+        };
+        if now < verified_at + rule.after_days * DAY {
+            continue;
+        }
+        let archive = match &rule.action {
+            LifecycleAction::Archive { volume } => Some(
+                volumes
+                    .iter()
+                    .find(|v| &v.name == volume)
+                    .ok_or_else(|| eyre!("No storage volume named {volume}"))?
+                    .path
+                    .join(sanitize(&project.name)),
+            ),
+            _ => None,
+        };
+        // A partial or failed conversion leaves POD5 behind, so each file needs its own
+        let converted = |file: &Path| conversions.iter().any(|(input, _)| file.starts_with(input));
+        for file in files
+            .iter()
+            .filter(|file| rule.matches(file) && (!rule.converted || converted(file)))
+        {
+            // Steps from an earlier rule already cover this file
+            if steps
+                .iter()
+                .any(|step: &LifecycleStep| Path::new(&step.path) == file)
+            {
+                continue;
+            }
+            let target = match (&rule.action, &archive) {
+                (LifecycleAction::Archive { .. }, Some(archive)) => {
+                    let relative = file.strip_prefix(data).unwrap_or(file);
+                    Some(archive.join(relative).display().to_string())
+                }
+                (LifecycleAction::Compress, _) => Some(format!("{}.gz", file.display())),
+                _ => None,
+            };
+            steps.push(LifecycleStep {
+                rule: index,
+                action: rule.action.clone(),
+                path: file.display().to_string(),
+                target,
+                bytes: std::fs::metadata(file)?.len(),
+            });
+        }
+    }
+    Ok(steps)
+}
+
+/// Move a file, copying it when the target is on another volume
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    let copied = std::fs::copy(from, to)?;
+    if copied != std::fs::metadata(from)?.len() {
+        return Err(io::Error::other(format!(
+            "copy of {} to {} is incomplete",
+            from.display(),
+            to.display()
+        )));
+    }
+    std::fs::remove_file(from)
+}
+
+fn compress_file(from: &Path, to: &Path) -> io::Result<()> {
+    let mut input = File::open(from)?;
+    let mut encoder = GzEncoder::new(File::create(to)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    std::fs::remove_file(from)
+}
+
+/// Where a raw data path recorded in `project` ends up after a rule's files were acted on
+///
+/// A file that was moved or compressed follows it. A directory follows its archived files, or
+/// is replaced by the POD5 converted from deleted FAST5, once no raw data is left in it, or
+/// nothing at all unless `raw_only`.
+fn relocation(
+    project: &Project,
+    action: &LifecycleAction,
+    done: &[(String, Option<String>)],
+    path: &Path,
+    raw_only: bool,
+) -> Option<PathBuf> {
+    if let Some((_, Some(target))) = done.iter().find(|(file, _)| Path::new(file) == path) {
+        return Some(PathBuf::from(target));
+    }
+    let (file, target) = done
+        .iter()
+        .find(|(file, _)| Path::new(file).starts_with(path))?;
+    let mut remaining = Vec::new();
+    if path.is_dir() {
+        files_in(path, &mut remaining).ok()?;
+    }
+    if remaining.iter().any(|file| !raw_only || is_raw(file)) {
+        return None;
+    }
+    match (action, target) {
+        (LifecycleAction::Archive { .. }, Some(target)) => {
+            let depth = Path::new(file)
+                .strip_prefix(path)
+                .ok()?
+                .components()
+                .count();
+            Some(Path::new(target).ancestors().nth(depth)?.to_path_buf())
+        }
+        (LifecycleAction::Delete, _) => {
+            let (input, output) = verified_conversions(project)
+                .into_iter()
+                .find(|(input, _)| path.starts_with(input))?;
+            let relative = path.strip_prefix(&input).ok()?;
+            Some(if relative.as_os_str().is_empty() {
+                output
+            } else {
+                output.join(relative)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Carry out a previewed plan, stopping a rule at its first failure
+///
+/// Returns one log per rule for the project history, with the raw data paths that moved.
+pub fn apply(
+    project: &Project,
+    rules: &[LifecycleRule],
+    steps: &[LifecycleStep],
+) -> Result<Vec<LifecycleLog>> {
+    ensure_verified(project)?;
+    let mut logs: Vec<(usize, LifecycleLog)> = Vec::new();
+    for step in steps {
+        let Some(rule) = rules.get(step.rule) else {
+            continue;
+        };
+        if !logs.iter().any(|(index, _)| *index == step.rule) {
+            logs.push((
+                step.rule,
+                LifecycleLog {
+                    status: BasecallStatus::Success,
+                    rule: rule.describe(),
+                    action: step.action.clone(),
+                    files: Vec::new(),
+                    bytes: 0,
+                    error: None,
+                    relocated: Vec::new(),
+                },
+            ));
+        }
+        let (_, log) = logs
+            .iter_mut()
+            .find(|(index, _)| *index == step.rule)
+            .expect("a log for every rule");
+        if log.status == BasecallStatus::Failed {
+            continue;
+        }
+
+        let path = Path::new(&step.path);
+        let result = match (&step.action, &step.target) {
+            (LifecycleAction::Delete, _) => std::fs::remove_file(path),
+            (LifecycleAction::Archive { .. }, Some(target)) => move_file(path, Path::new(target)),
+            (LifecycleAction::Compress, Some(target)) => compress_file(path, Path::new(target)),
+            _ => Err(io::Error::other(format!("no target for {}", step.path))),
+        };
+        match result {
+            Ok(()) => {
+                log.files.push((step.path.clone(), step.target.clone()));
+                log.bytes += step.bytes;
+            }
+            Err(e) => {
+                log.status = BasecallStatus::Failed;
+                log.error = Some(format!("{}: {e}", step.path));
+            }
+        }
+    }
+
+    // Basecalls only read raw data, but the data location also holds their output
+    let mut paths = vec![(project.data_location.clone(), false)];
+    paths.extend(
+        project
+            .basecall_logs()
+            .map(|log| (log.basecall_run.path.clone(), true))
+            .chain(project.raw_path.clone().map(|path| (path, true))),
+    );
+    paths.retain(|(path, _)| !path.is_empty());
+    paths.sort();
+    paths.dedup_by(|a, b| a.0 == b.0);
+    for (_, log) in logs.iter_mut() {
+        for (path, raw_only) in &paths {
+            let path = Path::new(path);
+            if let Some(to) = relocation(project, &log.action, &log.files, path, *raw_only) {
+                log.relocated
+                    .push((path.display().to_string(), to.display().to_string()));
+            }
+        }
+    }
+    Ok(logs.into_iter().map(|(_, log)| log).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::{
+        conversion::ConversionCheck,
+        project::{
+            BasecallLog, BasecallResults, BasecallRun, Basecaller, ConvertLog, ProjectHistory,
+        },
+    };
+
+    fn converted(input: &Path, output: &Path, pod5_reads: u64) -> ProjectHistory {
+        let check = ConversionCheck {
+            fast5_reads: 10,
+            pod5_reads,
+            removed_originals: false,
+        };
+        ProjectHistory {
+            action: ProjectAction::Convert(ConvertLog {
+                status: if check.verified() {
+                    BasecallStatus::Success
+                } else {
+                    BasecallStatus::Failed
+                },
+                input: input.display().to_string(),
+                output_dir: output.display().to_string(),
+                command: Vec::new(),
+                check: Some(check),
+            }),
+            created_by: String::new(),
+            created_at: 900,
+        }
+    }
+
+    fn project_in(dir: &Path, input: &Path, verified: bool) -> Project {
+        let run = BasecallRun {
+            path: input.display().to_string(),
+            basecaller: Basecaller {
+                model: "dna_r10.4.1_e8.2_400bps_sup@v4.3.0".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut log = BasecallLog::new(&run, String::new());
+        log.status = BasecallStatus::Success;
+        if verified {
+            log.results = Some(BasecallResults {
+                read_count: 10,
+                ..Default::default()
+            });
+        }
+        Project {
+            name: "run 1".to_string(),
+            data_location: dir.display().to_string(),
+            history: vec![ProjectHistory {
+                action: ProjectAction::Basecall(log),
+                created_by: String::new(),
+                created_at: 1000,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_and_apply() {
+        let root = std::env::temp_dir().join(format!("fishtank-lifecycle-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dir = root.join("data");
+        std::fs::create_dir_all(dir.join("pod5")).unwrap();
+        std::fs::create_dir_all(dir.join("fast5")).unwrap();
+        std::fs::write(dir.join("pod5/a.pod5"), vec![1; 100]).unwrap();
+        std::fs::write(dir.join("fast5/a.fast5"), vec![2; 50]).unwrap();
+
+        let volumes = vec![Volume {
+            name: "archive".to_string(),
+            path: root.join("archive"),
+        }];
+        let rules = vec![
+            LifecycleRule {
+                files: RawBasecallsFormat::Pod5,
+                action: LifecycleAction::Archive {
+                    volume: "archive".to_string(),
+                },
+                after_days: 90,
+                model: Some("sup".to_string()),
+                converted: false,
+            },
+            LifecycleRule {
+                files: RawBasecallsFormat::Fast5,
+                action: LifecycleAction::Delete,
+                after_days: 0,
+                model: None,
+                converted: true,
+            },
+        ];
+        assert_eq!(
+            rules[0].describe(),
+            "after a verified sup basecall and 90 days, move POD5 to archive"
+        );
+
+        let unverified = project_in(&dir, &dir.join("pod5"), false);
+        assert!(plan(&unverified, &rules, &volumes, 2000).is_err());
+
+        let mut project = project_in(&dir, &dir.join("pod5"), true);
+        assert!(plan(&project, &rules, &volumes, 2000).unwrap().is_empty());
+        project
+            .history
+            .insert(0, converted(&dir.join("fast5"), &dir.join("pod5"), 10));
+        // Too early for the archive rule, but the FAST5 has been converted
+        let steps = plan(&project, &rules, &volumes, 2000).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].action, LifecycleAction::Delete);
+
+        let steps = plan(&project, &rules, &volumes, 1000 + 90 * DAY).unwrap();
+        assert_eq!(steps.len(), 2);
+        let archived = root.join("archive/run_1/pod5/a.pod5");
+        assert_eq!(steps[0].target, Some(archived.display().to_string()));
+
+        assert!(apply(&unverified, &rules, &steps).is_err());
+        let logs = apply(&project, &rules, &steps).unwrap();
+        assert_eq!(logs.len(), 2);
+        assert!(logs.iter().all(|log| log.status == BasecallStatus::Success));
+        assert_eq!(logs[0].bytes, 100);
+        assert!(archived.exists());
+        assert!(!dir.join("pod5/a.pod5").exists());
+        assert!(!dir.join("fast5/a.fast5").exists());
+
+        // Nothing is left behind, so the project now lives in the archive
+        let archive = root.join("archive/run_1");
+        assert_eq!(
+            logs[0].relocated,
+            vec![
+                (dir.display().to_string(), archive.display().to_string()),
+                (
+                    dir.join("pod5").display().to_string(),
+                    archive.join("pod5").display().to_string()
+                ),
+            ]
+        );
+        assert!(logs[1].relocated.is_empty());
+        for (from, to) in &logs[0].relocated {
+            project.relocate(from, to);
+        }
+        assert_eq!(project.data_location, archive.display().to_string());
+        assert_eq!(
+            project.basecall_logs().next().unwrap().basecall_run.path,
+            archive.join("pod5").display().to_string()
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_partial_conversion() {
+        let dir = std::env::temp_dir().join(format!("fishtank-partial-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for sub in ["fast5_pass", "pod5_pass", "fast5_fail", "pod5_fail"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        std::fs::write(dir.join("fast5_pass/a.fast5"), vec![1; 10]).unwrap();
+        std::fs::write(dir.join("pod5_pass/a.pod5"), vec![1; 10]).unwrap();
+        std::fs::write(dir.join("fast5_fail/b.fast5"), vec![2; 10]).unwrap();
+        // Stopped partway, so this POD5 is missing reads
+        std::fs::write(dir.join("pod5_fail/b.pod5"), vec![2; 4]).unwrap();
+
+        let mut project = project_in(&dir, &dir.join("fast5_pass"), true);
+        project.history.insert(
+            0,
+            converted(&dir.join("fast5_pass"), &dir.join("pod5_pass"), 10),
+        );
+        project.history.insert(
+            0,
+            converted(&dir.join("fast5_fail"), &dir.join("pod5_fail"), 4),
+        );
+        let rules = vec![LifecycleRule {
+            files: RawBasecallsFormat::Fast5,
+            action: LifecycleAction::Delete,
+            after_days: 0,
+            model: None,
+            converted: true,
+        }];
+
+        let steps = plan(&project, &rules, &[], 2000).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].path,
+            dir.join("fast5_pass/a.fast5").display().to_string()
+        );
+
+        let logs = apply(&project, &rules, &steps).unwrap();
+        assert!(dir.join("fast5_fail/b.fast5").exists());
+        // The basecall input is gone, new runs read the converted POD5 instead
+        assert_eq!(
+            logs[0].relocated,
+            vec![(
+                dir.join("fast5_pass").display().to_string(),
+                dir.join("pod5_pass").display().to_string()
+            )]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,355 @@
+//! Checksum manifests for a project's data, to check it arrived intact after being moved
+//!
+//! A manifest is a tab separated `path`, `size` and `sha256` listing of the raw data below the
+//! project's data location, with paths relative to it, kept next to the project record. Only
+//! raw reads and MinKNOW's run summaries are listed, as basecalls and QC written into the data
+//! location later are expected to come and go.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::now;
+
+const HEADER: &str = "path\tsize\tsha256";
+
+/// Extensions of raw read files, optionally gzipped
+const RAW_EXTENSIONS: &[&str] = &[".pod5", ".fast5", ".slow5", ".blow5"];
+
+/// Prefixes of the summaries MinKNOW writes alongside the raw reads
+const RUN_SUMMARIES: &[&str] = &[
+    "final_summary",
+    "sequencing_summary",
+    "report_",
+    "pore_activity",
+    "throughput",
+    "duty_time",
+    "sample_sheet",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path relative to the project's data location, with `/` separators
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// Outcome of re-hashing a project's data against its manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub verified_at: u64,
+    /// Files that matched the manifest
+    pub ok: usize,
+    /// In the manifest but no longer on disk
+    pub missing: Vec<String>,
+    /// On disk but not in the manifest
+    pub extra: Vec<String>,
+    /// Size or checksum differs from the manifest
+    pub corrupted: Vec<String>,
+}
+
+impl VerifyReport {
+    /// The counts kept in the project record
+    pub fn summary(&self) -> Verification {
+        Verification {
+            verified_at: self.verified_at,
+            ok: self.ok,
+            missing: self.missing.len(),
+            extra: self.extra.len(),
+            corrupted: self.corrupted.len(),
+        }
+    }
+
+    /// Write the files that didn't match as tab separated `status` and `path` lines
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut text = "status\tpath\n".to_string();
+        for (status, paths) in [
+            ("missing", &self.missing),
+            ("extra", &self.extra),
+            ("corrupted", &self.corrupted),
+        ] {
+            for path in paths {
+                text.push_str(&format!("{status}\t{path}\n"));
+            }
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// File counts of the latest verification, with the paths in the report next to the manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verification {
+    pub verified_at: u64,
+    pub ok: usize,
+    #[serde(deserialize_with = "deserialize_count")]
+    pub missing: usize,
+    #[serde(deserialize_with = "deserialize_count")]
+    pub extra: usize,
+    #[serde(deserialize_with = "deserialize_count")]
+    pub corrupted: usize,
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        self.missing == 0 && self.extra == 0 && self.corrupted == 0
+    }
+}
+
+/// Read a count, or the list of paths older project records have in its place
+fn deserialize_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Recorded {
+        Count(usize),
+        Paths(Vec<String>),
+    }
+    Ok(match Recorded::deserialize(deserializer)? {
+        Recorded::Count(count) => count,
+        Recorded::Paths(paths) => paths.len(),
+    })
+}
+
+/// Whether a manifest covers the file at `relative`: raw reads and MinKNOW's run summaries
+fn is_raw_data(relative: &str) -> bool {
+    let name = relative.rsplit('/').next().unwrap_or_default();
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    RAW_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+        || RUN_SUMMARIES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// SHA-256 of a file as lowercase hex
+pub fn sha256(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn files_in(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            files_in(root, &path, files)?;
+        } else if file_type.is_file() {
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Every raw data file below `root` by relative path, sorted
+fn list(root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    files_in(root, root, &mut files)?;
+    files.retain(|(relative, _)| is_raw_data(relative));
+    files.sort();
+    Ok(files)
+}
+
+impl Manifest {
+    /// Hash the raw data below `root`
+    pub fn generate(root: &Path) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        for (relative, path) in list(root)? {
+            entries.push(ManifestEntry {
+                path: relative,
+                size: std::fs::metadata(&path)?.len(),
+                sha256: sha256(&path)?,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.is_empty() || (i == 0 && line == HEADER) {
+                continue;
+            }
+            let mut fields = line.rsplitn(3, '\t');
+            let (Some(sha256), Some(size), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(eyre!("line {}: expected path, size and sha256", i + 1));
+            };
+            entries.push(ManifestEntry {
+                path: path.to_string(),
+                size: size
+                    .parse()
+                    .map_err(|_| eyre!("line {}: invalid size {size}", i + 1))?,
+                sha256: sha256.to_string(),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut text = format!("{HEADER}\n");
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{}\t{}\t{}\n",
+                entry.path, entry.size, entry.sha256
+            ));
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Re-hash the files below `root` and compare them with the manifest
+    pub fn verify(&self, root: &Path) -> io::Result<VerifyReport> {
+        let expected = self
+            .entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry))
+            .collect::<HashMap<_, _>>();
+        let mut report = VerifyReport {
+            verified_at: now(),
+            ..Default::default()
+        };
+        let mut seen = Vec::new();
+        for (relative, path) in list(root)? {
+            let Some(entry) = expected.get(relative.as_str()) else {
+                report.extra.push(relative);
+                continue;
+            };
+            // Only hash files whose size still matches
+            if std::fs::metadata(&path)?.len() == entry.size && sha256(&path)? == entry.sha256 {
+                report.ok += 1;
+            } else {
+                report.corrupted.push(relative.clone());
+            }
+            seen.push(relative);
+        }
+        report.missing = self
+            .entries
+            .iter()
+            .filter(|entry| !seen.contains(&entry.path))
+            .map(|entry| entry.path.clone())
+            .collect();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_sha256() {
+        let dir = std::env::temp_dir().join(format!("fishtank-sha256-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("abc"), "abc").unwrap();
+        assert_eq!(
+            sha256(&dir.join("abc")).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_and_verify() {
+        let dir = std::env::temp_dir().join(format!("fishtank-manifest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("data/pod5")).unwrap();
+        std::fs::write(dir.join("data/pod5/a.pod5"), "aaaa").unwrap();
+        std::fs::write(dir.join("data/pod5/b.pod5"), "bbbb").unwrap();
+        std::fs::write(dir.join("data/final_summary.txt"), "summary").unwrap();
+        std::fs::create_dir_all(dir.join("data/bam_pass")).unwrap();
+        std::fs::write(dir.join("data/bam_pass/calls.bam"), "bam").unwrap();
+
+        let manifest = Manifest::generate(&dir.join("data")).unwrap();
+        assert_eq!(manifest.entries.len(), 3);
+        assert_eq!(manifest.entries[0].path, "final_summary.txt");
+        manifest.save(&dir.join("run.manifest.tsv")).unwrap();
+        let manifest = Manifest::load(&dir.join("run.manifest.tsv")).unwrap();
+        assert!(manifest
+            .verify(&dir.join("data"))
+            .unwrap()
+            .summary()
+            .is_ok());
+
+        std::fs::write(dir.join("data/pod5/a.pod5"), "aaab").unwrap();
+        std::fs::remove_file(dir.join("data/pod5/b.pod5")).unwrap();
+        std::fs::write(dir.join("data/pod5/c.pod5"), "cccc").unwrap();
+        // Later basecalls aren't raw data, so they aren't extra
+        std::fs::write(dir.join("data/bam_pass/sup.bam"), "sup").unwrap();
+        let report = manifest.verify(&dir.join("data")).unwrap();
+        assert_eq!(report.ok, 1);
+        assert_eq!(report.corrupted, vec!["pod5/a.pod5"]);
+        assert_eq!(report.missing, vec!["pod5/b.pod5"]);
+        assert_eq!(report.extra, vec!["pod5/c.pod5"]);
+        assert_eq!(
+            report.summary(),
+            Verification {
+                verified_at: report.verified_at,
+                ok: 1,
+                missing: 1,
+                extra: 1,
+                corrupted: 1,
+            }
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verification_from_paths() {
+        let verification: Verification = serde_yaml_ng::from_str(
+            "verified_at: 10\nok: 2\nmissing: [a.pod5, b.pod5]\nextra: []\ncorrupted: 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            verification,
+            Verification {
+                verified_at: 10,
+                ok: 2,
+                missing: 2,
+                extra: 0,
+                corrupted: 1,
+            }
+        );
+        assert!(!verification.is_ok());
+    }
+}
@@ -0,0 +1,153 @@
+//! Summaries of the modified base calls dorado writes as `MM`/`ML` tags
+//!
+//! Modification models such as `5mCG_5hmCG` or `6mA` give every candidate base a probability of
+//! carrying each modification. A call counts as modified when that probability is at least 50%.
+
+use std::{collections::BTreeMap, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::bam;
+
+/// `ML` values are probabilities scaled to 0-255, this is the first at or above 50%
+const MODIFIED_THRESHOLD: u8 = 128;
+
+/// Calls for one modification across a run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModificationSummary {
+    /// Canonical base the modification is called on, e.g. `C`
+    pub base: String,
+    /// SAM modification code, e.g. `m` or a ChEBI number
+    pub code: String,
+    pub calls: u64,
+    pub modified: u64,
+}
+
+impl ModificationSummary {
+    /// Common name of the modification, e.g. `5mC`
+    pub fn name(&self) -> String {
+        match (self.base.as_str(), self.code.as_str()) {
+            ("C", "m") => "5mC".to_string(),
+            ("C", "h") => "5hmC".to_string(),
+            ("A", "a") => "6mA".to_string(),
+            ("C", "21839") => "4mC".to_string(),
+            (base, code) => format!("{base}+{code}"),
+        }
+    }
+
+    pub fn percent_modified(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.modified as f64 * 100.0 / self.calls as f64
+        }
+    }
+}
+
+/// Add one read's calls, `mm` being e.g. `C+hm?,0,2;A+a.,5;` and `ml` a probability per
+/// position and code in the same order
+fn add_read(mm: &str, ml: &[u8], counts: &mut BTreeMap<(String, String), (u64, u64)>) {
+    let mut ml = ml.iter();
+    for entry in mm.split(';').filter(|entry| !entry.is_empty()) {
+        let mut fields = entry.split(',');
+        let header = fields.next().unwrap_or_default();
+        let positions = fields.count();
+        let header = header.trim_end_matches(['.', '?']);
+        let (Some(base), Some(codes)) = (header.get(..1), header.get(2..)) else {
+            continue;
+        };
+        // Codes are single letters, or one numeric ChEBI id
+        let codes = if codes.chars().all(|c| c.is_ascii_digit()) {
+            vec![codes.to_string()]
+        } else {
+            codes.chars().map(String::from).collect()
+        };
+        for _ in 0..positions {
+            for code in &codes {
+                let Some(&probability) = ml.next() else {
+                    return;
+                };
+                let (calls, modified) = counts.entry((base.to_string(), code.clone())).or_default();
+                *calls += 1;
+                if probability >= MODIFIED_THRESHOLD {
+                    *modified += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Modification calls of every primary read in a BAM file
+pub fn summarise(path: &Path) -> io::Result<Vec<ModificationSummary>> {
+    let mut reader = bam::Reader::from_path(path)?;
+    let mut record = bam::Record::default();
+    let mut counts = BTreeMap::new();
+    while reader.read(&mut record)? {
+        if !record.is_primary() {
+            continue;
+        }
+        // Older basecallers wrote the tags before they were standardised as `Mm`/`Ml`
+        let mm = record.aux_str(b"MM").or_else(|| record.aux_str(b"Mm"));
+        let ml = record.aux_bytes(b"ML").or_else(|| record.aux_bytes(b"Ml"));
+        if let (Some(mm), Some(ml)) = (mm, ml) {
+            add_read(mm, ml, &mut counts);
+        }
+    }
+    Ok(counts
+        .into_iter()
+        .map(|((base, code), (calls, modified))| ModificationSummary {
+            base,
+            code,
+            calls,
+            modified,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::bam::testing::*;
+
+    fn mod_tags(mm: &str, ml: &[u8]) -> Vec<u8> {
+        let mut aux = b"MMZ".to_vec();
+        aux.extend_from_slice(mm.as_bytes());
+        aux.push(0);
+        aux.extend_from_slice(b"MLBC");
+        aux.extend_from_slice(&(ml.len() as u32).to_le_bytes());
+        aux.extend_from_slice(ml);
+        aux
+    }
+
+    #[test]
+    fn test_summarise() {
+        let path = std::env::temp_dir().join(format!("fishtank-mods-{}.bam", std::process::id()));
+        let mut read1 = TestRecord::new("read1", vec![20; 10]);
+        // Two CpGs with a 5hmC then 5mC probability each, then one 6mA call
+        read1.aux = mod_tags("C+hm?,0,3;A+a.,1;", &[10, 240, 200, 20, 130]);
+        let mut read2 = TestRecord::new("read2", vec![20; 10]);
+        read2.aux = mod_tags("C+hm?,2;", &[5, 100]);
+        write_bam(
+            &path,
+            &[read1, read2, TestRecord::new("read3", vec![20; 10])],
+        );
+
+        let summary = summarise(&path).unwrap();
+        let names = summary
+            .iter()
+            .map(|m| (m.name(), m.calls, m.modified))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("6mA".to_string(), 1, 1),
+                ("5hmC".to_string(), 3, 1),
+                ("5mC".to_string(), 3, 1),
+            ]
+        );
+        assert!((summary[2].percent_modified() - 33.33).abs() < 0.01);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,309 @@
+//! Named basecall configurations for the runs made again and again
+//!
+//! Presets come from the `presets` section of the config file and from YAML files in the
+//! `presets` directory next to it, one preset per file so they can be shared by copying the file.
+//! Presets edited in the settings pane are saved to that directory, and a file replaces a config
+//! preset with the same name.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng as serde_yaml;
+
+use super::{
+    now,
+    project::{BasecallRun, Basecaller, EmitFormat, Project, RunType},
+    sanitize,
+};
+
+/// Labels of the values `Preset::to_fields` and `Preset::from_fields` convert between
+pub const FIELDS: [&str; 9] = [
+    "Name",
+    "Basecaller",
+    "Model",
+    "Kit",
+    "Min qscore",
+    "Emit",
+    "Modified bases",
+    "Run type",
+    "Extra args",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    /// Basecaller name, `dorado` if unset
+    #[serde(default)]
+    pub basecaller: String,
+    pub model: String,
+    #[serde(default)]
+    pub kit: Option<String>,
+    #[serde(default)]
+    pub min_qscore: Option<u32>,
+    #[serde(default)]
+    pub emit: EmitFormat,
+    #[serde(default)]
+    pub modified_bases: Vec<String>,
+    #[serde(default)]
+    pub run_type: RunType,
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// File the preset was loaded from, `None` for presets in the config file
+    #[serde(skip)]
+    pub file: Option<PathBuf>,
+}
+
+impl Preset {
+    pub fn dir_in(config_dir: &Path) -> PathBuf {
+        config_dir.join("presets")
+    }
+
+    /// Load every preset file in `dir`, sorted by name
+    pub fn load_all(dir: &Path) -> Result<Vec<Self>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut presets = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+            {
+                let file = std::fs::File::open(&path)?;
+                let preset: Self = serde_yaml::from_reader(file)
+                    .map_err(|e| eyre!("Invalid preset {}: {e}", path.display()))?;
+                presets.push(Self {
+                    file: Some(path),
+                    ..preset
+                });
+            }
+        }
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(presets)
+    }
+
+    /// Config presets followed by the files in `dir`, a file replacing a config preset of the
+    /// same name
+    pub fn merged(config: &[Self], dir: &Path) -> Result<Vec<Self>> {
+        let files = Self::load_all(dir)?;
+        let mut presets = config
+            .iter()
+            .filter(|preset| !files.iter().any(|file| file.name == preset.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        presets.extend(files);
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(presets)
+    }
+
+    /// Write the preset to its own file in `dir`, returning the path
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.yaml", sanitize(&self.name)));
+        let file = std::fs::File::create(&path)?;
+        serde_yaml::to_writer(file, self)?;
+        Ok(path)
+    }
+
+    /// A run of `project`'s raw data with this preset, written under its `basecalls` directory
+    pub fn run(&self, project: &Project) -> BasecallRun {
+        let path = project.raw_input();
+        let name = format!("{}-{}", sanitize(&self.name), now());
+        let output_path = Path::new(&project.data_location)
+            .join("basecalls")
+            .join(format!("{name}.{}", self.emit.extension()));
+        let basecaller = if self.basecaller.is_empty() {
+            "dorado"
+        } else {
+            &self.basecaller
+        };
+        BasecallRun {
+            name,
+            path,
+            output_path: output_path.display().to_string(),
+            basecaller: Basecaller {
+                name: basecaller.to_string(),
+                config: self.name.clone(),
+                model: self.model.clone(),
+                ..Default::default()
+            },
+            kit_name: self.kit.clone(),
+            modified_bases: self.modified_bases.clone(),
+            run_type: self.run_type,
+            min_qscore: self.min_qscore,
+            emit: self.emit,
+            extra_args: self.extra_args.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// The preset as text for the settings form, in the order of `FIELDS`
+    pub fn to_fields(&self) -> [String; 9] {
+        [
+            self.name.clone(),
+            self.basecaller.clone(),
+            self.model.clone(),
+            self.kit.clone().unwrap_or_default(),
+            self.min_qscore.map(|q| q.to_string()).unwrap_or_default(),
+            self.emit.extension().to_string(),
+            self.modified_bases.join(","),
+            format!("{:?}", self.run_type).to_lowercase(),
+            self.extra_args.join(" "),
+        ]
+    }
+
+    /// Parse the values typed into the settings form
+    pub fn from_fields(fields: &[String; 9]) -> Result<Self> {
+        let [name, basecaller, model, kit, min_qscore, emit, modified_bases, run_type, extra_args] =
+            fields.clone().map(|field| field.trim().to_string());
+        if name.is_empty() || model.is_empty() {
+            return Err(eyre!("A preset needs a name and a model"));
+        }
+        let min_qscore = match min_qscore.as_str() {
+            "" => None,
+            qscore => Some(
+                qscore
+                    .parse()
+                    .map_err(|_| eyre!("Min qscore must be a whole number, not {qscore}"))?,
+            ),
+        };
+        let emit = match emit.to_lowercase().as_str() {
+            "" | "bam" => EmitFormat::Bam,
+            "fastq" => EmitFormat::Fastq,
+            "sam" => EmitFormat::Sam,
+            other => return Err(eyre!("Emit must be bam, fastq or sam, not {other}")),
+        };
+        let run_type = match run_type.to_lowercase().as_str() {
+            "" | "simplex" => RunType::Simplex,
+            "duplex" => RunType::Duplex,
+            other => return Err(eyre!("Run type must be simplex or duplex, not {other}")),
+        };
+        Ok(Self {
+            name,
+            basecaller,
+            model,
+            kit: (!kit.is_empty()).then_some(kit),
+            min_qscore,
+            emit,
+            modified_bases: modified_bases
+                .split([',', ' '])
+                .filter(|model| !model.is_empty())
+                .map(String::from)
+                .collect(),
+            run_type,
+            extra_args: extra_args.split_whitespace().map(String::from).collect(),
+            file: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::{BasecallLog, BasecallStatus, ProjectAction, ProjectHistory};
+
+    #[test]
+    fn test_form_round_trip_and_run() {
+        let mut fields = Preset::default().to_fields();
+        for (field, value) in fields.iter_mut().zip([
+            "sup 5mC",
+            "",
+            "sup",
+            "SQK-NBD114-24",
+            "10",
+            "fastq",
+            "5mCG_5hmCG",
+            "",
+            "--trim adapters",
+        ]) {
+            *field = value.to_string();
+        }
+        let preset = Preset::from_fields(&fields).unwrap();
+        assert_eq!(preset.extra_args, vec!["--trim", "adapters"]);
+        assert_eq!(Preset::from_fields(&preset.to_fields()).unwrap(), preset);
+        fields[5] = "cram".to_string();
+        assert_eq!(
+            Preset::from_fields(&fields).unwrap_err().to_string(),
+            "Emit must be bam, fastq or sam, not cram"
+        );
+
+        let raw = BasecallRun {
+            path: "/data/run1/pod5".to_string(),
+            ..Default::default()
+        };
+        let project = Project {
+            data_location: "/data/run1".to_string(),
+            history: vec![ProjectHistory {
+                action: ProjectAction::Basecall(BasecallLog {
+                    status: BasecallStatus::Success,
+                    ..BasecallLog::new(&raw, String::new())
+                }),
+                created_by: String::new(),
+                created_at: 0,
+            }],
+            ..Default::default()
+        };
+        let mut run = preset.run(&project);
+        assert_eq!(run.basecaller.config, "sup 5mC");
+        assert!(run.output_path.starts_with("/data/run1/basecalls/sup_5mC-"));
+        assert!(run.output_path.ends_with(".fastq"));
+        assert_eq!(
+            run.command(),
+            vec![
+                "dorado",
+                "basecaller",
+                "sup",
+                "/data/run1/pod5",
+                "--modified-bases",
+                "5mCG_5hmCG",
+                "--kit-name",
+                "SQK-NBD114-24",
+                "--min-qscore",
+                "10",
+                "--emit-fastq",
+                "--trim",
+                "adapters"
+            ]
+        );
+
+        // Converted FAST5 is read from its POD5 from then on
+        let project = Project {
+            raw_path: Some("/data/run1/pod5_pass".to_string()),
+            ..project
+        };
+        run = preset.run(&project);
+        assert_eq!(run.path, "/data/run1/pod5_pass");
+    }
+
+    #[test]
+    fn test_files_replace_config_presets() {
+        let dir = std::env::temp_dir().join(format!("fishtank-presets-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let preset = |name: &str, model: &str| Preset {
+            name: name.to_string(),
+            model: model.to_string(),
+            ..Default::default()
+        };
+        let path = preset("hac barcoded", "hac@v5.0.0").save(&dir).unwrap();
+        let presets = Preset::merged(
+            &[preset("duplex", "sup"), preset("hac barcoded", "hac")],
+            &dir,
+        )
+        .unwrap();
+        assert_eq!(
+            presets,
+            vec![
+                preset("duplex", "sup"),
+                Preset {
+                    file: Some(path),
+                    ..preset("hac barcoded", "hac@v5.0.0")
+                }
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
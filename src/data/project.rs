@@ -1,5 +1,25 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::eyre, Result};
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng as serde_yaml;
+use tracing::warn;
+
+use super::{
+    alignment::{AlignLog, MappingStats},
+    barcoding,
+    conversion::ConversionCheck,
+    duplex::DuplexResults,
+    lifecycle::{LifecycleLog, LifecycleRule},
+    manifest::Verification,
+    modifications::ModificationSummary,
+    rna::{self, RnaInfo, RnaResults},
+    sanitize,
+    storage::{SizeSample, StorageUsage},
+};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Project {
@@ -14,6 +34,209 @@ pub struct Project {
     pub history: Vec<ProjectHistory>,
     pub data_location: String,
     pub data_size: u64,
+    #[serde(default)]
+    pub format: RawBasecallsFormat,
+    /// Raw data new basecalls read, e.g. the POD5 from a verified conversion of the FAST5
+    #[serde(default)]
+    pub raw_path: Option<String>,
+    /// Barcode kit and sample sheet for multiplexed runs
+    #[serde(default)]
+    pub barcoding: Option<Barcoding>,
+    #[serde(default)]
+    pub sequencing: SequencingInfo,
+    /// Latest disk usage by kind of data, `data_size` holds its total
+    #[serde(default)]
+    pub storage: StorageUsage,
+    #[serde(default)]
+    pub size_history: Vec<SizeSample>,
+    /// Rules for this project's raw data, replacing the configured defaults when set
+    #[serde(default)]
+    pub lifecycle: Vec<LifecycleRule>,
+    /// Name of the registered reference this project aligns to by default
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// When the checksum manifest was last generated
+    #[serde(default)]
+    pub manifest_at: Option<u64>,
+    /// Latest check of the data against its manifest
+    #[serde(default)]
+    pub verification: Option<Verification>,
+}
+
+impl Project {
+    pub fn to_list_item(&self) -> String {
+        match &self.project_type {
+            ProjectType::DNA(kind) | ProjectType::RNA(RnaInfo { kind, .. }) if !kind.is_empty() => {
+                format!("{} ({kind})", self.name)
+            }
+            _ => self.name.clone(),
+        }
+    }
+
+    /// Path of the YAML record for this project inside `dir`
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.yaml", sanitize(&self.name)))
+    }
+
+    /// Path of the checksum manifest for this project, next to its record in `dir`
+    pub fn manifest_path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.manifest.tsv", sanitize(&self.name)))
+    }
+
+    /// Path of the files that failed the latest verification, next to the manifest
+    pub fn verify_report_path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.verify.tsv", sanitize(&self.name)))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_yaml::from_reader(file)?)
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let file = std::fs::File::create(self.path_in(dir))?;
+        serde_yaml::to_writer(file, self)?;
+        Ok(())
+    }
+
+    /// Load every project record in `dir`, sorted by name
+    ///
+    /// Records that can't be read are logged and left out rather than hiding every other project.
+    pub fn load_all(dir: &Path) -> Result<Vec<Self>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut projects = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "yaml") {
+                match Self::load(&path) {
+                    Ok(project) => projects.push(project),
+                    Err(e) => warn!("Skipping project record {}: {e}", path.display()),
+                }
+            }
+        }
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    /// Raw data new basecalls read, falling back to the latest basecall's input and then to the
+    /// data location, imported projects know it from MinKNOW's basecalls
+    pub fn raw_input(&self) -> String {
+        self.raw_path
+            .clone()
+            .or_else(|| {
+                self.basecall_logs()
+                    .map(|log| log.basecall_run.path.clone())
+                    .filter(|path| !path.is_empty())
+                    .last()
+            })
+            .unwrap_or_else(|| self.data_location.clone())
+    }
+
+    /// Point the data location, raw input and recorded basecall inputs at or below `from` to `to`
+    pub fn relocate(&mut self, from: &str, to: &str) {
+        let moved = |path: &str| {
+            let relative = Path::new(path).strip_prefix(from).ok()?;
+            Some(if relative.as_os_str().is_empty() {
+                to.to_string()
+            } else {
+                Path::new(to).join(relative).display().to_string()
+            })
+        };
+        if let Some(path) = moved(&self.data_location) {
+            self.data_location = path;
+        }
+        if let Some(path) = self.raw_path.as_deref().and_then(moved) {
+            self.raw_path = Some(path);
+        }
+        for entry in self.history.iter_mut() {
+            if let ProjectAction::Basecall(log) = &mut entry.action {
+                if let Some(path) = moved(&log.basecall_run.path) {
+                    log.basecall_run.path = path;
+                }
+            }
+        }
+    }
+
+    pub fn basecall_logs(&self) -> impl Iterator<Item = &BasecallLog> {
+        self.history.iter().filter_map(|entry| match &entry.action {
+            ProjectAction::Basecall(log) => Some(log),
+            _ => None,
+        })
+    }
+
+    /// The most recent basecall writing to `output_path`
+    pub fn basecall_log_mut(&mut self, output_path: &str) -> Option<&mut BasecallLog> {
+        self.history
+            .iter_mut()
+            .rev()
+            .filter_map(|entry| match &mut entry.action {
+                ProjectAction::Basecall(log) => Some(log),
+                _ => None,
+            })
+            .find(|log| log.basecall_run.output_path == output_path)
+    }
+
+    pub fn demux_logs(&self) -> impl Iterator<Item = &DemuxLog> {
+        self.history.iter().filter_map(|entry| match &entry.action {
+            ProjectAction::Demux(log) => Some(log),
+            _ => None,
+        })
+    }
+
+    /// The most recent demultiplexing writing to `output_dir`
+    pub fn demux_log_mut(&mut self, output_dir: &str) -> Option<&mut DemuxLog> {
+        self.history
+            .iter_mut()
+            .rev()
+            .filter_map(|entry| match &mut entry.action {
+                ProjectAction::Demux(log) => Some(log),
+                _ => None,
+            })
+            .find(|log| log.output_dir == output_dir)
+    }
+
+    /// The most recent conversion writing to `output_dir`
+    pub fn convert_log_mut(&mut self, output_dir: &str) -> Option<&mut ConvertLog> {
+        self.history
+            .iter_mut()
+            .rev()
+            .filter_map(|entry| match &mut entry.action {
+                ProjectAction::Convert(log) => Some(log),
+                _ => None,
+            })
+            .find(|log| log.output_dir == output_dir)
+    }
+
+    /// The most recent alignment writing to `output_path`
+    pub fn align_log_mut(&mut self, output_path: &str) -> Option<&mut AlignLog> {
+        self.history
+            .iter_mut()
+            .rev()
+            .filter_map(|entry| match &mut entry.action {
+                ProjectAction::Align(log) => Some(log),
+                _ => None,
+            })
+            .find(|log| log.output_path == output_path)
+    }
+
+    /// The most recent basecall with QC results
+    pub fn latest_results(&self) -> Option<&BasecallResults> {
+        self.basecall_logs()
+            .filter_map(|log| log.results.as_ref())
+            .last()
+    }
+
+    /// Per-barcode results of the latest successful demultiplexing
+    pub fn barcode_results(&self) -> &[BarcodeResults] {
+        self.demux_logs()
+            .filter(|log| log.status == BasecallStatus::Success)
+            .last()
+            .map(|log| log.barcodes.as_slice())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -28,7 +251,7 @@ pub struct Note {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProjectType {
     DNA(String),
-    RNA(String),
+    RNA(#[serde(deserialize_with = "rna::deserialize_info")] RnaInfo),
 }
 
 impl Default for ProjectType {
@@ -44,9 +267,15 @@ pub struct ProjectHistory {
     pub created_at: u64,
 }
 
+// Projects only hold a handful of history entries, so the size difference doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProjectAction {
     Basecall(BasecallLog),
+    Demux(DemuxLog),
+    Lifecycle(LifecycleLog),
+    Convert(ConvertLog),
+    Align(AlignLog),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -56,25 +285,207 @@ pub struct BasecallLog {
     pub version: String,
     pub config: String,
     pub basecall_run: BasecallRun,
+    /// Every command line run for this basecall, the original first followed by any resumes
     pub command: Vec<String>,
     pub results: Option<BasecallResults>,
+    /// Reads already present in the partial output when the run was last resumed
+    #[serde(default)]
+    pub resumed_reads: u64,
+    /// How the output mapped to a reference, from its latest alignment
+    #[serde(default)]
+    pub mapping: Option<MappingStats>,
+    /// Executor the run was started on, e.g. `local` or an ssh host
+    #[serde(default)]
+    pub host: String,
+    /// Size of the raw data when the run started
+    #[serde(default)]
+    pub input_bytes: u64,
+    /// Seconds since the unix epoch the run finished successfully
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    /// GPUs or other hardware of the executor, from `execution.hardware`
+    #[serde(default)]
+    pub hardware: String,
+    /// Rate dorado reported when it finished
+    #[serde(default)]
+    pub samples_per_second: Option<f64>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl BasecallLog {
+    pub fn new(run: &BasecallRun, command: String) -> Self {
+        Self {
+            status: BasecallStatus::InProgress,
+            basecaller: run.basecaller.name.clone(),
+            version: run.basecaller.version.clone(),
+            config: run.basecaller.config.clone(),
+            basecall_run: run.clone(),
+            command: vec![command],
+            results: None,
+            resumed_reads: 0,
+            mapping: None,
+            host: String::new(),
+            input_bytes: 0,
+            finished_at: None,
+            hardware: String::new(),
+            samples_per_second: None,
+        }
+    }
+
+    /// An unfinished run whose partial output is still on disk can be resumed
+    pub fn is_resumable(&self) -> bool {
+        self.status != BasecallStatus::Success
+            && self.basecall_run.run_type == RunType::Simplex
+            && std::fs::metadata(&self.basecall_run.output_path).is_ok_and(|m| m.len() > 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BasecallStatus {
     Success,
     Failed,
     InProgress,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Defines a basecall run within a project
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BasecallRun {
     pub name: String,
+    /// Raw data (pod5/fast5) the run reads from
+    #[serde(default)]
+    pub path: String,
     pub read_count: u64,
+    /// BAM file the basecaller output is written to
     pub output_path: String,
+    #[serde(default)]
+    pub basecaller: Basecaller,
+    /// Barcode kit to classify reads with while basecalling
+    #[serde(default)]
+    pub kit_name: Option<String>,
+    /// Modification models to call alongside the bases, e.g. `5mCG_5hmCG` or `6mA`
+    #[serde(default)]
+    pub modified_bases: Vec<String>,
+    #[serde(default)]
+    pub run_type: RunType,
+    /// Estimate poly-A tail lengths of RNA reads
+    #[serde(default)]
+    pub estimate_poly_a: bool,
+    /// Drop reads with a lower mean qscore
+    #[serde(default)]
+    pub min_qscore: Option<u32>,
+    #[serde(default)]
+    pub emit: EmitFormat,
+    /// Further basecaller options, added to the end of the command line as they are
+    #[serde(default)]
+    pub extra_args: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl BasecallRun {
+    /// The command line that runs this basecall, output is written to stdout
+    pub fn command(&self) -> Vec<String> {
+        let mut command = vec![
+            self.basecaller.binary(),
+            self.run_type.subcommand().to_string(),
+            self.basecaller.model.clone(),
+            self.path.clone(),
+        ];
+        if !self.modified_bases.is_empty() {
+            command.push("--modified-bases".to_string());
+            command.extend(self.modified_bases.iter().cloned());
+        }
+        if self.estimate_poly_a {
+            command.push("--estimate-poly-a".to_string());
+        }
+        if let Some(kit) = &self.kit_name {
+            command.extend(["--kit-name".to_string(), kit.clone()]);
+        }
+        if let Some(qscore) = self.min_qscore {
+            command.extend(["--min-qscore".to_string(), qscore.to_string()]);
+        }
+        if let Some(flag) = self.emit.flag() {
+            command.push(flag.to_string());
+        }
+        command.extend(self.extra_args.iter().cloned());
+        command
+    }
+}
+
+/// Whether a run calls each strand on its own, or also pairs template and complement strands
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RunType {
+    #[default]
+    Simplex,
+    Duplex,
+}
+
+impl RunType {
+    /// The dorado subcommand for this kind of run
+    pub fn subcommand(self) -> &'static str {
+        match self {
+            RunType::Simplex => "basecaller",
+            RunType::Duplex => "duplex",
+        }
+    }
+}
+
+/// Format the basecaller writes its reads in
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmitFormat {
+    #[default]
+    Bam,
+    Fastq,
+    Sam,
+}
+
+impl EmitFormat {
+    /// The option selecting this format, dorado writes BAM without one
+    pub fn flag(self) -> Option<&'static str> {
+        match self {
+            EmitFormat::Bam => None,
+            EmitFormat::Fastq => Some("--emit-fastq"),
+            EmitFormat::Sam => Some("--emit-sam"),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            EmitFormat::Bam => "bam",
+            EmitFormat::Fastq => "fastq",
+            EmitFormat::Sam => "sam",
+        }
+    }
+}
+
+/// Defines the basecaller used to process raw basecalls
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Basecaller {
+    pub name: String,
+    pub version: String,
+    pub path: String,
+    pub config: String,
+    pub model: String,
+}
+
+impl Basecaller {
+    /// Path to the basecaller executable, falling back to its name on `PATH`
+    pub fn binary(&self) -> String {
+        if self.path.is_empty() {
+            self.name.to_lowercase()
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RawBasecallsFormat {
+    Fast5,
+    Slow5,
+    #[default]
+    Pod5,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BasecallResults {
     pub read_count: u64,
     pub mean_qscore: f64,
@@ -83,4 +494,298 @@ pub struct BasecallResults {
     pub max_qscore: f64,
     pub n50: u64,
     pub n_bases: u64,
+    /// Yield per interval of sequencing time, only known from a sequencing summary
+    #[serde(default)]
+    pub yield_over_time: Vec<YieldBin>,
+    /// Reads per flow cell channel, only known from a sequencing summary
+    #[serde(default)]
+    pub channels: Vec<ChannelActivity>,
+    /// Modified base calls, for runs basecalled with modification models
+    #[serde(default)]
+    pub modifications: Vec<ModificationSummary>,
+    /// Simplex and duplex reads counted separately, for duplex runs
+    #[serde(default)]
+    pub duplex: Option<DuplexResults>,
+    /// Poly-A tail lengths and strand orientation, for RNA projects
+    #[serde(default)]
+    pub rna: Option<RnaResults>,
+}
+
+/// Reads that started sequencing within one interval of the run
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YieldBin {
+    /// Seconds since the run started
+    pub start: u64,
+    pub reads: u64,
+    pub bases: u64,
+    pub n50: u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelActivity {
+    pub channel: u32,
+    pub reads: u64,
+    pub bases: u64,
+}
+
+/// What MinKNOW knows about the sequencing run behind a project
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SequencingInfo {
+    pub flow_cell_id: String,
+    /// Device position, e.g. `1A` or `X3`
+    pub position_id: String,
+    pub experiment_id: String,
+    /// Sample of a run that isn't barcoded, barcoded samples are in `Barcoding::samples`
+    pub sample_id: String,
+    /// Sequencing kit plus any expansion kits, space separated as in MinKNOW
+    pub kit: String,
+    /// MinKNOW protocol, e.g. `sequencing/sequencing_MIN114_DNA_e8_2_400K:FLO-MIN114:SQK-LSK114`
+    pub protocol: String,
+    /// Seconds since the unix epoch the run started, 0 if unknown
+    pub started_at: u64,
+}
+
+/// Barcode kit used for a multiplexed run and the samples on each barcode
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Barcoding {
+    /// Kit name as dorado expects it, e.g. `SQK-NBD114-24`
+    pub kit: String,
+    pub samples: Vec<Sample>,
+}
+
+impl Barcoding {
+    pub fn sample(&self, barcode: &str) -> Option<&Sample> {
+        self.samples.iter().find(|sample| sample.barcode == barcode)
+    }
+
+    /// Check the kit is known and every sample is on a distinct barcode from it
+    pub fn validate(&self) -> Result<()> {
+        let count = barcoding::barcode_count(&self.kit)
+            .ok_or_else(|| eyre!("Unknown barcode kit {}", self.kit))?;
+        let mut seen = HashSet::new();
+        for sample in &self.samples {
+            match barcoding::barcode_number(&sample.barcode) {
+                Some(n) if (1..=count).contains(&n) => {}
+                _ => {
+                    return Err(eyre!(
+                        "{} is not a barcode in {} (barcode01 to barcode{count:02})",
+                        sample.barcode,
+                        self.kit
+                    ))
+                }
+            }
+            if !seen.insert(&sample.barcode) {
+                return Err(eyre!(
+                    "{} is assigned to more than one sample",
+                    sample.barcode
+                ));
+            }
+            if sample.name.is_empty() {
+                return Err(eyre!("{} has no sample name", sample.barcode));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One row of a sample sheet
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Barcode name as dorado reports it, e.g. `barcode01`
+    pub barcode: String,
+    pub name: String,
+    #[serde(default)]
+    pub alias: String,
+}
+
+/// A `dorado demux` run splitting a basecalled BAM by barcode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DemuxLog {
+    pub status: BasecallStatus,
+    /// Basecalled BAM that was split
+    pub input: String,
+    /// Directory the per-barcode BAM files are written to
+    pub output_dir: String,
+    pub kit: String,
+    pub command: Vec<String>,
+    pub barcodes: Vec<BarcodeResults>,
+}
+
+/// A `pod5 convert fast5` run turning the project's FAST5 into POD5
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvertLog {
+    /// `Success` only once the read counts of the input and output match
+    pub status: BasecallStatus,
+    pub input: String,
+    pub output_dir: String,
+    pub command: Vec<String>,
+    /// Read counts, filled in once the conversion finishes
+    pub check: Option<ConversionCheck>,
+}
+
+/// Read counts and quality for the reads assigned to one barcode
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BarcodeResults {
+    /// `barcode01` etc, or `unclassified`
+    pub barcode: String,
+    pub read_count: u64,
+    pub n_bases: u64,
+    pub mean_qscore: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn barcoding(barcodes: &[&str]) -> Barcoding {
+        Barcoding {
+            kit: "SQK-NBD114-24".to_string(),
+            samples: barcodes
+                .iter()
+                .enumerate()
+                .map(|(i, barcode)| Sample {
+                    barcode: barcode.to_string(),
+                    name: format!("sample{i}"),
+                    alias: String::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_barcoding_validate() {
+        assert!(barcoding(&["barcode01", "barcode24"]).validate().is_ok());
+        assert_eq!(
+            barcoding(&["barcode25"])
+                .validate()
+                .unwrap_err()
+                .to_string(),
+            "barcode25 is not a barcode in SQK-NBD114-24 (barcode01 to barcode24)"
+        );
+        assert_eq!(
+            barcoding(&["barcode02", "barcode02"])
+                .validate()
+                .unwrap_err()
+                .to_string(),
+            "barcode02 is assigned to more than one sample"
+        );
+        let mut unknown = barcoding(&[]);
+        unknown.kit = "SQK-LSK114".to_string();
+        assert!(unknown.validate().is_err());
+    }
+
+    #[test]
+    fn test_basecall_command_with_kit() {
+        let run = BasecallRun {
+            path: "pod5_pass".to_string(),
+            basecaller: Basecaller {
+                name: "Dorado".to_string(),
+                model: "hac".to_string(),
+                ..Default::default()
+            },
+            kit_name: Some("SQK-RBK114-96".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            run.command(),
+            vec![
+                "dorado",
+                "basecaller",
+                "hac",
+                "pod5_pass",
+                "--kit-name",
+                "SQK-RBK114-96"
+            ]
+        );
+
+        let run = BasecallRun {
+            kit_name: None,
+            modified_bases: vec!["5mCG_5hmCG".to_string(), "6mA".to_string()],
+            ..run
+        };
+        assert_eq!(
+            run.command(),
+            vec![
+                "dorado",
+                "basecaller",
+                "hac",
+                "pod5_pass",
+                "--modified-bases",
+                "5mCG_5hmCG",
+                "6mA"
+            ]
+        );
+
+        let run = BasecallRun {
+            modified_bases: Vec::new(),
+            run_type: RunType::Duplex,
+            ..run
+        };
+        assert_eq!(run.command(), vec!["dorado", "duplex", "hac", "pod5_pass"]);
+    }
+
+    #[test]
+    fn test_rna_type_from_older_records() {
+        let project_type: ProjectType = serde_yaml::from_str("!RNA SQK-RNA002 polyA").unwrap();
+        let ProjectType::RNA(info) = project_type else {
+            panic!("not RNA");
+        };
+        assert_eq!(info.kind, "SQK-RNA002 polyA");
+        assert_eq!(info.chemistry, rna::RnaChemistry::Rna002);
+
+        let project_type = ProjectType::RNA(RnaInfo::from_kit("SQK-RNA004").unwrap());
+        let yaml = serde_yaml::to_string(&project_type).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<ProjectType>(&yaml).unwrap(),
+            project_type
+        );
+    }
+
+    #[test]
+    fn test_load_baseline_records() {
+        let dir = std::env::temp_dir().join(format!("fishtank-projects-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // Saved before basecall runs recorded their input and basecaller
+        let baseline = r#"
+name: run 1
+description: ''
+notes: []
+created_by: jguhlin
+created_at: 1700000000
+last_updated: 1700000000
+project_owner: jguhlin
+project_type: !DNA ligation
+history:
+- action: !Basecall
+    status: Success
+    basecaller: dorado
+    version: 0.5.0
+    config: sup
+    basecall_run:
+      name: run 1 sup
+      read_count: 4000
+      output_path: /data/run1/calls.bam
+    command:
+    - dorado basecaller sup /data/run1/pod5
+    results: null
+  created_by: jguhlin
+  created_at: 1700000100
+data_location: /data/run1
+data_size: 1024
+"#;
+        std::fs::write(dir.join("run_1.yaml"), baseline).unwrap();
+        std::fs::write(dir.join("broken.yaml"), "name: [unclosed").unwrap();
+
+        let projects = Project::load_all(&dir).unwrap();
+        assert_eq!(projects.len(), 1);
+        let log = projects[0].basecall_logs().next().unwrap();
+        assert_eq!(log.basecall_run.output_path, "/data/run1/calls.bam");
+        assert_eq!(log.basecall_run.path, "");
+        assert_eq!(log.basecall_run.basecaller, Basecaller::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
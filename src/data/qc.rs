@@ -0,0 +1,437 @@
+//! Read QC from sequencing summaries, falling back to the reads themselves
+//!
+//! `sequencing_summary.txt` has a line per read with its length, mean qscore, channel and
+//! start time, so a run's QC takes seconds to compute instead of re-reading every base.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use flate2::read::MultiGzDecoder;
+
+use super::{
+    bam,
+    project::{BasecallResults, ChannelActivity, YieldBin},
+};
+
+/// Width of each yield-over-time bin, in seconds of sequencing
+pub const YIELD_BIN_SECONDS: u64 = 600;
+
+/// Collects per-read length and quality into `BasecallResults`
+#[derive(Debug, Default)]
+pub struct QcAccumulator {
+    lengths: Vec<u32>,
+    qscores: Vec<f32>,
+    /// Read lengths by yield bin
+    bins: BTreeMap<u64, Vec<u32>>,
+    /// Reads and bases by channel
+    channels: BTreeMap<u32, (u64, u64)>,
+}
+
+impl QcAccumulator {
+    pub fn add(&mut self, length: u32, qscore: f32) {
+        self.lengths.push(length);
+        self.qscores.push(qscore);
+    }
+
+    /// Add a read whose channel and start time, in seconds since the run started, are known
+    pub fn add_timed(&mut self, length: u32, qscore: f32, channel: u32, start_time: f64) {
+        self.add(length, qscore);
+        let bin = start_time.max(0.0) as u64 / YIELD_BIN_SECONDS;
+        self.bins.entry(bin).or_default().push(length);
+        let (reads, bases) = self.channels.entry(channel).or_default();
+        *reads += 1;
+        *bases += length as u64;
+    }
+
+    pub fn finish(mut self) -> BasecallResults {
+        let n_bases = self.lengths.iter().map(|&l| l as u64).sum();
+        self.qscores.sort_by(f32::total_cmp);
+        let qscores = &self.qscores;
+        let mean_qscore = if qscores.is_empty() {
+            0.0
+        } else {
+            qscores.iter().map(|&q| q as f64).sum::<f64>() / qscores.len() as f64
+        };
+        let median_qscore = match qscores.len() {
+            0 => 0.0,
+            n if n % 2 == 1 => qscores[n / 2] as f64,
+            n => (qscores[n / 2 - 1] as f64 + qscores[n / 2] as f64) / 2.0,
+        };
+
+        BasecallResults {
+            read_count: self.lengths.len() as u64,
+            mean_qscore,
+            median_qscore,
+            min_qscore: qscores.first().copied().unwrap_or_default() as f64,
+            max_qscore: qscores.last().copied().unwrap_or_default() as f64,
+            n50: n50(&mut self.lengths),
+            n_bases,
+            yield_over_time: self
+                .bins
+                .into_iter()
+                .map(|(bin, mut lengths)| YieldBin {
+                    start: bin * YIELD_BIN_SECONDS,
+                    reads: lengths.len() as u64,
+                    bases: lengths.iter().map(|&l| l as u64).sum(),
+                    n50: n50(&mut lengths),
+                })
+                .collect(),
+            channels: self
+                .channels
+                .into_iter()
+                .map(|(channel, (reads, bases))| ChannelActivity {
+                    channel,
+                    reads,
+                    bases,
+                })
+                .collect(),
+            modifications: Vec::new(),
+            duplex: None,
+            rna: None,
+        }
+    }
+}
+
+/// Length of the read at which half of all bases are in reads at least that long
+fn n50(lengths: &mut [u32]) -> u64 {
+    lengths.sort_unstable_by(|a, b| b.cmp(a));
+    let total = lengths.iter().map(|&l| l as u64).sum::<u64>();
+    let mut running = 0;
+    for &length in lengths.iter() {
+        running += length as u64;
+        if running * 2 >= total {
+            return length as u64;
+        }
+    }
+    0
+}
+
+/// Open a file for reading, decompressing it if it ends in `.gz`
+fn open(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    Ok(if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    })
+}
+
+/// QC from a MinKNOW or dorado `sequencing_summary.txt`, plain or gzipped
+///
+/// Reads MinKNOW marked as failing its filters are skipped, matching what ends up in the
+/// `*_pass` outputs.
+pub fn from_sequencing_summary(path: &Path) -> Result<BasecallResults> {
+    let mut lines = open(path)?.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| eyre!("{} is empty", path.display()))??;
+    let columns = header.split('\t').collect::<Vec<_>>();
+    let column = |name: &str| columns.iter().position(|column| *column == name);
+    let (Some(length), Some(qscore)) = (
+        column("sequence_length_template"),
+        column("mean_qscore_template"),
+    ) else {
+        return Err(eyre!(
+            "{} has no sequence_length_template or mean_qscore_template column",
+            path.display()
+        ));
+    };
+    let channel = column("channel");
+    let start_time = column("start_time");
+    let passes = column("passes_filtering");
+
+    let mut qc = QcAccumulator::default();
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if passes.is_some_and(|passes| fields.get(passes) == Some(&"FALSE")) {
+            continue;
+        }
+        let field = |index: usize| {
+            fields
+                .get(index)
+                .ok_or_else(|| eyre!("{} line {} is truncated", path.display(), i + 2))
+        };
+        let read_length = field(length)?.parse::<u32>()?;
+        let read_qscore = field(qscore)?.parse::<f32>()?;
+        match (channel, start_time) {
+            (Some(channel), Some(start_time)) => qc.add_timed(
+                read_length,
+                read_qscore,
+                field(channel)?.parse()?,
+                field(start_time)?.parse()?,
+            ),
+            _ => qc.add(read_length, read_qscore),
+        }
+    }
+    Ok(qc.finish())
+}
+
+/// Mean quality of a read from its phred+33 quality string, averaged as error probabilities
+fn mean_qscore(qual: &[u8]) -> f32 {
+    if qual.is_empty() {
+        return 0.0;
+    }
+    let error = qual
+        .iter()
+        .map(|&q| 10f64.powf(-(q.saturating_sub(33) as f64) / 10.0))
+        .sum::<f64>()
+        / qual.len() as f64;
+    (-10.0 * error.log10()) as f32
+}
+
+fn add_fastq(path: &Path, qc: &mut QcAccumulator) -> Result<()> {
+    let mut reader = open(path)?;
+    let mut lines = [String::new(), String::new(), String::new(), String::new()];
+    loop {
+        for line in lines.iter_mut() {
+            line.clear();
+        }
+        if reader.read_line(&mut lines[0])? == 0 {
+            return Ok(());
+        }
+        for line in lines.iter_mut().skip(1) {
+            reader.read_line(line)?;
+        }
+        let sequence = lines[1].trim_end();
+        let qual = lines[3].trim_end();
+        if !lines[0].starts_with('@') || sequence.len() != qual.len() {
+            return Err(eyre!("{} is not valid FASTQ", path.display()));
+        }
+        qc.add(sequence.len() as u32, mean_qscore(qual.as_bytes()));
+    }
+}
+
+fn add_bam(path: &Path, qc: &mut QcAccumulator) -> Result<()> {
+    let mut reader = bam::Reader::from_path(path)?;
+    let mut record = bam::Record::default();
+    while reader.read(&mut record)? {
+        if record.is_primary() {
+            qc.add(record.seq_len() as u32, record.mean_qscore() as f32);
+        }
+    }
+    Ok(())
+}
+
+fn is_fastq(path: &Path) -> bool {
+    let name = file_name(path);
+    [".fastq", ".fq", ".fastq.gz", ".fq.gz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+fn read_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            read_files(&entry?.path(), files)?;
+        }
+    } else if is_fastq(path) || path.extension().is_some_and(|ext| ext == "bam") {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// QC by reading every FASTQ or BAM record in a file or directory
+pub fn from_reads(path: &Path) -> Result<BasecallResults> {
+    let mut files = Vec::new();
+    read_files(path, &mut files)?;
+    if files.is_empty() {
+        return Err(eyre!("No FASTQ or BAM files in {}", path.display()));
+    }
+    let mut qc = QcAccumulator::default();
+    for file in files {
+        if is_fastq(&file) {
+            add_fastq(&file, &mut qc)?;
+        } else {
+            add_bam(&file, &mut qc)?;
+        }
+    }
+    Ok(qc.finish())
+}
+
+/// The `sequencing_summary*.txt` files in `dir`, gzipped or not
+fn sequencing_summaries(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut summaries = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| summary_stem(path).is_some_and(|stem| stem.contains("sequencing_summary")))
+        .collect::<Vec<_>>();
+    summaries.sort();
+    summaries
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// The name of a `.txt` or `.txt.gz` file without its extension
+fn summary_stem(path: &Path) -> Option<String> {
+    let name = file_name(path);
+    let stem = name
+        .strip_suffix(".txt.gz")
+        .or_else(|| name.strip_suffix(".txt"))?;
+    Some(stem.to_string())
+}
+
+/// The sequencing summary written for the basecall output at `output_path`
+///
+/// A directory of reads, such as MinKNOW's `fastq_pass`, uses the summary in the run directory
+/// above it. Runs written to files often share a directory, so a file only uses a summary named
+/// after it, `sequencing_summary_<stem>.txt` or `<stem>_sequencing_summary.txt`.
+pub fn find_sequencing_summary(output_path: &Path) -> Option<PathBuf> {
+    if output_path.is_dir() {
+        return sequencing_summaries(output_path)
+            .into_iter()
+            .chain(sequencing_summaries(output_path.parent()?))
+            .next();
+    }
+    let name = file_name(output_path);
+    let stem = name.split('.').next().unwrap_or_default();
+    if stem.is_empty() {
+        return None;
+    }
+    let names = [
+        format!("sequencing_summary_{stem}"),
+        format!("{stem}_sequencing_summary"),
+    ];
+    sequencing_summaries(output_path.parent()?)
+        .into_iter()
+        .find(|summary| summary_stem(summary).is_some_and(|summary| names.contains(&summary)))
+}
+
+/// QC for basecall output, from its sequencing summary when there is one, otherwise from its
+/// reads
+pub fn basecall_results(output_path: &Path) -> Result<BasecallResults> {
+    match find_sequencing_summary(output_path) {
+        Some(summary) => from_sequencing_summary(&summary),
+        None => from_reads(output_path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const SUMMARY: &str = "\
+filename\tread_id\trun_id\tchannel\tmux\tstart_time\tduration\tpasses_filtering\tsequence_length_template\tmean_qscore_template
+a.pod5\tr1\trun\t1\t1\t10.5\t1.0\tTRUE\t1000\t12.0
+a.pod5\tr2\trun\t1\t2\t620.0\t1.0\tTRUE\t4000\t15.0
+a.pod5\tr3\trun\t7\t1\t650.0\t1.0\tTRUE\t3000\t9.0
+a.pod5\tr4\trun\t7\t1\t700.0\t1.0\tFALSE\t50000\t3.0
+a.pod5\tr5\trun\t7\t1\t1300.0\t1.0\tTRUE\t2000\t18.0
+";
+
+    #[test]
+    fn test_sequencing_summary() {
+        let dir = std::env::temp_dir().join(format!("fishtank-qc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("calls.bam"), "").unwrap();
+        let path = dir.join("sequencing_summary_calls.txt.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&path).unwrap(), Default::default());
+        encoder.write_all(SUMMARY.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(
+            basecall_results(&dir.join("calls.bam")).unwrap(),
+            BasecallResults {
+                read_count: 4,
+                mean_qscore: 13.5,
+                median_qscore: 13.5,
+                min_qscore: 9.0,
+                max_qscore: 18.0,
+                n50: 3000,
+                n_bases: 10000,
+                yield_over_time: vec![
+                    YieldBin {
+                        start: 0,
+                        reads: 1,
+                        bases: 1000,
+                        n50: 1000
+                    },
+                    YieldBin {
+                        start: 600,
+                        reads: 2,
+                        bases: 7000,
+                        n50: 4000
+                    },
+                    YieldBin {
+                        start: 1200,
+                        reads: 1,
+                        bases: 2000,
+                        n50: 2000
+                    },
+                ],
+                channels: vec![
+                    ChannelActivity {
+                        channel: 1,
+                        reads: 2,
+                        bases: 5000
+                    },
+                    ChannelActivity {
+                        channel: 7,
+                        reads: 2,
+                        bases: 5000
+                    },
+                ],
+                modifications: Vec::new(),
+                duplex: None,
+                rna: None,
+            }
+        );
+
+        // Other runs in the same directory only get a summary named after them
+        std::fs::write(dir.join("run1.bam"), "").unwrap();
+        std::fs::write(dir.join("sequencing_summary_run10.txt"), SUMMARY).unwrap();
+        std::fs::write(dir.join("sequencing_summary.txt"), SUMMARY).unwrap();
+        assert_eq!(find_sequencing_summary(&dir.join("run1.bam")), None);
+        let named = dir.join("run1_sequencing_summary.txt");
+        std::fs::write(&named, SUMMARY).unwrap();
+        assert_eq!(find_sequencing_summary(&dir.join("run1.bam")), Some(named));
+        assert_eq!(find_sequencing_summary(&dir.join("calls.bam")), Some(path));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fastq_fallback() {
+        let dir = std::env::temp_dir().join(format!("fishtank-qc-fastq-{}", std::process::id()));
+        let fastq = dir.join("fastq_pass");
+        std::fs::create_dir_all(&fastq).unwrap();
+        // '+' is Q10 and '5' is Q20
+        std::fs::write(
+            fastq.join("reads_0.fastq"),
+            "@r1 ch=1\nACGT\n+\n++++\n@r2 ch=2\nACGTACGT\n+\n55555555\n",
+        )
+        .unwrap();
+
+        let results = basecall_results(&fastq).unwrap();
+        assert_eq!(results.read_count, 2);
+        assert_eq!(results.n_bases, 12);
+        assert_eq!(results.n50, 8);
+        assert!((results.mean_qscore - 15.0).abs() < 1e-6);
+        assert!(results.yield_over_time.is_empty());
+
+        // MinKNOW's summary in the run directory is used over the reads, whatever its mtime
+        let summary = dir.join("sequencing_summary_PAQ00001_abcdef12.txt");
+        std::fs::write(&summary, SUMMARY).unwrap();
+        assert_eq!(find_sequencing_summary(&fastq), Some(summary));
+        assert_eq!(basecall_results(&fastq).unwrap().read_count, 4);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
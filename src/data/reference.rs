@@ -0,0 +1,206 @@
+//! Reference genomes, registered once in the data directory and shared by every project
+//!
+//! A reference's checksum is taken when it's registered, so alignments can record exactly which
+//! sequence they were made against without re-hashing a multi-gigabyte FASTA each time.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng as serde_yaml;
+
+use super::{manifest, now};
+
+/// Extensions of the indexes a reference needs, `.fai` for samtools and `.mmi` for minimap2
+pub const INDEXES: [&str; 2] = ["fai", "mmi"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reference {
+    pub name: String,
+    #[serde(default)]
+    pub species: String,
+    /// Assembly version, e.g. `GRCh38.p14`
+    #[serde(default)]
+    pub assembly: String,
+    /// FASTA file, optionally gzipped
+    pub path: String,
+    /// Index files found next to the FASTA
+    #[serde(default)]
+    pub indexes: Vec<String>,
+    pub sha256: String,
+    pub registered_at: u64,
+}
+
+/// The FASTA's file name without its extensions
+fn fasta_stem(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = file_name.strip_suffix(".gz").unwrap_or(&file_name);
+    [".fasta", ".fa", ".fna"]
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .unwrap_or(name)
+        .to_string()
+}
+
+impl Reference {
+    /// Hash the FASTA at `path`, naming the reference after the file
+    pub fn from_path(path: &Path) -> Result<Self> {
+        Self {
+            path: path.display().to_string(),
+            ..Default::default()
+        }
+        .hashed()
+    }
+
+    /// Fill in the checksum and indexes of a reference entered by hand, naming it after its
+    /// FASTA if it has no name
+    pub fn hashed(mut self) -> Result<Self> {
+        let path = PathBuf::from(&self.path);
+        if !path.is_file() {
+            return Err(eyre!("No reference FASTA at {}", path.display()));
+        }
+        if self.name.is_empty() {
+            self.name = fasta_stem(&path);
+        }
+        self.sha256 = manifest::sha256(&path)?;
+        self.registered_at = now();
+        self.find_indexes();
+        Ok(self)
+    }
+
+    /// Look for index files next to the FASTA, `ref.fa.fai` and `ref.mmi` or `ref.fa.mmi`
+    pub fn find_indexes(&mut self) {
+        let path = Path::new(&self.path);
+        let mut candidates = INDEXES
+            .iter()
+            .map(|ext| PathBuf::from(format!("{}.{ext}", self.path)))
+            .collect::<Vec<_>>();
+        candidates.push(path.with_file_name(format!("{}.mmi", fasta_stem(path))));
+        self.indexes = candidates
+            .into_iter()
+            .filter(|index| index.is_file())
+            .map(|index| index.display().to_string())
+            .collect();
+    }
+
+    /// The recorded index with the given extension, if it's still on disk
+    pub fn index(&self, ext: &str) -> Option<&str> {
+        self.indexes
+            .iter()
+            .find(|index| index.ends_with(&format!(".{ext}")) && Path::new(index).is_file())
+            .map(String::as_str)
+    }
+
+    /// Problems that would stop the reference being used, empty when it's ready
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !Path::new(&self.path).is_file() {
+            problems.push("FASTA missing".to_string());
+        }
+        for ext in INDEXES {
+            if self.index(ext).is_none() {
+                problems.push(format!("no .{ext}"));
+            }
+        }
+        problems
+    }
+}
+
+/// Every registered reference, kept in `references.yaml` in the data directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceRegistry {
+    pub references: Vec<Reference>,
+}
+
+impl ReferenceRegistry {
+    pub fn path_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("references.yaml")
+    }
+
+    /// Load the registry, which is empty until the first reference is registered
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = std::fs::File::open(path)?;
+        Ok(serde_yaml::from_reader(file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(path)?;
+        serde_yaml::to_writer(file, self)?;
+        Ok(())
+    }
+
+    /// A reference by name, or by the path of its FASTA
+    pub fn find(&self, name_or_path: &str) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|reference| reference.name == name_or_path)
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|reference| Path::new(&reference.path) == Path::new(name_or_path))
+            })
+    }
+
+    /// Add a reference, replacing any registered under the same name
+    pub fn register(&mut self, reference: Reference) {
+        self.remove(&reference.name);
+        self.references.push(reference);
+        self.references.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.references.retain(|existing| existing.name != name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_register_and_find() {
+        let dir = std::env::temp_dir().join(format!("fishtank-reference-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hg38.fa.gz"), "abc").unwrap();
+        std::fs::write(dir.join("hg38.fa.gz.fai"), "").unwrap();
+
+        let mut reference = Reference::from_path(&dir.join("hg38.fa.gz")).unwrap();
+        assert_eq!(reference.name, "hg38");
+        assert_eq!(reference.problems(), vec!["no .mmi"]);
+        std::fs::write(dir.join("hg38.mmi"), "").unwrap();
+        reference.find_indexes();
+        assert!(reference.problems().is_empty());
+        assert_eq!(
+            reference.index("mmi"),
+            Some(dir.join("hg38.mmi").display().to_string().as_str())
+        );
+        let mut registry = ReferenceRegistry::default();
+        registry.register(reference.clone());
+        registry.save(&ReferenceRegistry::path_in(&dir)).unwrap();
+
+        let registry = ReferenceRegistry::load(&ReferenceRegistry::path_in(&dir)).unwrap();
+        assert_eq!(registry.find("hg38"), Some(&reference));
+        assert_eq!(
+            registry.find(&dir.join("hg38.fa.gz").display().to_string()),
+            Some(&reference)
+        );
+        assert_eq!(registry.find("chm13"), None);
+
+        let mut registry = registry;
+        registry.remove("hg38");
+        assert!(registry.references.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
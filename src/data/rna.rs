@@ -0,0 +1,287 @@
+//! Direct RNA and cDNA projects, their library chemistry and RNA-specific QC
+//!
+//! The chemistry decides which models a direct RNA basecall needs, so runs that only ask for a
+//! speed (`fast`, `hac` or `sup`) are given the right model automatically. QC adds the poly-A
+//! tail lengths dorado writes as `pt` tags with `--estimate-poly-a`, and the strand each read
+//! came from as given by its `ts` tag, which only a spliced alignment adds.
+
+use std::{io, path::Path};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::bam;
+
+/// Width of each poly-A tail length bin, in bases
+pub const POLY_A_BIN_WIDTH: u32 = 10;
+/// Tails at least this long are counted in the last bin
+pub const POLY_A_MAX: u32 = 250;
+
+/// Model speeds that are resolved to a chemistry's model
+const SPEEDS: [&str; 3] = ["fast", "hac", "sup"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RnaLibrary {
+    /// Native RNA strands read through the pore, basecalled with RNA models
+    #[default]
+    DirectRna,
+    /// RNA reverse transcribed to cDNA, basecalled with DNA models
+    Cdna,
+}
+
+/// Direct RNA sequencing chemistry, each with its own models
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RnaChemistry {
+    Rna002,
+    #[default]
+    Rna004,
+}
+
+impl RnaChemistry {
+    pub fn name(self) -> &'static str {
+        match self {
+            RnaChemistry::Rna002 => "RNA002",
+            RnaChemistry::Rna004 => "RNA004",
+        }
+    }
+
+    /// dorado model for a `fast`, `hac` or `sup` basecall
+    pub fn model(self, speed: &str) -> String {
+        match self {
+            // There's no sup model for RNA002, hac is the most accurate
+            RnaChemistry::Rna002 if speed == "sup" => "rna002_70bps_hac@v3".to_string(),
+            RnaChemistry::Rna002 => format!("rna002_70bps_{speed}@v3"),
+            RnaChemistry::Rna004 => format!("rna004_130bps_{speed}@v5.1.0"),
+        }
+    }
+}
+
+/// What was sequenced in an RNA project
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RnaInfo {
+    /// Free text description, all that RNA projects recorded before their attributes were
+    pub kind: String,
+    pub library: RnaLibrary,
+    /// Only meaningful for direct RNA, cDNA is sequenced with DNA chemistry
+    pub chemistry: RnaChemistry,
+    /// Sequencing kit, e.g. `SQK-RNA004` or `SQK-PCB114.24`
+    pub kit: String,
+    /// Basecall with `--estimate-poly-a` to measure tail lengths
+    pub poly_a: bool,
+}
+
+impl RnaInfo {
+    /// Attributes implied by a sequencing kit, `None` when it's neither a direct RNA nor a cDNA kit
+    pub fn from_kit(kit: &str) -> Option<Self> {
+        let upper = kit.to_uppercase();
+        let (library, chemistry) = if upper.contains("RNA002") {
+            (RnaLibrary::DirectRna, RnaChemistry::Rna002)
+        } else if upper.contains("RNA004") {
+            (RnaLibrary::DirectRna, RnaChemistry::Rna004)
+        } else if ["SQK-PCS", "SQK-PCB", "SQK-DCS"]
+            .iter()
+            .any(|prefix| upper.contains(prefix))
+        {
+            (RnaLibrary::Cdna, RnaChemistry::default())
+        } else {
+            return None;
+        };
+        Some(Self {
+            kind: String::new(),
+            library,
+            chemistry,
+            kit: kit.to_string(),
+            poly_a: true,
+        })
+    }
+
+    /// The model a run asking for `model` should use, `None` to use it as it is
+    pub fn model(&self, model: &str) -> Option<String> {
+        (self.library == RnaLibrary::DirectRna && SPEEDS.contains(&model))
+            .then(|| self.chemistry.model(model))
+    }
+
+    /// One line summary, e.g. `direct RNA004 SQK-RNA004, poly-A`
+    pub fn describe(&self) -> String {
+        let mut parts = vec![match self.library {
+            RnaLibrary::DirectRna => format!("direct {}", self.chemistry.name()),
+            RnaLibrary::Cdna => "cDNA".to_string(),
+        }];
+        parts.extend(
+            [&self.kit, &self.kind]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .cloned(),
+        );
+        let mut description = parts.join(" ");
+        if self.poly_a {
+            description.push_str(", poly-A");
+        }
+        description
+    }
+}
+
+/// Read `RnaInfo`, or the plain description older project records have in its place
+pub fn deserialize_info<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RnaInfo, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Recorded {
+        Info(RnaInfo),
+        Kind(String),
+    }
+    Ok(match Recorded::deserialize(deserializer)? {
+        Recorded::Info(info) => info,
+        Recorded::Kind(kind) => RnaInfo {
+            kind: kind.clone(),
+            ..RnaInfo::from_kit(&kind).unwrap_or_default()
+        },
+    })
+}
+
+/// Reads whose poly-A tail length falls within one bin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolyABin {
+    pub start: u32,
+    pub reads: u64,
+}
+
+/// Poly-A tail lengths and strand orientation of an RNA basecall
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RnaResults {
+    /// Reads by tail length, in bins of `POLY_A_BIN_WIDTH` bases
+    pub poly_a: Vec<PolyABin>,
+    /// Reads with an estimated tail
+    pub poly_a_reads: u64,
+    pub median_poly_a: u32,
+    /// Reads from the transcript's strand, from its complement, and without a `ts` tag
+    pub sense_reads: u64,
+    pub antisense_reads: u64,
+    pub unknown_strand_reads: u64,
+}
+
+impl RnaResults {
+    /// Percentage of reads with a known strand that are sense, `None` when no read has a strand
+    pub fn sense_percent(&self) -> Option<f64> {
+        let known = self.sense_reads + self.antisense_reads;
+        (known > 0).then(|| self.sense_reads as f64 * 100.0 / known as f64)
+    }
+
+    /// The sense percentage for titles. dorado only writes `ts` tags with a spliced alignment,
+    /// so plain basecalls have no strand.
+    pub fn strand(&self) -> String {
+        match self.sense_percent() {
+            Some(percent) => format!("{percent:.1}% sense"),
+            None => "strand unknown".to_string(),
+        }
+    }
+}
+
+/// Poly-A tail lengths and strands of every primary read in a BAM file
+pub fn summarise(path: &Path) -> io::Result<RnaResults> {
+    let mut reader = bam::Reader::from_path(path)?;
+    let mut record = bam::Record::default();
+    let mut results = RnaResults::default();
+    let mut lengths = Vec::new();
+    while reader.read(&mut record)? {
+        if !record.is_primary() {
+            continue;
+        }
+        // dorado writes -1 when it couldn't find a tail
+        if let Some(length) = record.aux_int(b"pt").filter(|&length| length > 0) {
+            lengths.push(length as u32);
+        }
+        match record.aux_char(b"ts") {
+            Some(b'+') => results.sense_reads += 1,
+            Some(b'-') => results.antisense_reads += 1,
+            _ => results.unknown_strand_reads += 1,
+        }
+    }
+    lengths.sort_unstable();
+    results.poly_a_reads = lengths.len() as u64;
+    results.median_poly_a = lengths.get(lengths.len() / 2).copied().unwrap_or_default();
+    for length in lengths {
+        let start = length.min(POLY_A_MAX) / POLY_A_BIN_WIDTH * POLY_A_BIN_WIDTH;
+        match results.poly_a.last_mut() {
+            Some(bin) if bin.start == start => bin.reads += 1,
+            _ => results.poly_a.push(PolyABin { start, reads: 1 }),
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::bam::testing::*;
+
+    #[test]
+    fn test_model_selection() {
+        let info = RnaInfo::from_kit("SQK-RNA002").unwrap();
+        assert_eq!(info.model("fast"), Some("rna002_70bps_fast@v3".to_string()));
+        assert_eq!(info.model("sup"), Some("rna002_70bps_hac@v3".to_string()));
+        assert_eq!(info.model("rna002_70bps_fast@v3"), None);
+        let info = RnaInfo::from_kit("SQK-RNA004").unwrap();
+        assert_eq!(
+            info.model("hac"),
+            Some("rna004_130bps_hac@v5.1.0".to_string())
+        );
+        assert_eq!(info.describe(), "direct RNA004 SQK-RNA004, poly-A");
+        let info = RnaInfo::from_kit("SQK-PCB114.24").unwrap();
+        assert_eq!(info.library, RnaLibrary::Cdna);
+        assert_eq!(info.model("hac"), None);
+        assert_eq!(RnaInfo::from_kit("SQK-LSK114"), None);
+    }
+
+    #[test]
+    fn test_summarise() {
+        let path = std::env::temp_dir().join(format!("fishtank-rna-{}.bam", std::process::id()));
+        let read = |name, aux: &[u8]| {
+            let mut record = TestRecord::new(name, vec![20; 10]);
+            record.aux = aux.to_vec();
+            record
+        };
+        write_bam(
+            &path,
+            &[
+                read("read1", b"ptC\x55tsA+"),
+                read("read2", b"ptC\x5atsA+"),
+                read("read3", b"pts\x2c\x01tsA-"),
+                read("read4", b"ptc\xff"),
+            ],
+        );
+
+        let results = summarise(&path).unwrap();
+        assert_eq!(
+            results.poly_a,
+            vec![
+                PolyABin {
+                    start: 80,
+                    reads: 1
+                },
+                PolyABin {
+                    start: 90,
+                    reads: 1
+                },
+                PolyABin {
+                    start: 250,
+                    reads: 1
+                },
+            ]
+        );
+        assert_eq!((results.poly_a_reads, results.median_poly_a), (3, 90));
+        assert_eq!(
+            (
+                results.sense_reads,
+                results.antisense_reads,
+                results.unknown_strand_reads
+            ),
+            (2, 1, 1)
+        );
+        assert!((results.sense_percent().unwrap() - 66.67).abs() < 0.01);
+        assert_eq!(results.strand(), "66.7% sense");
+        assert_eq!(RnaResults::default().strand(), "strand unknown");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,572 @@
+//! MinKNOW sample sheet CSV files
+//!
+//! A sheet has a `flow_cell_id` or `position_id` column plus `kit`, `experiment_id` and
+//! `sample_id`, and barcoded runs add `barcode` and `alias`. Columns may come in any order and
+//! unknown columns are ignored, as MinKNOW does.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Result;
+
+use super::{
+    barcoding,
+    project::{Barcoding, Project, Sample, SequencingInfo},
+};
+
+/// MinKNOW limits aliases to this many characters
+const MAX_ALIAS_LEN: usize = 40;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SampleSheet {
+    pub rows: Vec<SampleSheetRow>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SampleSheetRow {
+    pub flow_cell_id: String,
+    pub position_id: String,
+    pub experiment_id: String,
+    pub sample_id: String,
+    pub kit: String,
+    pub barcode: String,
+    pub alias: String,
+}
+
+/// A problem with one cell of a sample sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetError {
+    /// Line number in the file, the header is row 1
+    pub row: usize,
+    /// Column name, empty when the problem is with the whole row
+    pub column: String,
+    pub message: String,
+}
+
+impl fmt::Display for SheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.column.is_empty() {
+            write!(f, "row {}: {}", self.row, self.message)
+        } else {
+            write!(
+                f,
+                "row {}, column {}: {}",
+                self.row, self.column, self.message
+            )
+        }
+    }
+}
+
+/// Every problem found while reading a sample sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidSampleSheet(pub Vec<SheetError>);
+
+impl fmt::Display for InvalidSampleSheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors = self.0.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        write!(f, "Invalid sample sheet\n{}", errors.join("\n"))
+    }
+}
+
+impl std::error::Error for InvalidSampleSheet {}
+
+impl SampleSheet {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_csv())?;
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, InvalidSampleSheet> {
+        let mut errors = Vec::new();
+        fn error(errors: &mut Vec<SheetError>, row: usize, column: &str, message: String) {
+            errors.push(SheetError {
+                row,
+                column: column.to_string(),
+                message,
+            })
+        }
+
+        let mut lines = text
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, header)) = lines.next() else {
+            return Err(InvalidSampleSheet(vec![SheetError {
+                row: 1,
+                column: String::new(),
+                message: "the sheet is empty".to_string(),
+            }]));
+        };
+        let header = split_record(header)
+            .into_iter()
+            .map(|name| name.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        let index = |name: &str| header.iter().position(|column| column == name);
+
+        let location = if index("flow_cell_id").is_some() {
+            "flow_cell_id"
+        } else {
+            "position_id"
+        };
+        for required in [location, "kit", "experiment_id", "sample_id"] {
+            if index(required).is_none() {
+                error(&mut errors, 1, required, "missing column".to_string());
+            }
+        }
+        let barcoded = index("barcode").is_some();
+        if barcoded != index("alias").is_some() {
+            let missing = if barcoded { "alias" } else { "barcode" };
+            error(
+                &mut errors,
+                1,
+                missing,
+                "barcode and alias columns go together".to_string(),
+            );
+        }
+        if !errors.is_empty() {
+            return Err(InvalidSampleSheet(errors));
+        }
+
+        let mut rows = Vec::new();
+        let mut barcodes = HashSet::new();
+        let mut aliases = HashSet::new();
+        let mut first: Option<(usize, SampleSheetRow)> = None;
+        for (line, record) in lines {
+            let fields = split_record(record);
+            if fields.len() != header.len() {
+                error(
+                    &mut errors,
+                    line,
+                    "",
+                    format!("expected {} columns, found {}", header.len(), fields.len()),
+                );
+                continue;
+            }
+            let cells = header
+                .iter()
+                .map(String::as_str)
+                .zip(fields.into_iter().map(|field| field.trim().to_string()))
+                .collect::<HashMap<_, _>>();
+            let cell = |name: &str| cells.get(name).cloned().unwrap_or_default();
+            let row = SampleSheetRow {
+                flow_cell_id: cell("flow_cell_id"),
+                position_id: cell("position_id"),
+                experiment_id: cell("experiment_id"),
+                sample_id: cell("sample_id"),
+                kit: cell("kit"),
+                barcode: cell("barcode"),
+                alias: cell("alias"),
+            };
+
+            for required in [location, "kit", "experiment_id", "sample_id"] {
+                if cell(required).is_empty() {
+                    error(&mut errors, line, required, "value is required".to_string());
+                }
+            }
+
+            // One sheet describes one run, so the run columns must agree on every row
+            match &first {
+                None => first = Some((line, row.clone())),
+                Some((first_line, first_row)) => {
+                    for (column, value, expected) in [
+                        ("flow_cell_id", &row.flow_cell_id, &first_row.flow_cell_id),
+                        ("position_id", &row.position_id, &first_row.position_id),
+                        (
+                            "experiment_id",
+                            &row.experiment_id,
+                            &first_row.experiment_id,
+                        ),
+                        ("kit", &row.kit, &first_row.kit),
+                    ] {
+                        if value != expected {
+                            error(
+                                &mut errors,
+                                line,
+                                column,
+                                format!("{value} differs from {expected} on row {first_line}"),
+                            );
+                        }
+                    }
+                }
+            }
+
+            if barcoded {
+                let kit = barcode_kit(&row.kit);
+                match barcoding::barcode_number(&row.barcode) {
+                    None => error(
+                        &mut errors,
+                        line,
+                        "barcode",
+                        format!("{} is not a barcode name like barcode01", row.barcode),
+                    ),
+                    Some(n) => {
+                        if let Some(count) = kit.and_then(barcoding::barcode_count) {
+                            if n == 0 || n > count {
+                                error(
+                                    &mut errors,
+                                    line,
+                                    "barcode",
+                                    format!(
+                                        "{} is not in {} (barcode01 to barcode{count:02})",
+                                        row.barcode,
+                                        kit.unwrap_or_default()
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                }
+                if !row.barcode.is_empty() && !barcodes.insert(row.barcode.clone()) {
+                    error(
+                        &mut errors,
+                        line,
+                        "barcode",
+                        format!("{} is used twice", row.barcode),
+                    );
+                }
+                if kit.is_none() && !row.kit.is_empty() {
+                    error(
+                        &mut errors,
+                        line,
+                        "kit",
+                        format!("{} has no barcoding kit", row.kit),
+                    );
+                }
+
+                if row.alias.is_empty() {
+                    error(&mut errors, line, "alias", "value is required".to_string());
+                } else if row.alias.len() > MAX_ALIAS_LEN
+                    || !row
+                        .alias
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    error(
+                        &mut errors,
+                        line,
+                        "alias",
+                        format!(
+                            "{} must be at most {MAX_ALIAS_LEN} letters, digits, - or _",
+                            row.alias
+                        ),
+                    );
+                } else if barcoding::barcode_number(&row.alias).is_some() {
+                    error(
+                        &mut errors,
+                        line,
+                        "alias",
+                        format!("{} would be confused with a barcode", row.alias),
+                    );
+                } else if !aliases.insert(row.alias.clone()) {
+                    error(
+                        &mut errors,
+                        line,
+                        "alias",
+                        format!("{} is used twice", row.alias),
+                    );
+                }
+            }
+            rows.push(row);
+        }
+
+        if rows.is_empty() && errors.is_empty() {
+            error(&mut errors, 2, "", "the sheet has no samples".to_string());
+        }
+        if errors.is_empty() {
+            Ok(Self { rows })
+        } else {
+            Err(InvalidSampleSheet(errors))
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let barcoded = self.rows.iter().any(|row| !row.barcode.is_empty());
+        let by_position = self.rows.iter().all(|row| row.flow_cell_id.is_empty());
+        let location = if by_position {
+            "position_id"
+        } else {
+            "flow_cell_id"
+        };
+
+        let mut header = vec![location, "kit", "sample_id", "experiment_id"];
+        if barcoded {
+            header.extend(["barcode", "alias"]);
+        }
+        let mut lines = vec![header.join(",")];
+        for row in &self.rows {
+            let mut fields = vec![
+                if by_position {
+                    &row.position_id
+                } else {
+                    &row.flow_cell_id
+                },
+                &row.kit,
+                &row.sample_id,
+                &row.experiment_id,
+            ];
+            if barcoded {
+                fields.extend([&row.barcode, &row.alias]);
+            }
+            let fields = fields
+                .into_iter()
+                .map(|field| quote(field))
+                .collect::<Vec<_>>();
+            lines.push(fields.join(","));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    /// The barcoding kit named in the sheet's `kit` column, if any
+    pub fn barcode_kit(&self) -> Option<&str> {
+        self.rows.first().and_then(|row| barcode_kit(&row.kit))
+    }
+
+    /// Describe a project's run and samples as a sheet
+    pub fn from_project(project: &Project) -> Self {
+        let info = &project.sequencing;
+        let run_row = SampleSheetRow {
+            flow_cell_id: info.flow_cell_id.clone(),
+            position_id: info.position_id.clone(),
+            experiment_id: info.experiment_id.clone(),
+            sample_id: info.sample_id.clone(),
+            kit: info.kit.clone(),
+            ..Default::default()
+        };
+        let rows = match &project.barcoding {
+            Some(barcoding) if !barcoding.samples.is_empty() => {
+                // The sheet's kit column has to name the barcoding kit too
+                let kit = if info.kit.split_whitespace().any(|kit| kit == barcoding.kit) {
+                    info.kit.clone()
+                } else {
+                    format!("{} {}", info.kit, barcoding.kit).trim().to_string()
+                };
+                barcoding
+                    .samples
+                    .iter()
+                    .map(|sample| SampleSheetRow {
+                        sample_id: sample.name.clone(),
+                        kit: kit.clone(),
+                        barcode: sample.barcode.clone(),
+                        alias: sample.alias.clone(),
+                        ..run_row.clone()
+                    })
+                    .collect()
+            }
+            _ => vec![run_row],
+        };
+        Self { rows }
+    }
+
+    /// Set the project's run details and barcode assignments from the sheet
+    pub fn apply(&self, project: &mut Project) {
+        let Some(first) = self.rows.first() else {
+            return;
+        };
+        let barcoded = self.rows.iter().any(|row| !row.barcode.is_empty());
+        project.sequencing = SequencingInfo {
+            flow_cell_id: first.flow_cell_id.clone(),
+            position_id: first.position_id.clone(),
+            experiment_id: first.experiment_id.clone(),
+            sample_id: if barcoded {
+                String::new()
+            } else {
+                first.sample_id.clone()
+            },
+            kit: first.kit.clone(),
+            ..project.sequencing.clone()
+        };
+        project.barcoding = match self.barcode_kit() {
+            Some(kit) if barcoded => Some(Barcoding {
+                kit: kit.to_string(),
+                samples: self
+                    .rows
+                    .iter()
+                    .map(|row| Sample {
+                        barcode: row.barcode.clone(),
+                        name: row.sample_id.clone(),
+                        alias: row.alias.clone(),
+                    })
+                    .collect(),
+            }),
+            _ => project.barcoding.take(),
+        };
+    }
+}
+
+/// The barcoding kit among the space separated kits in a `kit` cell
+fn barcode_kit(kits: &str) -> Option<&str> {
+    kits.split_whitespace()
+        .find(|kit| barcoding::barcode_count(kit).is_some())
+}
+
+/// The sample sheet MinKNOW copies into a run's output directory
+pub fn find_in(dir: &Path) -> Option<PathBuf> {
+    let mut sheets = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "csv")
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("sample_sheet"))
+        })
+        .collect::<Vec<_>>();
+    sheets.sort();
+    sheets.pop()
+}
+
+/// Split one CSV line, honouring double quoted fields
+fn split_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const SHEET: &str = "\
+flow_cell_id,kit,sample_id,experiment_id,barcode,alias,type
+PAQ12345,SQK-NBD114-24,liver,\"mouse, 2024\",barcode01,liver_a,test_sample
+PAQ12345,SQK-NBD114-24,liver,\"mouse, 2024\",barcode02,liver_b,test_sample
+";
+
+    fn errors(text: &str) -> Vec<String> {
+        SampleSheet::parse(text)
+            .unwrap_err()
+            .0
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_import_sample_sheet() {
+        let sheet = SampleSheet::parse(SHEET).unwrap();
+        let mut project = Project::default();
+        sheet.apply(&mut project);
+
+        assert_eq!(
+            project.sequencing,
+            SequencingInfo {
+                flow_cell_id: "PAQ12345".to_string(),
+                experiment_id: "mouse, 2024".to_string(),
+                kit: "SQK-NBD114-24".to_string(),
+                ..Default::default()
+            }
+        );
+        let barcoding = project.barcoding.clone().unwrap();
+        assert_eq!(barcoding.kit, "SQK-NBD114-24");
+        assert_eq!(
+            barcoding.sample("barcode02"),
+            Some(&Sample {
+                barcode: "barcode02".to_string(),
+                name: "liver".to_string(),
+                alias: "liver_b".to_string(),
+            })
+        );
+
+        // Exporting gives back the same rows
+        let exported = SampleSheet::from_project(&project);
+        assert_eq!(exported, sheet);
+        assert_eq!(SampleSheet::parse(&exported.to_csv()).unwrap(), sheet);
+    }
+
+    #[test]
+    fn test_unbarcoded_sheet() {
+        let sheet = SampleSheet::parse(
+            "position_id,kit,sample_id,experiment_id\r\n1A,SQK-LSK114,blood,exp1\r\n",
+        )
+        .unwrap();
+        let mut project = Project::default();
+        sheet.apply(&mut project);
+        assert_eq!(project.sequencing.position_id, "1A");
+        assert_eq!(project.sequencing.sample_id, "blood");
+        assert_eq!(project.barcoding, None);
+        assert_eq!(SampleSheet::from_project(&project), sheet);
+    }
+
+    #[test]
+    fn test_sample_sheet_errors() {
+        assert_eq!(
+            errors("flow_cell_id,kit,sample_id\n"),
+            vec!["row 1, column experiment_id: missing column"]
+        );
+        assert_eq!(
+            errors(
+                "\
+flow_cell_id,kit,sample_id,experiment_id,barcode,alias
+PAQ12345,SQK-NBD114-24,liver,exp1,barcode01,liver_a
+PAQ12345,SQK-NBD114-24,,exp1,barcode25,liver_a
+PAQ99999,SQK-NBD114-24,liver,exp1,barcode01,barcode03
+PAQ12345,SQK-NBD114-24,liver
+"
+            ),
+            vec![
+                "row 3, column sample_id: value is required",
+                "row 3, column barcode: barcode25 is not in SQK-NBD114-24 (barcode01 to barcode24)",
+                "row 3, column alias: liver_a is used twice",
+                "row 4, column flow_cell_id: PAQ99999 differs from PAQ12345 on row 2",
+                "row 4, column barcode: barcode01 is used twice",
+                "row 4, column alias: barcode03 would be confused with a barcode",
+                "row 5: expected 6 columns, found 3",
+            ]
+        );
+        assert_eq!(
+            errors("flow_cell_id,kit,sample_id,experiment_id,barcode,alias\nPAQ1,SQK-LSK114,s,e,barcode01,a\n"),
+            vec!["row 2, column kit: SQK-LSK114 has no barcoding kit"]
+        );
+    }
+
+    #[test]
+    fn test_find_in() {
+        let dir = std::env::temp_dir().join(format!("fishtank-sheet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(find_in(&dir), None);
+        std::fs::write(dir.join("sample_sheet_PAQ12345_20240101.csv"), SHEET).unwrap();
+        std::fs::write(dir.join("report.csv"), "").unwrap();
+        assert_eq!(
+            find_in(&dir),
+            Some(dir.join("sample_sheet_PAQ12345_20240101.csv"))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{lifecycle::LifecycleRule, project::Project};
+use crate::disk;
+
+/// Growth samples kept per project, older ones are dropped first
+const MAX_SIZE_SAMPLES: usize = 200;
+
+/// Storage settings, read from the `storage` section of the config file
+#[derive(Clone, Debug, Deserialize)]
+pub struct StorageConfig {
+    /// Volumes to report free space on
+    #[serde(default)]
+    pub volumes: Vec<Volume>,
+    /// How often (in seconds) project disk usage is recounted
+    #[serde(default = "default_usage_check_interval")]
+    pub usage_check_interval: u64,
+    /// Expected size of basecall output as a fraction of its raw input
+    #[serde(default = "default_basecall_output_ratio")]
+    pub basecall_output_ratio: f64,
+    /// Lifecycle rules for projects that don't set their own
+    #[serde(default)]
+    pub lifecycle: Vec<LifecycleRule>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            volumes: Vec::new(),
+            usage_check_interval: default_usage_check_interval(),
+            basecall_output_ratio: default_basecall_output_ratio(),
+            lifecycle: Vec::new(),
+        }
+    }
+}
+
+fn default_usage_check_interval() -> u64 {
+    3600
+}
+
+fn default_basecall_output_ratio() -> f64 {
+    0.3
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Volume {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Bytes a project takes up on disk, by kind of data
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageUsage {
+    pub raw: u64,
+    pub basecalls: u64,
+    pub alignments: u64,
+    pub reports: u64,
+    pub other: u64,
+    /// When the usage was measured, in seconds since the unix epoch
+    pub measured_at: u64,
+}
+
+impl StorageUsage {
+    pub fn total(&self) -> u64 {
+        self.raw + self.basecalls + self.alignments + self.reports + self.other
+    }
+
+    fn add(&mut self, kind: DataKind, bytes: u64) {
+        match kind {
+            DataKind::Raw => self.raw += bytes,
+            DataKind::Basecalls => self.basecalls += bytes,
+            DataKind::Alignments => self.alignments += bytes,
+            DataKind::Reports => self.reports += bytes,
+            DataKind::Other => self.other += bytes,
+        }
+    }
+}
+
+/// A project's total size at one point in time
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeSample {
+    pub at: u64,
+    pub bytes: u64,
+}
+
+/// Free space on a configured volume
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeUsage {
+    pub name: String,
+    pub path: String,
+    pub free: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataKind {
+    Raw,
+    Basecalls,
+    Alignments,
+    Reports,
+    Other,
+}
+
+/// Kind of data in a file that isn't a known basecall output, going by its name
+fn classify(path: &Path) -> DataKind {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .unwrap_or_default();
+    match extension {
+        "pod5" | "fast5" | "blow5" | "slow5" => DataKind::Raw,
+        "cram" | "crai" | "bai" | "csi" | "sam" => DataKind::Alignments,
+        "bam" if name.contains("sorted") || name.contains("aligned") => DataKind::Alignments,
+        "bam" | "fastq" | "fq" => DataKind::Basecalls,
+        "txt" | "tsv" | "csv" | "json" | "html" | "pdf" | "md" | "log" => DataKind::Reports,
+        _ => DataKind::Other,
+    }
+}
+
+/// Total size of a file, or of every file below a directory
+pub fn path_size(path: &Path) -> io::Result<u64> {
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += path_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+fn walk(path: &Path, outputs: &[PathBuf], usage: &mut StorageUsage) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            walk(&entry?.path(), outputs, usage)?;
+        }
+    } else if metadata.is_file() {
+        let kind = if outputs.iter().any(|output| path.starts_with(output)) {
+            DataKind::Basecalls
+        } else {
+            classify(path)
+        };
+        usage.add(kind, metadata.len());
+    }
+    Ok(())
+}
+
+/// Measure the project's data directory and any basecall outputs kept outside it
+pub fn measure(project: &Project) -> io::Result<StorageUsage> {
+    let mut outputs = project
+        .basecall_logs()
+        .map(|log| PathBuf::from(&log.basecall_run.output_path))
+        .chain(
+            project
+                .demux_logs()
+                .map(|log| PathBuf::from(&log.output_dir)),
+        )
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect::<Vec<_>>();
+    outputs.sort();
+    outputs.dedup();
+
+    let mut usage = StorageUsage {
+        measured_at: super::now(),
+        ..Default::default()
+    };
+    let data = PathBuf::from(&project.data_location);
+    if !project.data_location.is_empty() && data.exists() {
+        walk(&data, &outputs, &mut usage)?;
+    }
+    for output in outputs.iter().filter(|output| !output.starts_with(&data)) {
+        if output.exists() {
+            walk(output, &outputs, &mut usage)?;
+        }
+    }
+    Ok(usage)
+}
+
+/// Free and total space on each volume, skipping any that can't be read
+pub fn volume_usage(volumes: &[Volume]) -> Vec<VolumeUsage> {
+    volumes
+        .iter()
+        .filter_map(|volume| {
+            let (free, total) = disk::space(&volume.path).ok()?;
+            Some(VolumeUsage {
+                name: volume.name.clone(),
+                path: volume.path.display().to_string(),
+                free,
+                total,
+            })
+        })
+        .collect()
+}
+
+impl Project {
+    /// Store a new measurement, keeping a sample whenever the total size changes
+    ///
+    /// Returns whether a sample was kept, i.e. the record changed enough to be worth saving.
+    pub fn record_usage(&mut self, usage: StorageUsage) -> bool {
+        let bytes = usage.total();
+        let changed = self.size_history.last().map(|sample| sample.bytes) != Some(bytes);
+        if changed {
+            self.size_history.push(SizeSample {
+                at: usage.measured_at,
+                bytes,
+            });
+            if self.size_history.len() > MAX_SIZE_SAMPLES {
+                self.size_history.remove(0);
+            }
+        }
+        self.data_size = bytes;
+        self.storage = usage;
+        changed
+    }
+
+    /// Change in total size since `since`, measured from the last sample taken by then
+    pub fn growth_since(&self, since: u64) -> i64 {
+        let before = self
+            .size_history
+            .iter()
+            .rev()
+            .find(|sample| sample.at <= since)
+            .or(self.size_history.first())
+            .map(|sample| sample.bytes)
+            .unwrap_or(self.data_size);
+        self.data_size as i64 - before as i64
+    }
+}
+
+/// Free space on the volume `output` will be written to
+pub fn free_space_for(output: &Path) -> io::Result<u64> {
+    // The output directory may not exist yet, so check the closest ancestor that does
+    let volume = output
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.exists())
+        .unwrap_or(Path::new("."));
+    let (free, _) = disk::space(volume)?;
+    Ok(free)
+}
+
+/// Human readable size with binary units, e.g. `1.5 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::{
+        BasecallLog, BasecallRun, BasecallStatus, ProjectAction, ProjectHistory,
+    };
+
+    #[test]
+    fn test_measure_by_kind() {
+        let dir = std::env::temp_dir().join(format!("fishtank-storage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("pod5_pass")).unwrap();
+        std::fs::create_dir_all(dir.join("calls")).unwrap();
+        std::fs::write(dir.join("pod5_pass/a.pod5"), vec![0; 1000]).unwrap();
+        std::fs::write(dir.join("calls/out.bam"), vec![0; 300]).unwrap();
+        std::fs::write(dir.join("calls/out.sorted.bam"), vec![0; 200]).unwrap();
+        std::fs::write(dir.join("final_summary.txt"), vec![0; 10]).unwrap();
+        std::fs::write(dir.join("notes.xyz"), vec![0; 5]).unwrap();
+
+        let mut project = Project {
+            data_location: dir.display().to_string(),
+            ..Default::default()
+        };
+        let run = BasecallRun {
+            path: dir.join("pod5_pass").display().to_string(),
+            output_path: dir.join("calls/out.bam").display().to_string(),
+            ..Default::default()
+        };
+        let mut log = BasecallLog::new(&run, String::new());
+        log.status = BasecallStatus::Success;
+        project.history.push(ProjectHistory {
+            action: ProjectAction::Basecall(log),
+            created_by: String::new(),
+            created_at: 0,
+        });
+
+        let usage = measure(&project).unwrap();
+        assert_eq!(
+            (
+                usage.raw,
+                usage.basecalls,
+                usage.alignments,
+                usage.reports,
+                usage.other
+            ),
+            (1000, 300, 200, 10, 5)
+        );
+
+        assert!(project.record_usage(usage.clone()));
+        assert!(!project.record_usage(usage));
+        assert_eq!(project.data_size, 1515);
+        assert_eq!(project.size_history.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_growth_since() {
+        let mut project = Project::default();
+        for (at, bytes) in [(100, 1000), (200, 1500), (300, 4000)] {
+            project.record_usage(StorageUsage {
+                raw: bytes,
+                measured_at: at,
+                ..Default::default()
+            });
+        }
+        assert_eq!(project.growth_since(250), 2500);
+        assert_eq!(project.growth_since(50), 3000);
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536 * 1024 * 1024), "1.5 GB");
+    }
+}
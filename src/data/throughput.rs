@@ -0,0 +1,206 @@
+//! How fast each machine basecalls, learned from the runs it has finished
+//!
+//! Every successful basecall records where it ran, how much raw data it read and when it
+//! finished, along with the samples per second dorado reports at the end of its log. Grouping
+//! those by host, hardware and model gives a throughput table that predicts how long a new run
+//! of a given size will take.
+
+use serde::{Deserialize, Serialize};
+
+use super::project::{Project, ProjectAction};
+
+/// What dorado logs when a basecall finishes, followed by its rate
+const SAMPLES_RATE_PREFIX: &str = "Samples/s:";
+
+/// Throughput of the finished runs of one model on one machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputEntry {
+    pub host: String,
+    pub hardware: String,
+    pub model: String,
+    pub runs: usize,
+    /// Raw data read by all the runs
+    pub input_bytes: u64,
+    /// Wall-clock time taken by all the runs
+    pub seconds: u64,
+    /// Mean of the rates dorado reported, `None` if no run logged one
+    pub samples_per_second: Option<f64>,
+}
+
+impl ThroughputEntry {
+    pub fn bytes_per_second(&self) -> f64 {
+        self.input_bytes as f64 / self.seconds.max(1) as f64
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputTable {
+    /// Sorted by host, hardware and model
+    pub entries: Vec<ThroughputEntry>,
+}
+
+impl ThroughputTable {
+    /// Every successful run with a known duration and input size
+    ///
+    /// Resumed runs are left out, their wall-clock time includes however long they were stopped.
+    pub fn from_projects(projects: &[Project]) -> Self {
+        let mut entries: Vec<ThroughputEntry> = Vec::new();
+        let mut rates: Vec<Vec<f64>> = Vec::new();
+        for entry in projects.iter().flat_map(|project| &project.history) {
+            let ProjectAction::Basecall(log) = &entry.action else {
+                continue;
+            };
+            let Some(finished_at) = log.finished_at else {
+                continue;
+            };
+            if log.command.len() != 1 || log.input_bytes == 0 || finished_at <= entry.created_at {
+                continue;
+            }
+            let model = &log.basecall_run.basecaller.model;
+            let i = match entries.iter().position(|row| {
+                row.host == log.host && row.hardware == log.hardware && row.model == *model
+            }) {
+                Some(i) => i,
+                None => {
+                    entries.push(ThroughputEntry {
+                        host: log.host.clone(),
+                        hardware: log.hardware.clone(),
+                        model: model.clone(),
+                        ..Default::default()
+                    });
+                    rates.push(Vec::new());
+                    entries.len() - 1
+                }
+            };
+            entries[i].runs += 1;
+            entries[i].input_bytes += log.input_bytes;
+            entries[i].seconds += finished_at - entry.created_at;
+            rates[i].extend(log.samples_per_second);
+        }
+        for (entry, rates) in entries.iter_mut().zip(rates) {
+            entry.samples_per_second =
+                (!rates.is_empty()).then(|| rates.iter().sum::<f64>() / rates.len() as f64);
+        }
+        entries.sort_by(|a, b| {
+            (&a.host, &a.hardware, &a.model).cmp(&(&b.host, &b.hardware, &b.model))
+        });
+        Self { entries }
+    }
+
+    /// Predicted wall-clock seconds for basecalling `input_bytes` with `model` on `host`
+    ///
+    /// Runs of the same model are preferred, falling back to everything the host has basecalled
+    /// when the model hasn't been run there yet. `None` until the host has finished a run.
+    pub fn estimate(&self, host: &str, model: &str, input_bytes: u64) -> Option<u64> {
+        let on_host = self
+            .entries
+            .iter()
+            .filter(|entry| entry.host == host)
+            .collect::<Vec<_>>();
+        let same_model = on_host
+            .iter()
+            .filter(|entry| entry.model == model)
+            .copied()
+            .collect::<Vec<_>>();
+        let entries = if same_model.is_empty() {
+            on_host
+        } else {
+            same_model
+        };
+        let bytes = entries.iter().map(|entry| entry.input_bytes).sum::<u64>();
+        let seconds = entries.iter().map(|entry| entry.seconds).sum::<u64>();
+        (seconds > 0).then(|| (input_bytes as f64 * seconds as f64 / bytes as f64).ceil() as u64)
+    }
+}
+
+/// The rate in dorado's closing `Basecalled @ Samples/s: 3.2e+07` line
+pub fn parse_samples_per_second(line: &str) -> Option<f64> {
+    let (_, rate) = line.split_once(SAMPLES_RATE_PREFIX)?;
+    rate.trim().parse().ok()
+}
+
+/// Hours and minutes, e.g. `3h 05m`
+pub fn format_duration(seconds: u64) -> String {
+    let minutes = seconds.div_ceil(60);
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::{
+        BasecallLog, BasecallRun, BasecallStatus, Basecaller, ProjectHistory,
+    };
+
+    fn finished(host: &str, model: &str, input_bytes: u64, seconds: u64) -> ProjectHistory {
+        let run = BasecallRun {
+            basecaller: Basecaller {
+                model: model.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        ProjectHistory {
+            action: ProjectAction::Basecall(BasecallLog {
+                status: BasecallStatus::Success,
+                host: host.to_string(),
+                hardware: "A100".to_string(),
+                input_bytes,
+                finished_at: Some(1000 + seconds),
+                samples_per_second: Some(input_bytes as f64 / seconds as f64),
+                ..BasecallLog::new(&run, "dorado".to_string())
+            }),
+            created_by: String::new(),
+            created_at: 1000,
+        }
+    }
+
+    #[test]
+    fn test_table_and_estimates() {
+        let projects = [Project {
+            history: vec![
+                finished("gpu01", "hac", 100, 10),
+                finished("gpu01", "hac", 300, 10),
+                finished("gpu01", "sup", 100, 100),
+                finished("local", "hac", 1000, 1),
+            ],
+            ..Default::default()
+        }];
+        let table = ThroughputTable::from_projects(&projects);
+        assert_eq!(table.entries.len(), 3);
+        assert_eq!(
+            table.entries[0],
+            ThroughputEntry {
+                host: "gpu01".to_string(),
+                hardware: "A100".to_string(),
+                model: "hac".to_string(),
+                runs: 2,
+                input_bytes: 400,
+                seconds: 20,
+                samples_per_second: Some(20.0),
+            }
+        );
+        assert_eq!(table.estimate("gpu01", "hac", 1000), Some(50));
+        assert_eq!(table.estimate("gpu01", "sup", 1000), Some(1000));
+        // Nothing run with fast yet, so every gpu01 run counts
+        assert_eq!(table.estimate("gpu01", "fast", 500), Some(120));
+        assert_eq!(table.estimate("gpu02", "hac", 1000), None);
+    }
+
+    #[test]
+    fn test_parse_samples_per_second() {
+        assert_eq!(
+            parse_samples_per_second(
+                "[2024-05-01 10:00:00.000] [info] > Basecalled @ Samples/s: 3.5e+07"
+            ),
+            Some(3.5e7)
+        );
+        assert_eq!(
+            parse_samples_per_second("[info] > Reads basecalled: 4000"),
+            None
+        );
+        assert_eq!(format_duration(3 * 3600 + 241), "3h 05m");
+    }
+}
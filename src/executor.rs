@@ -0,0 +1,489 @@
+use std::process::{ExitStatus, Stdio};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncReadExt, process::Command, sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::{
+    action::Action,
+    data::{
+        alignment::Minimap2,
+        project::{BasecallRun, BasecallStatus},
+    },
+    notifications::Notification,
+};
+
+pub mod local;
+pub mod slurm;
+pub mod ssh;
+
+pub use local::LocalExecutor;
+pub use slurm::{SlurmConfig, SlurmExecutor};
+pub use ssh::{SshConfig, SshExecutor};
+
+/// A basecall run submitted to an executor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: usize,
+    pub project: String,
+    pub run: BasecallRun,
+    /// Partial output of an interrupted attempt, passed to dorado's `--resume-from`
+    pub resume_from: Option<String>,
+    #[serde(default)]
+    pub kind: JobKind,
+}
+
+/// What a job does with its basecall run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobKind {
+    #[default]
+    Basecall,
+    /// Split the run's output BAM into one file per barcode
+    Demux {
+        /// Kit to classify reads with, `None` when the basecaller already did
+        kit: Option<String>,
+        output_dir: String,
+    },
+    /// Convert the run's FAST5 input to POD5 with the `pod5` tool, which is the run's basecaller
+    Convert {
+        output_dir: String,
+        /// Remove the FAST5 files once the read counts are verified
+        remove_originals: bool,
+    },
+    /// Align the run's output BAM to a reference FASTA, writing the aligned BAM to stdout
+    Align {
+        reference: String,
+        output_path: String,
+        /// Align with `minimap2 | samtools sort` instead of `dorado aligner`
+        minimap2: Option<Minimap2>,
+    },
+}
+
+impl Job {
+    /// Name shown for the job and used for its staging directories
+    pub fn name(&self) -> String {
+        match self.kind {
+            JobKind::Basecall => self.run.name.clone(),
+            JobKind::Demux { .. } => format!("{}-demux", self.run.name),
+            JobKind::Convert { .. } => format!("{}-pod5", self.run.name),
+            JobKind::Align { .. } => format!("{}-align", self.run.name),
+        }
+    }
+
+    /// What the job does, as named in notifications
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            JobKind::Basecall => "Basecall",
+            JobKind::Demux { .. } => "Demultiplexing",
+            JobKind::Convert { .. } => "Conversion to POD5",
+            JobKind::Align { .. } => "Alignment",
+        }
+    }
+
+    /// Notification for a job that finished, or failed with `reason`
+    pub fn notification(&self, reason: Option<String>) -> Notification {
+        let (project, run) = (self.project.clone(), self.name());
+        match (&self.kind, reason) {
+            (JobKind::Basecall, None) => Notification::BasecallFinished { project, run },
+            (JobKind::Basecall, Some(reason)) => Notification::BasecallFailed {
+                project,
+                run,
+                reason,
+            },
+            (_, None) => Notification::JobFinished {
+                project,
+                job: self.label().to_string(),
+                run,
+            },
+            (_, Some(reason)) => Notification::JobFailed {
+                project,
+                job: self.label().to_string(),
+                run,
+                reason,
+            },
+        }
+    }
+
+    /// File or directory the job reads
+    pub fn input(&self) -> &str {
+        match self.kind {
+            JobKind::Basecall | JobKind::Convert { .. } => &self.run.path,
+            JobKind::Demux { .. } | JobKind::Align { .. } => &self.run.output_path,
+        }
+    }
+
+    /// File or directory the job writes
+    pub fn output(&self) -> &str {
+        match &self.kind {
+            JobKind::Basecall => &self.run.output_path,
+            JobKind::Demux { output_dir, .. } | JobKind::Convert { output_dir, .. } => output_dir,
+            JobKind::Align { output_path, .. } => output_path,
+        }
+    }
+
+    /// File the command's stdout goes to, demux and conversion write their own files into
+    /// `output`
+    pub fn stdout(&self) -> Option<&str> {
+        match &self.kind {
+            JobKind::Basecall => Some(&self.run.output_path),
+            JobKind::Align { output_path, .. } => Some(output_path),
+            JobKind::Demux { .. } | JobKind::Convert { .. } => None,
+        }
+    }
+
+    /// Whether the job runs dorado, rather than another tool such as `pod5` or `minimap2`
+    pub fn runs_dorado(&self) -> bool {
+        !matches!(
+            self.kind,
+            JobKind::Convert { .. }
+                | JobKind::Align {
+                    minimap2: Some(_),
+                    ..
+                }
+        )
+    }
+
+    /// The command line for the job
+    pub fn command(&self) -> Vec<String> {
+        match &self.kind {
+            JobKind::Basecall => {
+                let mut command = self.run.command();
+                if let Some(partial) = &self.resume_from {
+                    command.extend(["--resume-from".to_string(), partial.clone()]);
+                }
+                command
+            }
+            JobKind::Demux { kit, output_dir } => {
+                let mut command = vec![
+                    self.run.basecaller.binary(),
+                    "demux".to_string(),
+                    "--output-dir".to_string(),
+                    output_dir.clone(),
+                ];
+                match kit {
+                    Some(kit) => command.extend(["--kit-name".to_string(), kit.clone()]),
+                    None => command.push("--no-classify".to_string()),
+                }
+                command.push(self.run.output_path.clone());
+                command
+            }
+            // One POD5 per FAST5, laid out like the input
+            JobKind::Convert { output_dir, .. } => vec![
+                self.run.basecaller.binary(),
+                "convert".to_string(),
+                "fast5".to_string(),
+                self.run.path.clone(),
+                "--recursive".to_string(),
+                "--output".to_string(),
+                output_dir.clone(),
+                "--one-to-one".to_string(),
+                self.run.path.clone(),
+            ],
+            JobKind::Align {
+                reference,
+                minimap2: None,
+                ..
+            } => vec![
+                self.run.basecaller.binary(),
+                "aligner".to_string(),
+                reference.clone(),
+                self.run.output_path.clone(),
+            ],
+            // The input and reference are passed as arguments so executors can stage them, the
+            // ssh executor also swaps in its own tool paths
+            JobKind::Align {
+                reference,
+                minimap2: Some(tools),
+                ..
+            } => vec![
+                "bash".to_string(),
+                "-c".to_string(),
+                format!(
+                    "set -o pipefail; {} fastq -T '*' \"$1\" | {} -y -ax {} \"$2\" - | {} sort -",
+                    shell_quote(&tools.samtools),
+                    shell_quote(&tools.minimap2),
+                    shell_quote(&tools.preset),
+                    shell_quote(&tools.samtools)
+                ),
+                "bash".to_string(),
+                self.run.output_path.clone(),
+                reference.clone(),
+            ],
+        }
+    }
+
+    /// The command as a single shell-quoted line, as recorded in `BasecallLog::command`
+    pub fn command_line(&self) -> String {
+        let argv = self
+            .command()
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        match self.stdout() {
+            Some(stdout) => format!("{argv} > {}", shell_quote(stdout)),
+            None => argv,
+        }
+    }
+}
+
+/// A backend that basecall jobs run on
+///
+/// Executors report output with `Action::JobProgress` and finish every job with a single
+/// `Action::JobStatus` carrying `BasecallStatus::Success` or `BasecallStatus::Failed`, so the
+/// local, ssh and slurm backends can be swapped through the `execution.backend` setting.
+pub trait Executor: Send + Sync {
+    /// Short name shown in the job view
+    fn name(&self) -> &str;
+
+    /// Start the job in the background
+    fn spawn(&self, job: Job, tx: UnboundedSender<Action>) -> JoinHandle<()>;
+}
+
+/// Execution settings, read from the `execution` section of the config file
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExecutionConfig {
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub ssh: Option<SshConfig>,
+    #[serde(default)]
+    pub slurm: Option<SlurmConfig>,
+    /// Path to the `pod5` tool used for FAST5 conversion, `pod5` on `PATH` if unset
+    #[serde(default)]
+    pub pod5: Option<String>,
+    /// Description of the backend's GPUs, e.g. `4x A100`, recorded with each basecall so its
+    /// throughput is compared with runs on the same hardware
+    #[serde(default)]
+    pub hardware: Option<String>,
+    /// Hours a job may take, such as a GPU reservation or slurm time limit. Jobs expected to run
+    /// longer are marked in the jobs view.
+    #[serde(default)]
+    pub runtime_window_hours: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Local,
+    Ssh,
+    Slurm,
+}
+
+impl ExecutionConfig {
+    /// Build the executor selected by `backend`
+    pub fn executor(&self) -> Result<Box<dyn Executor>> {
+        Ok(match self.backend {
+            Backend::Local => Box::new(LocalExecutor),
+            Backend::Ssh => {
+                let ssh = self.ssh.clone().ok_or_else(|| {
+                    eyre!("execution.backend is ssh but no ssh host is configured")
+                })?;
+                Box::new(SshExecutor::new(ssh))
+            }
+            Backend::Slurm => {
+                let slurm = self.slurm.clone().ok_or_else(|| {
+                    eyre!("execution.backend is slurm but no slurm settings are configured")
+                })?;
+                Box::new(SlurmExecutor::new(slurm))
+            }
+        })
+    }
+}
+
+/// Run `command` to completion, forwarding every line it writes to stderr as job progress
+///
+/// Carriage returns are treated as line breaks so progress bars show up as they redraw. The
+/// process id is reported first so the resource monitor can follow the job's processes.
+pub(crate) async fn run_streaming(
+    job_id: usize,
+    mut command: Command,
+    tx: &UnboundedSender<Action>,
+) -> Result<ExitStatus> {
+    command
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command.spawn()?;
+    if let Some(pid) = child.id() {
+        let _ = tx.send(Action::JobProcess(job_id, pid));
+    }
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+
+    let mut buf = [0u8; 4096];
+    let mut lines = ProgressLines::new(job_id, tx);
+    loop {
+        let n = stderr.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        lines.push(&buf[..n]);
+    }
+    lines.flush();
+
+    Ok(child.wait().await?)
+}
+
+/// Splits raw output into `Action::JobProgress` lines
+pub(crate) struct ProgressLines<'a> {
+    job_id: usize,
+    tx: &'a UnboundedSender<Action>,
+    line: Vec<u8>,
+}
+
+impl<'a> ProgressLines<'a> {
+    pub fn new(job_id: usize, tx: &'a UnboundedSender<Action>) -> Self {
+        Self {
+            job_id,
+            tx,
+            line: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if byte == b'\n' || byte == b'\r' {
+                self.flush();
+            } else {
+                self.line.push(byte);
+            }
+        }
+    }
+
+    /// Send whatever is buffered as a line
+    pub fn flush(&mut self) {
+        let text = strip_ansi_escapes::strip_str(String::from_utf8_lossy(&self.line));
+        self.line.clear();
+        let text = text.trim();
+        if !text.is_empty() {
+            let _ = self
+                .tx
+                .send(Action::JobProgress(self.job_id, text.to_string()));
+        }
+    }
+}
+
+/// Turn a non-zero exit status into an error naming the program
+pub(crate) fn check_status(program: &str, status: ExitStatus) -> Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(eyre!("{program} exited with {status}"))
+    }
+}
+
+/// Send the final status of a job, with the error as the last progress line on failure
+pub(crate) fn report(job_id: usize, result: Result<()>, tx: &UnboundedSender<Action>) {
+    let status = match result {
+        Ok(()) => BasecallStatus::Success,
+        Err(e) => {
+            let _ = tx.send(Action::JobProgress(job_id, e.to_string()));
+            BasecallStatus::Failed
+        }
+    };
+    let _ = tx.send(Action::JobStatus(job_id, status));
+}
+
+/// Quote `arg` for a POSIX shell
+pub(crate) fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::Basecaller;
+
+    #[test]
+    fn test_demux_command_line() {
+        let mut job = Job {
+            id: 1,
+            project: "PAQ123".to_string(),
+            run: BasecallRun {
+                name: "hac".to_string(),
+                output_path: "/data/run1/calls.bam".to_string(),
+                basecaller: Basecaller {
+                    name: "Dorado".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            resume_from: None,
+            kind: JobKind::Demux {
+                kit: None,
+                output_dir: "/data/run1/demux".to_string(),
+            },
+        };
+        assert_eq!(
+            job.command_line(),
+            "dorado demux --output-dir /data/run1/demux --no-classify /data/run1/calls.bam"
+        );
+
+        job.kind = JobKind::Demux {
+            kit: Some("SQK-NBD114-24".to_string()),
+            output_dir: "/data/run1/demux".to_string(),
+        };
+        assert_eq!(
+            job.command_line(),
+            "dorado demux --output-dir /data/run1/demux --kit-name SQK-NBD114-24 /data/run1/calls.bam"
+        );
+        assert_eq!(job.name(), "hac-demux");
+        assert_eq!(job.input(), "/data/run1/calls.bam");
+    }
+
+    #[test]
+    fn test_align_command_line() {
+        let mut job = Job {
+            id: 1,
+            project: "PAQ123".to_string(),
+            run: BasecallRun {
+                name: "hac".to_string(),
+                output_path: "/data/run1/calls.bam".to_string(),
+                basecaller: Basecaller {
+                    name: "Dorado".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            resume_from: None,
+            kind: JobKind::Align {
+                reference: "/refs/hg38.fa".to_string(),
+                output_path: "/data/run1/calls.hg38.aligned.bam".to_string(),
+                minimap2: None,
+            },
+        };
+        assert_eq!(
+            job.command_line(),
+            "dorado aligner /refs/hg38.fa /data/run1/calls.bam > /data/run1/calls.hg38.aligned.bam"
+        );
+        assert!(job.runs_dorado());
+
+        job.kind = JobKind::Align {
+            reference: "/refs/hg38.fa".to_string(),
+            output_path: "/data/run1/calls.hg38.aligned.bam".to_string(),
+            minimap2: Some(Minimap2 {
+                minimap2: "minimap2".to_string(),
+                samtools: "samtools".to_string(),
+                preset: "map-ont".to_string(),
+            }),
+        };
+        assert_eq!(
+            job.command_line(),
+            "bash -c 'set -o pipefail; samtools fastq -T '\\''*'\\'' \"$1\" | minimap2 -y -ax map-ont \"$2\" - | samtools sort -' \
+             bash /data/run1/calls.bam /refs/hg38.fa > /data/run1/calls.hg38.aligned.bam"
+        );
+        assert!(!job.runs_dorado());
+        assert_eq!(job.name(), "hac-align");
+    }
+}
@@ -0,0 +1,109 @@
+use std::{fs::File, path::Path, process::Stdio};
+
+use color_eyre::Result;
+use tokio::{process::Command, sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use super::{check_status, report, run_streaming, Executor, Job};
+use crate::action::Action;
+
+/// Runs basecalls as child processes on this machine
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalExecutor;
+
+impl Executor for LocalExecutor {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn spawn(&self, job: Job, tx: UnboundedSender<Action>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let result = run(&job, &tx).await;
+            report(job.id, result, &tx);
+        })
+    }
+}
+
+async fn run(job: &Job, tx: &UnboundedSender<Action>) -> Result<()> {
+    let argv = job.command();
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    match job.stdout() {
+        Some(stdout) => {
+            let stdout = Path::new(stdout);
+            if let Some(parent) = stdout.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            command.stdout(Stdio::from(File::create(stdout)?));
+        }
+        None => {
+            std::fs::create_dir_all(job.output())?;
+            command.stdout(Stdio::null());
+        }
+    }
+    check_status(&argv[0], run_streaming(job.id, command, tx).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::{BasecallRun, BasecallStatus, Basecaller};
+    use crate::executor::JobKind;
+
+    #[tokio::test]
+    async fn test_local_job_streams_progress() {
+        let root = std::env::temp_dir().join(format!("fishtank-local-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let stub = root.join("dorado");
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\nprintf 'Basecalling 10%%\\rBasecalling 90%%\\n' >&2\necho \"$2 $3\"\n",
+        )
+        .unwrap();
+        std::process::Command::new("chmod")
+            .arg("+x")
+            .arg(&stub)
+            .status()
+            .unwrap();
+
+        let job = Job {
+            id: 7,
+            project: "PAQ123".to_string(),
+            run: BasecallRun {
+                name: "hac".to_string(),
+                path: "pod5_pass".to_string(),
+                output_path: root.join("out/calls.bam").display().to_string(),
+                basecaller: Basecaller {
+                    path: stub.display().to_string(),
+                    model: "hac".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            resume_from: None,
+            kind: JobKind::Basecall,
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        LocalExecutor.spawn(job.clone(), tx).await.unwrap();
+
+        let mut actions = Vec::new();
+        while let Ok(action) = rx.try_recv() {
+            actions.push(action);
+        }
+        assert!(matches!(actions.remove(0), Action::JobProcess(7, _)));
+        assert_eq!(
+            actions,
+            vec![
+                Action::JobProgress(7, "Basecalling 10%".to_string()),
+                Action::JobProgress(7, "Basecalling 90%".to_string()),
+                Action::JobStatus(7, BasecallStatus::Success),
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&job.run.output_path).unwrap(),
+            "hac pod5_pass\n"
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,536 @@
+use std::{io::SeekFrom, path::Path, time::Duration};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+    task::JoinHandle,
+};
+use tracing::warn;
+
+use super::{report, shell_quote, Executor, Job, JobKind, ProgressLines};
+use crate::{
+    action::Action,
+    data::{project::BasecallStatus, sanitize},
+};
+
+/// Settings for submitting basecalls to a Slurm cluster
+#[derive(Clone, Debug, Deserialize)]
+pub struct SlurmConfig {
+    #[serde(default)]
+    pub partition: Option<String>,
+    #[serde(default)]
+    pub account: Option<String>,
+    /// GPU request passed to `--gres=gpu:<gpus>` for basecalls, e.g. `1` or `a100:2`
+    #[serde(default = "default_gpus")]
+    pub gpus: String,
+    /// Wall-clock limit in any format sbatch accepts, e.g. `24:00:00`
+    #[serde(default)]
+    pub time_limit: Option<String>,
+    #[serde(default)]
+    pub cpus: Option<u32>,
+    /// Memory request, e.g. `64G`
+    #[serde(default)]
+    pub memory: Option<String>,
+    /// Environment modules loaded before the basecaller runs
+    #[serde(default)]
+    pub modules: Vec<String>,
+    /// Additional `#SBATCH` directives, without the prefix
+    #[serde(default)]
+    pub directives: Vec<String>,
+    /// Seconds between `squeue` polls
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+    /// Paths to the Slurm commands, if they aren't on PATH
+    #[serde(default)]
+    pub sbatch: Option<String>,
+    #[serde(default)]
+    pub squeue: Option<String>,
+    #[serde(default)]
+    pub sacct: Option<String>,
+    #[serde(default)]
+    pub scancel: Option<String>,
+}
+
+/// Consecutive failed polls tolerated before the job is cancelled, as squeue and sacct
+/// briefly fail or forget jobs while the controller is busy or restarting
+const MAX_POLL_FAILURES: u32 = 5;
+
+fn default_gpus() -> String {
+    "1".to_string()
+}
+
+fn default_poll_interval() -> u64 {
+    30
+}
+
+/// The configured path to a Slurm command, or its bare name
+fn command(path: &Option<String>, name: &str) -> Command {
+    Command::new(path.as_deref().unwrap_or(name))
+}
+
+/// Submits basecalls with `sbatch` and follows them with `squeue` and `sacct`
+///
+/// The batch script and the job's log are written next to the output BAM, so the output
+/// directory must be on a filesystem shared with the compute nodes.
+#[derive(Clone, Debug)]
+pub struct SlurmExecutor {
+    config: SlurmConfig,
+}
+
+impl SlurmExecutor {
+    pub fn new(config: SlurmConfig) -> Self {
+        Self { config }
+    }
+
+    /// Render the batch script for a job
+    pub fn script(&self, job: &Job) -> String {
+        let log = log_path(job);
+        let mut lines = vec![
+            "#!/bin/bash".to_string(),
+            format!(
+                "#SBATCH --job-name=fishtank-{}-{}",
+                sanitize(&job.project),
+                sanitize(&job.name())
+            ),
+        ];
+        // Conversion, demultiplexing and alignment run on CPUs, so only basecalls wait for a GPU
+        if job.kind == JobKind::Basecall {
+            lines.push(format!("#SBATCH --gres=gpu:{}", self.config.gpus));
+        }
+        if let Some(partition) = &self.config.partition {
+            lines.push(format!("#SBATCH --partition={partition}"));
+        }
+        if let Some(account) = &self.config.account {
+            lines.push(format!("#SBATCH --account={account}"));
+        }
+        if let Some(time_limit) = &self.config.time_limit {
+            lines.push(format!("#SBATCH --time={time_limit}"));
+        }
+        if let Some(cpus) = self.config.cpus {
+            lines.push(format!("#SBATCH --cpus-per-task={cpus}"));
+        }
+        if let Some(memory) = &self.config.memory {
+            lines.push(format!("#SBATCH --mem={memory}"));
+        }
+        lines.push(format!("#SBATCH --output={}", shell_quote(&log)));
+        lines.extend(
+            self.config
+                .directives
+                .iter()
+                .map(|directive| format!("#SBATCH {directive}")),
+        );
+
+        lines.push(String::new());
+        lines.push("set -euo pipefail".to_string());
+        lines.extend(
+            self.config
+                .modules
+                .iter()
+                .map(|module| format!("module load {}", shell_quote(module))),
+        );
+        lines.push(job.command_line());
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    async fn submit(&self, job: &Job) -> Result<String> {
+        let script_path = format!("{}.sbatch", job.output().trim_end_matches('/'));
+        if let Some(parent) = Path::new(&script_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&script_path, self.script(job))?;
+
+        let output = command(&self.config.sbatch, "sbatch")
+            .arg("--parsable")
+            .arg(&script_path)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(eyre!(
+                "sbatch failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        // --parsable prints `jobid[;cluster]`
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let id = stdout.trim().split(';').next().unwrap_or_default();
+        if id.is_empty() {
+            return Err(eyre!("sbatch did not return a job id"));
+        }
+        Ok(id.to_string())
+    }
+
+    /// Current Slurm state of the job, from `squeue` while queued and `sacct` afterwards
+    async fn state(&self, slurm_id: &str) -> Result<String> {
+        let output = command(&self.config.squeue, "squeue")
+            .args(["-h", "-j", slurm_id, "-o", "%T"])
+            .output()
+            .await?;
+        let state = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !state.is_empty() {
+            return Ok(state);
+        }
+
+        let output = command(&self.config.sacct, "sacct")
+            .args(["-n", "-X", "-P", "-j", slurm_id, "-o", "State"])
+            .output()
+            .await?;
+        // e.g. `CANCELLED by 1000`
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .map(str::to_string)
+            .ok_or_else(|| eyre!("Slurm job {slurm_id} is not known to squeue or sacct"))
+    }
+
+    /// Cancel the job so it doesn't keep running unwatched once fishtank gives up on it
+    async fn cancel(&self, slurm_id: &str) {
+        let cancelled = command(&self.config.scancel, "scancel")
+            .arg(slurm_id)
+            .status()
+            .await;
+        if !cancelled.is_ok_and(|status| status.success()) {
+            warn!("Unable to cancel Slurm job {slurm_id}");
+        }
+    }
+
+    async fn run(&self, job: &Job, tx: &UnboundedSender<Action>) -> Result<()> {
+        let slurm_id = self.submit(job).await?;
+        let _ = tx.send(Action::JobProgress(
+            job.id,
+            format!("Submitted Slurm job {slurm_id}"),
+        ));
+
+        let mut log = LogTail::new(log_path(job));
+        let mut last_state = String::new();
+        let mut failures = 0;
+        loop {
+            let state = match self.state(&slurm_id).await {
+                Ok(state) => state,
+                Err(e) => {
+                    failures += 1;
+                    if failures >= MAX_POLL_FAILURES {
+                        self.cancel(&slurm_id).await;
+                        return Err(eyre!(
+                            "Unable to poll Slurm job {slurm_id}, so it was cancelled: {e}"
+                        ));
+                    }
+                    let _ = tx.send(Action::JobProgress(job.id, e.to_string()));
+                    tokio::time::sleep(Duration::from_secs(self.config.poll_interval)).await;
+                    continue;
+                }
+            };
+            failures = 0;
+            log.forward(job.id, tx).await;
+            let status = basecall_status(&state);
+            if status == BasecallStatus::Failed {
+                return Err(eyre!("Slurm job {slurm_id} {state}"));
+            }
+            if state != last_state {
+                let _ = tx.send(Action::JobProgress(
+                    job.id,
+                    format!("Slurm job {slurm_id} {state}"),
+                ));
+                last_state = state.clone();
+            }
+            if status == BasecallStatus::Success {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(self.config.poll_interval)).await;
+        }
+    }
+}
+
+impl Executor for SlurmExecutor {
+    fn name(&self) -> &str {
+        "slurm"
+    }
+
+    fn spawn(&self, job: Job, tx: UnboundedSender<Action>) -> JoinHandle<()> {
+        let executor = self.clone();
+        tokio::spawn(async move {
+            let result = executor.run(&job, &tx).await;
+            report(job.id, result, &tx);
+        })
+    }
+}
+
+fn log_path(job: &Job) -> String {
+    format!("{}.slurm.log", job.output().trim_end_matches('/'))
+}
+
+/// Map a Slurm job state onto the basecall status
+pub fn basecall_status(state: &str) -> BasecallStatus {
+    match state {
+        "PENDING" | "CONFIGURING" | "RUNNING" | "COMPLETING" | "SUSPENDED" | "REQUEUED"
+        | "REQUEUE_HOLD" | "REQUEUE_FED" | "RESIZING" | "STAGE_OUT" | "SIGNALING" => {
+            BasecallStatus::InProgress
+        }
+        "COMPLETED" => BasecallStatus::Success,
+        _ => BasecallStatus::Failed,
+    }
+}
+
+/// Forwards lines appended to the job's log since the last poll
+struct LogTail {
+    path: String,
+    offset: u64,
+}
+
+impl LogTail {
+    fn new(path: String) -> Self {
+        Self { path, offset: 0 }
+    }
+
+    async fn forward(&mut self, job_id: usize, tx: &UnboundedSender<Action>) {
+        // The log only appears once the job starts running
+        let Ok(mut file) = tokio::fs::File::open(&self.path).await else {
+            return;
+        };
+        if file.seek(SeekFrom::Start(self.offset)).await.is_err() {
+            return;
+        }
+        let mut bytes = Vec::new();
+        if let Ok(n) = file.read_to_end(&mut bytes).await {
+            self.offset += n as u64;
+            let mut lines = ProgressLines::new(job_id, tx);
+            lines.push(&bytes);
+            lines.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::project::{BasecallRun, Basecaller};
+
+    fn config() -> SlurmConfig {
+        SlurmConfig {
+            partition: Some("gpu".to_string()),
+            account: None,
+            gpus: "a100:1".to_string(),
+            time_limit: Some("12:00:00".to_string()),
+            cpus: Some(8),
+            memory: Some("64G".to_string()),
+            modules: vec!["dorado/0.8.1".to_string()],
+            directives: vec!["--qos=long".to_string()],
+            poll_interval: 0,
+            sbatch: None,
+            squeue: None,
+            sacct: None,
+            scancel: None,
+        }
+    }
+
+    /// Write stub Slurm commands into `bin` and point the config at them
+    fn stub_config(bin: &Path, stubs: &[(&str, String)]) -> SlurmConfig {
+        std::fs::create_dir_all(bin).unwrap();
+        for (name, body) in stubs {
+            std::fs::write(bin.join(name), body).unwrap();
+            std::process::Command::new("chmod")
+                .arg("+x")
+                .arg(bin.join(name))
+                .status()
+                .unwrap();
+        }
+        let stub = |name: &str| Some(bin.join(name).display().to_string());
+        SlurmConfig {
+            sbatch: stub("sbatch"),
+            squeue: stub("squeue"),
+            sacct: stub("sacct"),
+            scancel: stub("scancel"),
+            ..config()
+        }
+    }
+
+    async fn spawn(config: SlurmConfig, output_path: &Path) -> Vec<Action> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let job = job(&output_path.display().to_string());
+        SlurmExecutor::new(config).spawn(job, tx).await.unwrap();
+
+        let mut actions = Vec::new();
+        while let Ok(action) = rx.try_recv() {
+            actions.push(action);
+        }
+        actions
+    }
+
+    fn job(output_path: &str) -> Job {
+        Job {
+            id: 3,
+            project: "PAQ123".to_string(),
+            run: BasecallRun {
+                name: "sup".to_string(),
+                path: "/data/pod5_pass".to_string(),
+                output_path: output_path.to_string(),
+                basecaller: Basecaller {
+                    name: "Dorado".to_string(),
+                    model: "sup".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            resume_from: None,
+            kind: JobKind::Basecall,
+        }
+    }
+
+    #[test]
+    fn test_script() {
+        let script = SlurmExecutor::new(config()).script(&job("/data/calls.bam"));
+        assert_eq!(
+            script,
+            "#!/bin/bash
+#SBATCH --job-name=fishtank-PAQ123-sup
+#SBATCH --gres=gpu:a100:1
+#SBATCH --partition=gpu
+#SBATCH --time=12:00:00
+#SBATCH --cpus-per-task=8
+#SBATCH --mem=64G
+#SBATCH --output=/data/calls.bam.slurm.log
+#SBATCH --qos=long
+
+set -euo pipefail
+module load dorado/0.8.1
+dorado basecaller sup /data/pod5_pass > /data/calls.bam
+"
+        );
+    }
+
+    #[test]
+    fn test_cpu_job_script() {
+        let job = Job {
+            kind: JobKind::Convert {
+                output_dir: "/data/pod5_pass".to_string(),
+                remove_originals: false,
+            },
+            ..job("/data/calls.bam")
+        };
+        let script = SlurmExecutor::new(config()).script(&job);
+        assert_eq!(
+            script,
+            format!(
+                "#!/bin/bash
+#SBATCH --job-name=fishtank-PAQ123-sup-pod5
+#SBATCH --partition=gpu
+#SBATCH --time=12:00:00
+#SBATCH --cpus-per-task=8
+#SBATCH --mem=64G
+#SBATCH --output=/data/pod5_pass.slurm.log
+#SBATCH --qos=long
+
+set -euo pipefail
+module load dorado/0.8.1
+{}
+",
+                job.command_line()
+            )
+        );
+    }
+
+    #[test]
+    fn test_basecall_status() {
+        assert_eq!(basecall_status("PENDING"), BasecallStatus::InProgress);
+        assert_eq!(basecall_status("RUNNING"), BasecallStatus::InProgress);
+        assert_eq!(basecall_status("COMPLETED"), BasecallStatus::Success);
+        assert_eq!(basecall_status("TIMEOUT"), BasecallStatus::Failed);
+        assert_eq!(basecall_status("OUT_OF_MEMORY"), BasecallStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_poll() {
+        let root = std::env::temp_dir().join(format!("fishtank-slurm-{}", std::process::id()));
+        let output_path = root.join("calls.bam");
+        let config = stub_config(
+            &root.join("bin"),
+            &[
+                (
+                    "sbatch",
+                    format!(
+                        "#!/bin/sh\necho 'Basecalling 42%' > {}.slurm.log\necho '4242;cluster'\n",
+                        output_path.display()
+                    ),
+                ),
+                // Running on the first poll, then gone from the queue
+                (
+                    "squeue",
+                    format!(
+                        "#!/bin/sh\nif [ ! -e {0}/polled ]; then touch {0}/polled; echo RUNNING; fi\n",
+                        root.display()
+                    ),
+                ),
+                // Not yet accounted for on the first lookup
+                (
+                    "sacct",
+                    format!(
+                        "#!/bin/sh\nif [ -e {0}/accounted ]; then echo COMPLETED; else touch {0}/accounted; fi\n",
+                        root.display()
+                    ),
+                ),
+            ],
+        );
+
+        assert_eq!(
+            spawn(config, &output_path).await,
+            vec![
+                Action::JobProgress(3, "Submitted Slurm job 4242".to_string()),
+                Action::JobProgress(3, "Basecalling 42%".to_string()),
+                Action::JobProgress(3, "Slurm job 4242 RUNNING".to_string()),
+                Action::JobProgress(
+                    3,
+                    "Slurm job 4242 is not known to squeue or sacct".to_string()
+                ),
+                Action::JobProgress(3, "Slurm job 4242 COMPLETED".to_string()),
+                Action::JobStatus(3, BasecallStatus::Success),
+            ]
+        );
+        assert!(std::fs::read_to_string(root.join("calls.bam.sbatch"))
+            .unwrap()
+            .contains("module load dorado/0.8.1"));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_failed_polls() {
+        let root =
+            std::env::temp_dir().join(format!("fishtank-slurm-cancel-{}", std::process::id()));
+        let output_path = root.join("calls.bam");
+        let config = stub_config(
+            &root.join("bin"),
+            &[
+                ("sbatch", "#!/bin/sh\necho 4242\n".to_string()),
+                ("squeue", "#!/bin/sh\nexit 1\n".to_string()),
+                ("sacct", "#!/bin/sh\nexit 1\n".to_string()),
+                (
+                    "scancel",
+                    format!("#!/bin/sh\necho \"$1\" > {}/cancelled\n", root.display()),
+                ),
+            ],
+        );
+
+        let actions = spawn(config, &output_path).await;
+        assert_eq!(
+            actions[actions.len() - 2..],
+            [
+                Action::JobProgress(
+                    3,
+                    "Unable to poll Slurm job 4242, so it was cancelled: \
+                     Slurm job 4242 is not known to squeue or sacct"
+                        .to_string()
+                ),
+                Action::JobStatus(3, BasecallStatus::Failed),
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("cancelled")).unwrap(),
+            "4242\n"
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
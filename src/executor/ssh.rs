@@ -0,0 +1,606 @@
+use std::{path::Path, process::Stdio};
+
+use color_eyre::Result;
+use serde::Deserialize;
+use tokio::{process::Command, sync::mpsc::UnboundedSender, task::JoinHandle};
+use tracing::warn;
+
+use super::{check_status, report, run_streaming, shell_quote, Executor, Job, JobKind};
+use crate::{action::Action, data::sanitize};
+
+/// File in the job directory holding the remote job's pid
+const PID_FILE: &str = "job.pid";
+
+/// Settings for running basecalls on a remote GPU host
+#[derive(Clone, Debug, Deserialize)]
+pub struct SshConfig {
+    /// Anything `ssh` accepts as a destination, e.g. `user@gpu01` or a `~/.ssh/config` alias
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// Extra `-o` options, e.g. `StrictHostKeyChecking=accept-new`
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Remote directory jobs are staged in
+    pub work_dir: String,
+    /// Basecaller executable on the remote host, if it differs from the local path
+    #[serde(default)]
+    pub basecaller: Option<String>,
+    /// `pod5`, `minimap2` and `samtools` on the remote host, if they differ from the local paths
+    #[serde(default)]
+    pub pod5: Option<String>,
+    #[serde(default)]
+    pub minimap2: Option<String>,
+    #[serde(default)]
+    pub samtools: Option<String>,
+    #[serde(default)]
+    pub sync: SyncTool,
+    /// Remove the remote staging directory once the output has been copied back
+    #[serde(default)]
+    pub cleanup: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncTool {
+    #[default]
+    Rsync,
+    Scp,
+}
+
+/// Runs basecalls on a remote host through the system `ssh` binary
+///
+/// Inputs are copied into a per-job staging directory, the basecaller runs there with its
+/// stderr streamed back as progress, and the output BAM is copied back to `output_path`.
+/// References are kept in a shared directory, so rsync only uploads them once. A job that's
+/// cancelled or abandoned mid-run is killed on the remote host as well.
+#[derive(Clone, Debug)]
+pub struct SshExecutor {
+    config: SshConfig,
+}
+
+impl SshExecutor {
+    pub fn new(config: SshConfig) -> Self {
+        Self { config }
+    }
+
+    /// Options shared by every ssh invocation
+    fn ssh_options(&self) -> Vec<String> {
+        let mut args = vec!["-o".to_string(), "BatchMode=yes".to_string()];
+        if let Some(port) = self.config.port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if let Some(identity) = &self.config.identity_file {
+            args.extend(["-i".to_string(), identity.clone()]);
+        }
+        for option in &self.config.options {
+            args.extend(["-o".to_string(), option.clone()]);
+        }
+        args
+    }
+
+    fn ssh(&self, remote_command: &str) -> Command {
+        Command::from(self.ssh_std(remote_command))
+    }
+
+    /// A blocking ssh command, for use where an async one can't be awaited
+    fn ssh_std(&self, remote_command: &str) -> std::process::Command {
+        let mut command = std::process::Command::new("ssh");
+        command
+            .args(self.ssh_options())
+            .arg(&self.config.host)
+            .arg(remote_command);
+        command
+    }
+
+    /// Staging directory for a job on the remote host
+    fn job_dir(&self, job: &Job) -> String {
+        format!(
+            "{}/{}-{}",
+            self.config.work_dir.trim_end_matches('/'),
+            sanitize(&job.project),
+            sanitize(&job.name())
+        )
+    }
+
+    /// Directory on the remote host alignment references are uploaded to
+    fn references_dir(&self) -> String {
+        format!("{}/references", self.config.work_dir.trim_end_matches('/'))
+    }
+
+    /// The job with its reference and tools swapped for their remote paths
+    fn remote_job(&self, job: &Job) -> Job {
+        let mut job = job.clone();
+        match &mut job.kind {
+            JobKind::Align {
+                reference,
+                minimap2,
+                ..
+            } => {
+                *reference = format!("{}/{}", self.references_dir(), file_name(reference));
+                if let Some(tools) = minimap2 {
+                    if let Some(path) = &self.config.minimap2 {
+                        tools.minimap2 = path.clone();
+                    }
+                    if let Some(path) = &self.config.samtools {
+                        tools.samtools = path.clone();
+                    }
+                }
+            }
+            JobKind::Convert { .. } => {
+                if let Some(path) = &self.config.pod5 {
+                    job.run.basecaller.path = path.clone();
+                }
+            }
+            JobKind::Basecall | JobKind::Demux { .. } => {}
+        }
+        job
+    }
+
+    fn remote(&self, path: &str) -> String {
+        format!("{}:{}", self.config.host, path)
+    }
+
+    /// Copy `from` to `to`, either of which may be a `host:path` remote
+    fn copy(&self, from: &str, to: &str) -> Command {
+        match self.config.sync {
+            SyncTool::Rsync => {
+                let mut command = Command::new("rsync");
+                let ssh = std::iter::once("ssh".to_string())
+                    .chain(self.ssh_options())
+                    .map(|arg| shell_quote(&arg))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                command.args(["-a", "--partial", "-e", &ssh, from, to]);
+                command
+            }
+            SyncTool::Scp => {
+                let mut command = Command::new("scp");
+                command.args(["-r", "-o", "BatchMode=yes"]);
+                if let Some(port) = self.config.port {
+                    command.args(["-P", &port.to_string()]);
+                }
+                if let Some(identity) = &self.config.identity_file {
+                    command.args(["-i", identity]);
+                }
+                for option in &self.config.options {
+                    command.args(["-o", option]);
+                }
+                command.args([from, to]);
+                command
+            }
+        }
+    }
+
+    /// The job's command as run from inside the job directory
+    fn remote_command(&self, job: &Job) -> String {
+        let mut argv = self.remote_job(job).command();
+        if let Some(basecaller) = self
+            .config
+            .basecaller
+            .as_ref()
+            .filter(|_| job.runs_dorado())
+        {
+            argv[0] = basecaller.clone();
+        }
+        // The input is staged under input/ with its original file or directory name, and
+        // output directories are written next to it under their own name
+        for arg in argv.iter_mut().skip(1) {
+            if arg == job.input() {
+                *arg = format!("input/{}", file_name(job.input()));
+            } else if job.resume_from.as_ref() == Some(arg) {
+                *arg = "resume.bam".to_string();
+            } else if job.stdout().is_none() && arg == job.output() {
+                *arg = file_name(job.output());
+            }
+        }
+
+        let argv = argv
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let redirect = if job.stdout().is_some() {
+            " > output.bam"
+        } else {
+            ""
+        };
+        // sshd starts the remote shell in its own session, so the pid it records is also the
+        // process group that `kill_command` signals, and exec keeps that pid for the job
+        format!(
+            "cd {} && echo $$ > {PID_FILE} && exec {argv}{redirect}",
+            shell_quote(&self.job_dir(job))
+        )
+    }
+
+    /// Remote command that stops the job and everything it started, such as a pipeline
+    fn kill_command(&self, job: &Job) -> String {
+        format!(
+            "cd {} && kill -TERM -\"$(cat {PID_FILE})\"",
+            shell_quote(&self.job_dir(job))
+        )
+    }
+
+    async fn run(&self, job: &Job, tx: &UnboundedSender<Action>) -> Result<()> {
+        let job_dir = self.job_dir(job);
+        let progress = |line: String| {
+            let _ = tx.send(Action::JobProgress(job.id, line));
+        };
+
+        progress(format!("Staging on {}:{job_dir}", self.config.host));
+        let mkdir = format!("mkdir -p {}", shell_quote(&format!("{job_dir}/input")));
+        check_status("ssh", run_streaming(job.id, self.ssh(&mkdir), tx).await?)?;
+
+        progress(format!("Uploading {}", job.input()));
+        let upload = self.copy(
+            job.input().trim_end_matches('/'),
+            &self.remote(&format!("{job_dir}/input/")),
+        );
+        check_status("upload", run_streaming(job.id, upload, tx).await?)?;
+
+        if let JobKind::Align { reference, .. } = &job.kind {
+            let references = self.references_dir();
+            let mkdir = format!("mkdir -p {}", shell_quote(&references));
+            check_status("ssh", run_streaming(job.id, self.ssh(&mkdir), tx).await?)?;
+            progress(format!("Uploading {reference}"));
+            let upload = self.copy(reference, &self.remote(&format!("{references}/")));
+            check_status("upload", run_streaming(job.id, upload, tx).await?)?;
+        }
+
+        if let Some(partial) = &job.resume_from {
+            progress(format!("Uploading {partial}"));
+            let upload = self.copy(partial, &self.remote(&format!("{job_dir}/resume.bam")));
+            check_status("upload", run_streaming(job.id, upload, tx).await?)?;
+        }
+
+        // Dropping the job only kills the local ssh, so the remote job is killed separately
+        let mut remote = RemoteJob {
+            kill: Some(self.ssh_std(&self.kill_command(job))),
+        };
+        let command = self.ssh(&self.remote_command(job));
+        let status = run_streaming(job.id, command, tx).await?;
+        // ssh exits with 255 when the connection fails, which can leave the job running
+        if status.code() != Some(255) {
+            remote.kill = None;
+        }
+        check_status("ssh", status)?;
+
+        let output = job.output().trim_end_matches('/');
+        progress(format!("Downloading {output}"));
+        let parent = Path::new(output)
+            .parent()
+            .map(|parent| parent.display().to_string())
+            .unwrap_or_default();
+        if !parent.is_empty() {
+            std::fs::create_dir_all(&parent)?;
+        }
+        // Output directories are copied into the parent so they keep their name
+        let download = match job.stdout() {
+            Some(stdout) => self.copy(&self.remote(&format!("{job_dir}/output.bam")), stdout),
+            None => self.copy(
+                &self.remote(&format!("{job_dir}/{}", file_name(output))),
+                &format!("{parent}/"),
+            ),
+        };
+        check_status("download", run_streaming(job.id, download, tx).await?)?;
+
+        if self.config.cleanup {
+            let rm = format!("rm -rf {}", shell_quote(&job_dir));
+            check_status("ssh", run_streaming(job.id, self.ssh(&rm), tx).await?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Kills the remote job when dropped while it may still be running, as happens when the job's
+/// task is aborted or the app quits mid-job
+struct RemoteJob {
+    kill: Option<std::process::Command>,
+}
+
+impl Drop for RemoteJob {
+    fn drop(&mut self) {
+        let Some(mut kill) = self.kill.take() else {
+            return;
+        };
+        kill.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // Started here so it outlives the app, and waited on elsewhere so it's reaped
+        match kill.spawn() {
+            Ok(mut child) => {
+                std::thread::spawn(move || child.wait());
+            }
+            Err(e) => warn!("Unable to kill the remote job: {e}"),
+        }
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+impl Executor for SshExecutor {
+    fn name(&self) -> &str {
+        &self.config.host
+    }
+
+    fn spawn(&self, job: Job, tx: UnboundedSender<Action>) -> JoinHandle<()> {
+        let executor = self.clone();
+        tokio::spawn(async move {
+            let result = executor.run(&job, &tx).await;
+            report(job.id, result, &tx);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::data::{
+        alignment::Minimap2,
+        project::{BasecallRun, BasecallStatus, Basecaller},
+    };
+    use crate::executor::JobKind;
+
+    fn config() -> SshConfig {
+        SshConfig {
+            host: "gpu01".to_string(),
+            port: Some(2222),
+            identity_file: None,
+            options: vec!["StrictHostKeyChecking=accept-new".to_string()],
+            work_dir: "/scratch/fishtank/".to_string(),
+            basecaller: Some("/opt/dorado/bin/dorado".to_string()),
+            pod5: None,
+            minimap2: Some("/opt/minimap2/minimap2".to_string()),
+            samtools: None,
+            sync: SyncTool::Rsync,
+            cleanup: false,
+        }
+    }
+
+    fn job() -> Job {
+        Job {
+            id: 1,
+            project: "Mouse liver".to_string(),
+            run: BasecallRun {
+                name: "sup".to_string(),
+                path: "/data/run1/pod5_pass".to_string(),
+                output_path: "/data/run1/calls.bam".to_string(),
+                basecaller: Basecaller {
+                    name: "Dorado".to_string(),
+                    model: "sup".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            resume_from: None,
+            kind: JobKind::Basecall,
+        }
+    }
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("pod5_pass/"), "pod5_pass/");
+        assert_eq!(shell_quote("my run"), "'my run'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_remote_command() {
+        let executor = SshExecutor::new(config());
+        assert_eq!(
+            executor.remote_command(&job()),
+            "cd /scratch/fishtank/Mouse_liver-sup && echo $$ > job.pid && exec /opt/dorado/bin/dorado basecaller sup input/pod5_pass > output.bam"
+        );
+    }
+
+    #[test]
+    fn test_kill_command() {
+        let executor = SshExecutor::new(config());
+        assert_eq!(
+            executor.kill_command(&job()),
+            "cd /scratch/fishtank/Mouse_liver-sup && kill -TERM -\"$(cat job.pid)\""
+        );
+    }
+
+    #[test]
+    fn test_remote_resume_command() {
+        let executor = SshExecutor::new(config());
+        let mut job = job();
+        job.resume_from = Some("/data/run1/calls.partial-1.bam".to_string());
+        assert_eq!(
+            executor.remote_command(&job),
+            "cd /scratch/fishtank/Mouse_liver-sup && echo $$ > job.pid && exec /opt/dorado/bin/dorado basecaller sup input/pod5_pass --resume-from resume.bam > output.bam"
+        );
+    }
+
+    #[test]
+    fn test_remote_demux_command() {
+        let executor = SshExecutor::new(config());
+        let mut job = job();
+        job.kind = JobKind::Demux {
+            kit: None,
+            output_dir: "/data/run1/demux".to_string(),
+        };
+        assert_eq!(
+            executor.remote_command(&job),
+            "cd /scratch/fishtank/Mouse_liver-sup-demux && echo $$ > job.pid && exec /opt/dorado/bin/dorado demux --output-dir demux --no-classify input/calls.bam"
+        );
+    }
+
+    #[test]
+    fn test_remote_convert_command() {
+        let executor = SshExecutor::new(config());
+        let mut job = job();
+        job.run.path = "/data/run1/fast5_pass".to_string();
+        job.run.basecaller = Basecaller {
+            name: "pod5".to_string(),
+            ..Default::default()
+        };
+        job.kind = JobKind::Convert {
+            output_dir: "/data/run1/pod5_pass".to_string(),
+            remove_originals: false,
+        };
+        assert_eq!(
+            executor.remote_command(&job),
+            "cd /scratch/fishtank/Mouse_liver-sup-pod5 && echo $$ > job.pid && exec pod5 convert fast5 input/fast5_pass --recursive --output pod5_pass --one-to-one input/fast5_pass"
+        );
+    }
+
+    #[test]
+    fn test_remote_align_command() {
+        let executor = SshExecutor::new(config());
+        let mut job = job();
+        job.kind = JobKind::Align {
+            reference: "/refs/GRCm39.mmi".to_string(),
+            output_path: "/data/run1/calls.GRCm39.bam".to_string(),
+            minimap2: None,
+        };
+        assert_eq!(
+            executor.remote_command(&job),
+            "cd /scratch/fishtank/Mouse_liver-sup-align && echo $$ > job.pid && exec /opt/dorado/bin/dorado aligner /scratch/fishtank/references/GRCm39.mmi input/calls.bam > output.bam"
+        );
+
+        // The local tools are swapped for the remote ones configured
+        job.kind = JobKind::Align {
+            reference: "/refs/GRCm39.mmi".to_string(),
+            output_path: "/data/run1/calls.GRCm39.bam".to_string(),
+            minimap2: Some(Minimap2 {
+                minimap2: "/usr/local/bin/minimap2".to_string(),
+                samtools: "samtools".to_string(),
+                preset: "map-ont".to_string(),
+            }),
+        };
+        assert_eq!(
+            executor.remote_command(&job),
+            "cd /scratch/fishtank/Mouse_liver-sup-align && echo $$ > job.pid && exec bash -c 'set -o pipefail; samtools fastq -T '\\''*'\\'' \"$1\" | /opt/minimap2/minimap2 -y -ax map-ont \"$2\" - | samtools sort -' bash input/calls.bam /scratch/fishtank/references/GRCm39.mmi > output.bam"
+        );
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let executor = SshExecutor::new(config());
+        assert_eq!(
+            args(&executor.ssh("true")),
+            vec![
+                "-o",
+                "BatchMode=yes",
+                "-p",
+                "2222",
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "gpu01",
+                "true"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copy_args() {
+        let mut config = config();
+        let executor = SshExecutor::new(config.clone());
+        assert_eq!(
+            args(&executor.copy("/data/in", "gpu01:/scratch/in/")),
+            vec![
+                "-a",
+                "--partial",
+                "-e",
+                "ssh -o BatchMode=yes -p 2222 -o StrictHostKeyChecking=accept-new",
+                "/data/in",
+                "gpu01:/scratch/in/"
+            ]
+        );
+
+        config.sync = SyncTool::Scp;
+        let executor = SshExecutor::new(config);
+        assert_eq!(
+            args(&executor.copy("/data/in", "gpu01:/scratch/in/")),
+            vec![
+                "-r",
+                "-o",
+                "BatchMode=yes",
+                "-P",
+                "2222",
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "/data/in",
+                "gpu01:/scratch/in/"
+            ]
+        );
+    }
+
+    /// Runs a stub basecaller through a real sshd, e.g.
+    /// `FISHTANK_TEST_SSH_HOST=localhost cargo test -- --ignored ssh`
+    #[tokio::test]
+    #[ignore = "needs an sshd reachable with key auth, set FISHTANK_TEST_SSH_HOST"]
+    async fn test_localhost_sshd() {
+        let host = std::env::var("FISHTANK_TEST_SSH_HOST").unwrap();
+        let root = std::env::temp_dir().join(format!("fishtank-ssh-{}", std::process::id()));
+        let input = root.join("reads");
+        std::fs::create_dir_all(&input).unwrap();
+        std::fs::write(input.join("reads.pod5"), "raw").unwrap();
+        let stub = root.join("dorado");
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\necho 'Basecalling 50%' >&2\ncat \"$3\"/*\n",
+        )
+        .unwrap();
+        std::process::Command::new("chmod")
+            .arg("+x")
+            .arg(&stub)
+            .status()
+            .unwrap();
+
+        let executor = SshExecutor::new(SshConfig {
+            host,
+            port: None,
+            identity_file: None,
+            options: vec!["StrictHostKeyChecking=accept-new".to_string()],
+            work_dir: root.join("remote").display().to_string(),
+            basecaller: Some(stub.display().to_string()),
+            pod5: None,
+            minimap2: None,
+            samtools: None,
+            sync: SyncTool::Rsync,
+            cleanup: true,
+        });
+        let mut job = job();
+        job.run.path = input.display().to_string();
+        job.run.output_path = root.join("calls.bam").display().to_string();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        executor.spawn(job.clone(), tx).await.unwrap();
+
+        let mut actions = Vec::new();
+        while let Ok(action) = rx.try_recv() {
+            actions.push(action);
+        }
+        assert!(actions.contains(&Action::JobProgress(1, "Basecalling 50%".to_string())));
+        assert_eq!(
+            actions.last(),
+            Some(&Action::JobStatus(1, BasecallStatus::Success))
+        );
+        assert_eq!(
+            std::fs::read_to_string(&job.run.output_path).unwrap(),
+            "raw"
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
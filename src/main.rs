@@ -10,8 +10,12 @@ mod cli;
 mod components;
 mod config;
 mod data;
+mod disk;
 mod errors;
+mod executor;
 mod logging;
+mod notifications;
+mod resources;
 mod tui;
 
 #[tokio::main]
@@ -21,6 +25,9 @@ async fn main() -> Result<()> {
 
     let args = Cli::parse();
     let mut app = App::new(args.tick_rate, args.frame_rate)?;
+    if let Some(root) = args.import {
+        app.preview_import(root);
+    }
     app.run().await?;
     Ok(())
 }
@@ -0,0 +1,600 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{error, warn};
+
+use crate::disk;
+
+/// Notification settings, read from the `notifications` section of the config file
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub endpoints: Vec<Endpoint>,
+    #[serde(default)]
+    pub disk_space: Vec<DiskSpaceWatch>,
+    /// How often (in seconds) the watched volumes are checked
+    #[serde(default = "default_disk_check_interval")]
+    pub disk_check_interval: u64,
+}
+
+fn default_disk_check_interval() -> u64 {
+    300
+}
+
+/// A single destination notifications are POSTed to
+#[derive(Clone, Debug, Deserialize)]
+pub struct Endpoint {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub kind: EndpointKind,
+    /// Events this endpoint subscribes to, all events if empty
+    #[serde(default)]
+    pub events: Vec<NotificationKind>,
+    /// JSON payload with `{{field}}` placeholders, replaces the built-in payload for `kind`
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Topic for ntfy endpoints, `url` should then be the server root
+    #[serde(default)]
+    pub topic: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointKind {
+    Slack,
+    #[default]
+    Webhook,
+    Ntfy,
+}
+
+/// A volume to watch, notifying once free space drops below `min_free_gb`
+#[derive(Clone, Debug, Deserialize)]
+pub struct DiskSpaceWatch {
+    pub path: PathBuf,
+    pub min_free_gb: u64,
+}
+
+impl DiskSpaceWatch {
+    pub fn threshold_bytes(&self) -> u64 {
+        self.min_free_gb * 1024 * 1024 * 1024
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    BasecallFinished,
+    BasecallFailed,
+    /// Demultiplexing, conversion or alignment finished
+    JobFinished,
+    JobFailed,
+    DiskSpaceLow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Notification {
+    BasecallFinished {
+        project: String,
+        run: String,
+    },
+    BasecallFailed {
+        project: String,
+        run: String,
+        reason: String,
+    },
+    /// Any other job, `job` says which kind, e.g. `Alignment`
+    JobFinished {
+        project: String,
+        job: String,
+        run: String,
+    },
+    JobFailed {
+        project: String,
+        job: String,
+        run: String,
+        reason: String,
+    },
+    DiskSpaceLow {
+        path: String,
+        free_bytes: u64,
+        threshold_bytes: u64,
+    },
+}
+
+impl Notification {
+    pub fn kind(&self) -> NotificationKind {
+        match self {
+            Notification::BasecallFinished { .. } => NotificationKind::BasecallFinished,
+            Notification::BasecallFailed { .. } => NotificationKind::BasecallFailed,
+            Notification::JobFinished { .. } => NotificationKind::JobFinished,
+            Notification::JobFailed { .. } => NotificationKind::JobFailed,
+            Notification::DiskSpaceLow { .. } => NotificationKind::DiskSpaceLow,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Notification::BasecallFinished { project, .. } => {
+                format!("Basecall finished: {project}")
+            }
+            Notification::BasecallFailed { project, .. } => format!("Basecall failed: {project}"),
+            Notification::JobFinished { project, job, .. } => format!("{job} finished: {project}"),
+            Notification::JobFailed { project, job, .. } => format!("{job} failed: {project}"),
+            Notification::DiskSpaceLow { path, .. } => format!("Low disk space: {path}"),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Notification::BasecallFinished { project, run } => {
+                format!("Basecall run {run} of project {project} completed successfully")
+            }
+            Notification::BasecallFailed {
+                project,
+                run,
+                reason,
+            } => format!("Basecall run {run} of project {project} failed: {reason}"),
+            Notification::JobFinished { project, job, run } => {
+                format!("{job} {run} of project {project} completed successfully")
+            }
+            Notification::JobFailed {
+                project,
+                job,
+                run,
+                reason,
+            } => format!("{job} {run} of project {project} failed: {reason}"),
+            Notification::DiskSpaceLow {
+                path,
+                free_bytes,
+                threshold_bytes,
+            } => format!(
+                "{path} has {:.1} GB free, below the {:.1} GB threshold",
+                gigabytes(*free_bytes),
+                gigabytes(*threshold_bytes)
+            ),
+        }
+    }
+
+    /// Fields available to payload templates, in addition to `event`, `title` and `message`
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Notification::BasecallFinished { project, run } => {
+                vec![("project", project.clone()), ("run", run.clone())]
+            }
+            Notification::BasecallFailed {
+                project,
+                run,
+                reason,
+            } => vec![
+                ("project", project.clone()),
+                ("run", run.clone()),
+                ("reason", reason.clone()),
+            ],
+            Notification::JobFinished { project, job, run } => vec![
+                ("project", project.clone()),
+                ("job", job.clone()),
+                ("run", run.clone()),
+            ],
+            Notification::JobFailed {
+                project,
+                job,
+                run,
+                reason,
+            } => vec![
+                ("project", project.clone()),
+                ("job", job.clone()),
+                ("run", run.clone()),
+                ("reason", reason.clone()),
+            ],
+            Notification::DiskSpaceLow {
+                path,
+                free_bytes,
+                threshold_bytes,
+            } => vec![
+                ("path", path.clone()),
+                ("free_bytes", free_bytes.to_string()),
+                ("threshold_bytes", threshold_bytes.to_string()),
+            ],
+        }
+    }
+
+    fn event_name(&self) -> &'static str {
+        match self.kind() {
+            NotificationKind::BasecallFinished => "basecall_finished",
+            NotificationKind::BasecallFailed => "basecall_failed",
+            NotificationKind::JobFinished => "job_finished",
+            NotificationKind::JobFailed => "job_failed",
+            NotificationKind::DiskSpaceLow => "disk_space_low",
+        }
+    }
+
+    fn all_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("event", self.event_name().to_string()),
+            ("title", self.title()),
+            ("message", self.message()),
+        ];
+        fields.extend(self.fields());
+        fields
+    }
+}
+
+fn gigabytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+}
+
+impl Endpoint {
+    pub fn wants(&self, kind: NotificationKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    /// Build the JSON body POSTed to this endpoint
+    pub fn payload(&self, notification: &Notification) -> Result<Value> {
+        if let Some(template) = &self.template {
+            return render_template(template, notification);
+        }
+
+        let payload = match self.kind {
+            EndpointKind::Slack => json!({
+                "text": format!("*{}*\n{}", notification.title(), notification.message()),
+            }),
+            EndpointKind::Webhook => Value::Object(
+                notification
+                    .all_fields()
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), Value::String(v)))
+                    .collect(),
+            ),
+            EndpointKind::Ntfy => {
+                let (priority, tags) = match notification.kind() {
+                    NotificationKind::BasecallFinished | NotificationKind::JobFinished => {
+                        (3, vec!["white_check_mark"])
+                    }
+                    NotificationKind::BasecallFailed | NotificationKind::JobFailed => {
+                        (4, vec!["x"])
+                    }
+                    NotificationKind::DiskSpaceLow => (4, vec!["warning"]),
+                };
+                json!({
+                    "topic": self.topic.clone().unwrap_or_else(|| "fishtank".to_string()),
+                    "title": notification.title(),
+                    "message": notification.message(),
+                    "priority": priority,
+                    "tags": tags,
+                })
+            }
+        };
+        Ok(payload)
+    }
+}
+
+/// Replace `{{field}}` placeholders with JSON-escaped values and parse the result
+///
+/// The template is read once, so placeholders inside values, e.g. a dorado error line, are left
+/// as they are. Unknown placeholders are kept too.
+fn render_template(template: &str, notification: &Notification) -> Result<Value> {
+    let fields = notification.all_fields();
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let field = after
+            .find("}}")
+            .and_then(|end| fields.iter().find(|(key, _)| *key == &after[..end]));
+        match field {
+            Some((key, value)) => {
+                let escaped = serde_json::to_string(value)?;
+                rendered.push_str(&escaped[1..escaped.len() - 1]);
+                rest = &after[key.len() + 2..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    serde_json::from_str(&rendered)
+        .map_err(|e| eyre!("Notification template did not render to valid JSON: {e}"))
+}
+
+/// Sends notifications to every configured endpoint
+#[derive(Clone, Debug, Default)]
+pub struct Notifier {
+    client: reqwest::Client,
+    endpoints: Vec<Endpoint>,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            endpoints: config.endpoints.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// POST the notification to each subscribed endpoint, logging failures
+    pub async fn send(&self, notification: &Notification) {
+        for endpoint in self
+            .endpoints
+            .iter()
+            .filter(|e| e.wants(notification.kind()))
+        {
+            if let Err(e) = self.send_to(endpoint, notification).await {
+                error!("Failed to notify {}: {e}", endpoint.name);
+            }
+        }
+    }
+
+    async fn send_to(&self, endpoint: &Endpoint, notification: &Notification) -> Result<()> {
+        let payload = endpoint.payload(notification)?;
+        let response = self
+            .client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&payload)?)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(eyre!("{} returned {}", endpoint.url, response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Periodically checks watched volumes and reports when they cross below their threshold
+#[derive(Debug)]
+pub struct DiskSpaceMonitor {
+    watches: Vec<DiskSpaceWatch>,
+    interval: Duration,
+    last_check: Option<Instant>,
+    below_threshold: HashSet<PathBuf>,
+}
+
+impl DiskSpaceMonitor {
+    pub fn new(config: &NotificationConfig) -> Self {
+        Self {
+            watches: config.disk_space.clone(),
+            interval: Duration::from_secs(config.disk_check_interval),
+            last_check: None,
+            below_threshold: HashSet::new(),
+        }
+    }
+
+    /// Check the watched volumes if the check interval has elapsed
+    pub fn poll(&mut self) -> Vec<Notification> {
+        if self.watches.is_empty()
+            || self
+                .last_check
+                .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return Vec::new();
+        }
+        self.last_check = Some(Instant::now());
+
+        let mut notifications = Vec::new();
+        for watch in self.watches.clone() {
+            match disk::available_space(&watch.path) {
+                Ok(free) => notifications.extend(self.observe(&watch, free)),
+                Err(e) => warn!(
+                    "Unable to check free space on {}: {e}",
+                    watch.path.display()
+                ),
+            }
+        }
+        notifications
+    }
+
+    /// Record a free space reading, returning a notification only when the threshold is crossed
+    fn observe(&mut self, watch: &DiskSpaceWatch, free_bytes: u64) -> Option<Notification> {
+        let threshold_bytes = watch.threshold_bytes();
+        if free_bytes >= threshold_bytes {
+            self.below_threshold.remove(&watch.path);
+            return None;
+        }
+        if !self.below_threshold.insert(watch.path.clone()) {
+            return None;
+        }
+        Some(Notification::DiskSpaceLow {
+            path: watch.path.display().to_string(),
+            free_bytes,
+            threshold_bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::oneshot,
+    };
+
+    use super::*;
+
+    fn finished() -> Notification {
+        Notification::BasecallFinished {
+            project: "PAQ123".to_string(),
+            run: "sup_v5".to_string(),
+        }
+    }
+
+    fn endpoint(kind: EndpointKind, url: &str) -> Endpoint {
+        Endpoint {
+            name: "test".to_string(),
+            url: url.to_string(),
+            kind,
+            events: Vec::new(),
+            template: None,
+            topic: None,
+        }
+    }
+
+    /// Accept a single HTTP request and hand back its body
+    async fn http_stand_in() -> (String, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        socket
+                            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                            .await
+                            .unwrap();
+                        tx.send(body.to_string()).unwrap();
+                        break;
+                    }
+                }
+            }
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn test_slack_payload() {
+        let payload = endpoint(EndpointKind::Slack, "")
+            .payload(&finished())
+            .unwrap();
+        assert_eq!(
+            payload,
+            json!({"text": "*Basecall finished: PAQ123*\nBasecall run sup_v5 of project PAQ123 completed successfully"})
+        );
+    }
+
+    #[test]
+    fn test_ntfy_payload() {
+        let mut ntfy = endpoint(EndpointKind::Ntfy, "");
+        ntfy.topic = Some("sequencing".to_string());
+        let payload = ntfy.payload(&finished()).unwrap();
+        assert_eq!(payload["topic"], "sequencing");
+        assert_eq!(payload["title"], "Basecall finished: PAQ123");
+    }
+
+    #[test]
+    fn test_template_payload_escapes_values() {
+        let mut hook = endpoint(EndpointKind::Webhook, "");
+        hook.template = Some(r#"{"summary": "{{event}}: {{reason}}"}"#.to_string());
+        let failed = Notification::BasecallFailed {
+            project: "PAQ123".to_string(),
+            run: "hac".to_string(),
+            reason: "exit \"137\"".to_string(),
+        };
+        assert_eq!(
+            hook.payload(&failed).unwrap(),
+            json!({"summary": "basecall_failed: exit \"137\""})
+        );
+    }
+
+    #[test]
+    fn test_job_payload() {
+        let failed = Notification::JobFailed {
+            project: "PAQ123".to_string(),
+            job: "Conversion to POD5".to_string(),
+            run: "PAQ123-pod5".to_string(),
+            reason: "Read counts differ: 10 in FAST5, 4 in POD5".to_string(),
+        };
+        let payload = endpoint(EndpointKind::Webhook, "")
+            .payload(&failed)
+            .unwrap();
+        assert_eq!(payload["event"], "job_failed");
+        assert_eq!(payload["title"], "Conversion to POD5 failed: PAQ123");
+        assert_eq!(payload["job"], "Conversion to POD5");
+    }
+
+    #[test]
+    fn test_template_values_are_not_expanded() {
+        let mut hook = endpoint(EndpointKind::Webhook, "");
+        hook.template = Some(r#"{"text": "{{reason}} in {{project}} {{unknown}}"}"#.to_string());
+        let failed = Notification::BasecallFailed {
+            project: "PAQ123".to_string(),
+            run: "hac".to_string(),
+            reason: "bad read {{project}}".to_string(),
+        };
+        assert_eq!(
+            hook.payload(&failed).unwrap(),
+            json!({"text": "bad read {{project}} in PAQ123 {{unknown}}"})
+        );
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        let mut hook = endpoint(EndpointKind::Webhook, "");
+        hook.template = Some("{not json".to_string());
+        assert!(hook.payload(&finished()).is_err());
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let mut hook = endpoint(EndpointKind::Webhook, "");
+        assert!(hook.wants(NotificationKind::DiskSpaceLow));
+        hook.events = vec![NotificationKind::BasecallFailed];
+        assert!(!hook.wants(NotificationKind::DiskSpaceLow));
+        assert!(hook.wants(NotificationKind::BasecallFailed));
+    }
+
+    #[test]
+    fn test_disk_space_threshold_crossing() {
+        let watch = DiskSpaceWatch {
+            path: PathBuf::from("/data"),
+            min_free_gb: 1,
+        };
+        let mut monitor = DiskSpaceMonitor::new(&NotificationConfig {
+            disk_space: vec![watch.clone()],
+            ..Default::default()
+        });
+        let gb = 1024 * 1024 * 1024;
+        assert_eq!(monitor.observe(&watch, 2 * gb), None);
+        assert!(monitor.observe(&watch, gb / 2).is_some());
+        // Still below, so don't notify again
+        assert_eq!(monitor.observe(&watch, gb / 4), None);
+        assert_eq!(monitor.observe(&watch, 2 * gb), None);
+        assert!(monitor.observe(&watch, gb / 2).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook() {
+        let (url, body) = http_stand_in().await;
+        let notifier = Notifier::new(&NotificationConfig {
+            endpoints: vec![endpoint(EndpointKind::Webhook, &url)],
+            ..Default::default()
+        });
+        notifier.send(&finished()).await;
+        let body: Value = serde_json::from_str(&body.await.unwrap()).unwrap();
+        assert_eq!(body["event"], "basecall_finished");
+        assert_eq!(body["project"], "PAQ123");
+        assert_eq!(body["run"], "sup_v5");
+    }
+}
@@ -0,0 +1,432 @@
+//! CPU, memory, disk and per-job process usage, read from Linux's `/proc`
+//!
+//! Counters in `/proc` only ever grow, so each sample is the difference from the one before it.
+//! On systems without `/proc` sampling fails and the monitor says so rather than showing zeros.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::storage::Volume;
+
+/// `/proc/diskstats` counts in 512 byte sectors whatever the device's own sector size
+const SECTOR_SIZE: u64 = 512;
+
+/// Resource monitor settings, read from the `monitor` section of the config file
+#[derive(Clone, Debug, Deserialize)]
+pub struct MonitorConfig {
+    /// Seconds between samples
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    /// How far back the sparklines go
+    #[serde(default = "default_history_minutes")]
+    pub history_minutes: u64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: default_interval_seconds(),
+            history_minutes: default_history_minutes(),
+        }
+    }
+}
+
+fn default_interval_seconds() -> u64 {
+    2
+}
+
+fn default_history_minutes() -> u64 {
+    10
+}
+
+impl MonitorConfig {
+    /// Samples kept to cover `history_minutes`
+    pub fn history_len(&self) -> usize {
+        (self.history_minutes * 60 / self.interval_seconds.max(1)).max(1) as usize
+    }
+}
+
+/// Usage over the interval since the previous sample
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSample {
+    /// Busy share of all CPUs
+    pub cpu_percent: f64,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// Throughput of the devices holding the configured volumes, in their order
+    pub volumes: Vec<VolumeIo>,
+    /// Running jobs whose processes could be found, in the order they were asked for
+    pub jobs: Vec<JobUsage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VolumeIo {
+    pub name: String,
+    pub read_per_second: u64,
+    pub write_per_second: u64,
+}
+
+/// Usage of a job's process and everything it started
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobUsage {
+    pub id: usize,
+    pub name: String,
+    pub processes: usize,
+    /// Share of one CPU, so a job keeping four cores busy is at 400%
+    pub cpu_percent: f64,
+    /// Resident memory
+    pub memory: u64,
+}
+
+/// A process's parent and counters from `/proc/<pid>/stat`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ProcessStat {
+    parent: u32,
+    /// User and system time, in clock ticks
+    cpu_ticks: u64,
+    /// Resident set size, in pages
+    resident_pages: u64,
+}
+
+/// Raw counters from one read of `/proc`
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    /// Busy and total jiffies across all CPUs
+    cpu: (u64, u64),
+    /// Bytes read and written by each device, by major and minor number
+    disks: HashMap<(u32, u32), (u64, u64)>,
+    processes: HashMap<u32, ProcessStat>,
+}
+
+/// Takes a sample every `interval_seconds`, keeping the counters to diff the next one against
+pub struct ResourceSampler {
+    volumes: Vec<Volume>,
+    interval: Duration,
+    previous: Option<(Instant, Counters)>,
+}
+
+impl ResourceSampler {
+    pub fn new(config: &MonitorConfig, volumes: Vec<Volume>) -> Self {
+        Self {
+            volumes,
+            interval: Duration::from_secs(config.interval_seconds.max(1)),
+            previous: None,
+        }
+    }
+
+    /// A new sample once the interval has passed, `jobs` being the id, name and process id of
+    /// each running job
+    ///
+    /// The first call only records counters, there's nothing to compare them with yet.
+    pub fn poll(&mut self, jobs: &[(usize, String, u32)]) -> Option<io::Result<ResourceSample>> {
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|(at, _)| at.elapsed() < self.interval)
+        {
+            return None;
+        }
+        let counters = match read_counters() {
+            Ok(counters) => counters,
+            Err(e) => {
+                self.previous = Some((Instant::now(), Counters::default()));
+                return Some(Err(e));
+            }
+        };
+        let now = Instant::now();
+        let sample = self
+            .previous
+            .take()
+            .filter(|(_, previous)| previous.cpu.1 > 0)
+            .map(|(at, previous)| {
+                self.sample(&previous, &counters, (now - at).as_secs_f64(), jobs)
+            });
+        self.previous = Some((now, counters));
+        sample.map(Ok)
+    }
+
+    fn sample(
+        &self,
+        previous: &Counters,
+        counters: &Counters,
+        seconds: f64,
+        jobs: &[(usize, String, u32)],
+    ) -> ResourceSample {
+        let (memory_total, memory_available) = std::fs::read_to_string("/proc/meminfo")
+            .map(|meminfo| parse_meminfo(&meminfo))
+            .unwrap_or_default();
+        let busy = counters.cpu.0.saturating_sub(previous.cpu.0);
+        let total = counters.cpu.1.saturating_sub(previous.cpu.1);
+        let per_second = |bytes: u64| (bytes as f64 / seconds.max(0.001)) as u64;
+
+        let volumes = self
+            .volumes
+            .iter()
+            .filter_map(|volume| {
+                let device = device_of(&volume.path).ok()?;
+                let (read, written) = counters.disks.get(&device)?;
+                let (read_before, written_before) = previous
+                    .disks
+                    .get(&device)
+                    .copied()
+                    .unwrap_or((*read, *written));
+                Some(VolumeIo {
+                    name: volume.name.clone(),
+                    read_per_second: per_second(read.saturating_sub(read_before)),
+                    write_per_second: per_second(written.saturating_sub(written_before)),
+                })
+            })
+            .collect();
+
+        let ticks_per_second = clock_ticks_per_second() as f64;
+        let page_size = page_size();
+        let jobs = jobs
+            .iter()
+            .filter(|(_, _, pid)| counters.processes.contains_key(pid))
+            .map(|(id, name, pid)| {
+                let tree = process_tree(&counters.processes, *pid);
+                let ticks = tree
+                    .iter()
+                    .map(|pid| {
+                        let now = counters.processes[pid].cpu_ticks;
+                        // A process started since the last sample used all its time within it
+                        let before = previous.processes.get(pid).map_or(0, |stat| stat.cpu_ticks);
+                        now.saturating_sub(before)
+                    })
+                    .sum::<u64>();
+                JobUsage {
+                    id: *id,
+                    name: name.clone(),
+                    processes: tree.len(),
+                    cpu_percent: ticks as f64 / ticks_per_second / seconds.max(0.001) * 100.0,
+                    memory: tree
+                        .iter()
+                        .map(|pid| counters.processes[pid].resident_pages * page_size)
+                        .sum(),
+                }
+            })
+            .collect();
+
+        ResourceSample {
+            cpu_percent: if total == 0 {
+                0.0
+            } else {
+                busy as f64 * 100.0 / total as f64
+            },
+            memory_used: memory_total.saturating_sub(memory_available),
+            memory_total,
+            volumes,
+            jobs,
+        }
+    }
+}
+
+fn read_counters() -> io::Result<Counters> {
+    let stat = std::fs::read_to_string("/proc/stat")?;
+    let cpu = parse_cpu(&stat)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no cpu line in /proc/stat"))?;
+    let disks = parse_diskstats(&std::fs::read_to_string("/proc/diskstats")?);
+    let mut processes = HashMap::new();
+    for entry in std::fs::read_dir("/proc")? {
+        let entry = entry?;
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        // Processes can exit between listing and reading
+        if let Some(stat) = std::fs::read_to_string(entry.path().join("stat"))
+            .ok()
+            .and_then(|stat| parse_process_stat(&stat))
+        {
+            processes.insert(pid, stat);
+        }
+    }
+    Ok(Counters {
+        cpu,
+        disks,
+        processes,
+    })
+}
+
+/// Busy and total jiffies from the first, all-CPU line of `/proc/stat`
+fn parse_cpu(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let times = line
+        .split_whitespace()
+        .skip(1)
+        .map(|field| field.parse::<u64>().unwrap_or(0))
+        .collect::<Vec<_>>();
+    // Guest time is already counted in user time
+    let total = times.iter().take(8).sum::<u64>();
+    // idle and iowait
+    let idle = times.get(3).copied().unwrap_or(0) + times.get(4).copied().unwrap_or(0);
+    Some((total.saturating_sub(idle), total))
+}
+
+/// Total and available memory in bytes from `/proc/meminfo`
+fn parse_meminfo(meminfo: &str) -> (u64, u64) {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map_or(0, |kb| kb * 1024)
+    };
+    (field("MemTotal:"), field("MemAvailable:"))
+}
+
+/// Bytes read and written by each device in `/proc/diskstats`
+fn parse_diskstats(diskstats: &str) -> HashMap<(u32, u32), (u64, u64)> {
+    diskstats
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let number = |i: usize| fields.get(i)?.parse::<u64>().ok();
+            Some((
+                (number(0)? as u32, number(1)? as u32),
+                (number(5)? * SECTOR_SIZE, number(9)? * SECTOR_SIZE),
+            ))
+        })
+        .collect()
+}
+
+/// Parent, CPU time and resident size from `/proc/<pid>/stat`
+fn parse_process_stat(stat: &str) -> Option<ProcessStat> {
+    // The command name is in parentheses and may contain spaces or parentheses itself
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields = rest.split_whitespace().collect::<Vec<_>>();
+    let number = |i: usize| fields.get(i)?.parse::<u64>().ok();
+    Some(ProcessStat {
+        parent: number(1)? as u32,
+        cpu_ticks: number(11)? + number(12)?,
+        resident_pages: number(21)?,
+    })
+}
+
+/// `root` and all its descendants
+fn process_tree(processes: &HashMap<u32, ProcessStat>, root: u32) -> Vec<u32> {
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(
+            processes
+                .iter()
+                .filter(|(_, stat)| stat.parent == parent)
+                .map(|(pid, _)| *pid),
+        );
+        i += 1;
+    }
+    tree
+}
+
+/// Major and minor number of the device holding `path`, as listed in `/proc/diskstats`
+#[cfg(unix)]
+fn device_of(path: &Path) -> io::Result<(u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = std::fs::metadata(path)?.dev();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    Ok((major as u32, minor as u32))
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> io::Result<(u32, u32)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "device numbers are only supported on unix",
+    ))
+}
+
+#[cfg(unix)]
+fn clock_ticks_per_second() -> u64 {
+    // SAFETY: sysconf only reads a system setting
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 {
+        ticks as u64
+    } else {
+        100
+    }
+}
+
+#[cfg(not(unix))]
+fn clock_ticks_per_second() -> u64 {
+    100
+}
+
+#[cfg(unix)]
+fn page_size() -> u64 {
+    // SAFETY: sysconf only reads a system setting
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as u64
+    } else {
+        4096
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> u64 {
+    4096
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_files() {
+        let stat = "cpu  100 5 50 800 40 3 2 0 10 0\ncpu0 50 2 25 400 20 1 1 0 5 0\n";
+        assert_eq!(parse_cpu(stat), Some((160, 1000)));
+
+        let meminfo =
+            "MemTotal:       16384 kB\nMemFree:         1024 kB\nMemAvailable:    4096 kB\n";
+        assert_eq!(parse_meminfo(meminfo), (16384 * 1024, 4096 * 1024));
+
+        let diskstats = "   8       0 sda 100 0 2000 50 30 0 800 20 0 60 70\n 259       1 nvme0n1p1 5 0 8 1 2 0 16 1 0 2 2\n";
+        let disks = parse_diskstats(diskstats);
+        assert_eq!(disks[&(8, 0)], (2000 * 512, 800 * 512));
+        assert_eq!(disks[&(259, 1)], (8 * 512, 16 * 512));
+
+        let process = "4242 (dorado (basecaller)) S 4200 4242 4200 0 -1 4194304 10 0 0 0 700 300 0 0 20 0 12 0 5000 1000000 2500 18446744073709551615";
+        assert_eq!(
+            parse_process_stat(process),
+            Some(ProcessStat {
+                parent: 4200,
+                cpu_ticks: 1000,
+                resident_pages: 2500,
+            })
+        );
+    }
+
+    #[test]
+    fn test_process_tree() {
+        let process = |parent| ProcessStat {
+            parent,
+            ..Default::default()
+        };
+        let processes = HashMap::from([
+            (1, process(0)),
+            (10, process(1)),
+            (11, process(10)),
+            (12, process(11)),
+            (20, process(1)),
+        ]);
+        let mut tree = process_tree(&processes, 10);
+        tree.sort();
+        assert_eq!(tree, vec![10, 11, 12]);
+    }
+}
@@ -19,13 +19,71 @@ use futures::{FutureExt, StreamExt};
 use ratatui::backend::CrosstermBackend as Backend;
 use serde::{Deserialize, Serialize};
 use tokio::{
-    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        watch,
+    },
     task::JoinHandle,
     time::interval,
 };
 use tokio_util::sync::CancellationToken;
 use tracing::error;
 
+/// Display settings, read from the `display` section of the config file
+#[derive(Clone, Debug, Deserialize)]
+pub struct DisplayConfig {
+    /// Ticks per second, which drive background checks, unless `--tick-rate` is given
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: f64,
+    /// Frames per second, unless `--frame-rate` is given
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: f64,
+    /// Drop to `idle_frame_rate` while no keys are pressed and nothing on screen animates
+    #[serde(default)]
+    pub adaptive: bool,
+    #[serde(default = "default_idle_frame_rate")]
+    pub idle_frame_rate: f64,
+    /// Seconds without input before the frame rate drops
+    #[serde(default = "default_idle_after_seconds")]
+    pub idle_after_seconds: u64,
+    /// Capture the mouse to click and scroll panes, which stops the terminal selecting text
+    #[serde(default = "default_mouse")]
+    pub mouse: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: default_tick_rate(),
+            frame_rate: default_frame_rate(),
+            adaptive: false,
+            idle_frame_rate: default_idle_frame_rate(),
+            idle_after_seconds: default_idle_after_seconds(),
+            mouse: default_mouse(),
+        }
+    }
+}
+
+fn default_tick_rate() -> f64 {
+    4.0
+}
+
+fn default_frame_rate() -> f64 {
+    60.0
+}
+
+fn default_idle_frame_rate() -> f64 {
+    2.0
+}
+
+fn default_idle_after_seconds() -> u64 {
+    5
+}
+
+fn default_mouse() -> bool {
+    true
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
     Init,
@@ -49,6 +107,8 @@ pub struct Tui {
     pub event_rx: UnboundedReceiver<Event>,
     pub event_tx: UnboundedSender<Event>,
     pub frame_rate: f64,
+    /// Passes frame rate changes to the running event loop
+    pub frame_rate_tx: watch::Sender<f64>,
     pub tick_rate: f64,
     pub mouse: bool,
     pub paste: bool,
@@ -64,6 +124,7 @@ impl Tui {
             event_rx,
             event_tx,
             frame_rate: 60.0,
+            frame_rate_tx: watch::Sender::new(60.0),
             tick_rate: 4.0,
             mouse: false,
             paste: false,
@@ -76,10 +137,16 @@ impl Tui {
     }
 
     pub fn frame_rate(mut self, frame_rate: f64) -> Self {
-        self.frame_rate = frame_rate;
+        self.set_frame_rate(frame_rate);
         self
     }
 
+    /// Change the frame rate, taking effect immediately if the event loop is running
+    pub fn set_frame_rate(&mut self, frame_rate: f64) {
+        self.frame_rate = frame_rate;
+        self.frame_rate_tx.send_replace(frame_rate);
+    }
+
     pub fn mouse(mut self, mouse: bool) -> Self {
         self.mouse = mouse;
         self
@@ -97,7 +164,7 @@ impl Tui {
             self.event_tx.clone(),
             self.cancellation_token.clone(),
             self.tick_rate,
-            self.frame_rate,
+            self.frame_rate_tx.subscribe(),
         );
         self.task = tokio::spawn(async {
             event_loop.await;
@@ -108,10 +175,11 @@ impl Tui {
         event_tx: UnboundedSender<Event>,
         cancellation_token: CancellationToken,
         tick_rate: f64,
-        frame_rate: f64,
+        mut frame_rate_rx: watch::Receiver<f64>,
     ) {
         let mut event_stream = EventStream::new();
         let mut tick_interval = interval(Duration::from_secs_f64(1.0 / tick_rate));
+        let frame_rate = *frame_rate_rx.borrow_and_update();
         let mut render_interval = interval(Duration::from_secs_f64(1.0 / frame_rate));
 
         // if this fails, then it's likely a bug in the calling code
@@ -125,6 +193,11 @@ impl Tui {
                 }
                 _ = tick_interval.tick() => Event::Tick,
                 _ = render_interval.tick() => Event::Render,
+                Ok(()) = frame_rate_rx.changed() => {
+                    let frame_rate = *frame_rate_rx.borrow_and_update();
+                    render_interval = interval(Duration::from_secs_f64(1.0 / frame_rate));
+                    continue;
+                }
                 crossterm_event = event_stream.next().fuse() => match crossterm_event {
                     Some(Ok(event)) => match event {
                         CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => Event::Key(key),